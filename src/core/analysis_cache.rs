@@ -0,0 +1,315 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::PathBuf,
+    time::UNIX_EPOCH,
+};
+
+use log::warn;
+
+use crate::core::{
+    analyzer::{self, fold_bpm, PreviewSample, ANALYZER_VERSION},
+    config::{self, Config},
+};
+
+//------------------------------------------------------------------//
+//                          AnalysisCache                           //
+//------------------------------------------------------------------//
+
+/// Identifies cache entries. Entries without it were written before the format was versioned
+const MAGIC: &[u8; 4] = b"FLWC";
+
+/// Version of the layout of cache entries. Entries of other versions are not read
+const FORMAT_VERSION: u32 = 1;
+
+/// Number of bytes before the payload: magic, format version, analyzer version and payload
+/// length
+const HEADER_LEN: usize = 4 + 4 + 4 + 8;
+
+/// Results of a finished analysis. They are kept in `<data dir>/cache` and reused, as long as
+/// neither the file, the analysis settings nor the analyzer changed.
+///
+/// An entry consists of a header, the payload and a FNV-1a checksum of the payload. All numbers
+/// are little endian.
+pub struct CachedAnalysis {
+    pub bpm: u32,
+    /// results of analyzer plugins, keyed by `<plugin>.<key>`
+    pub extra: BTreeMap<String, String>,
+    pub preview: Vec<PreviewSample>,
+}
+
+/// returns the cached analysis of a file, if it is still valid. Entries of older analyzer
+/// versions, from before the format was versioned or stored under an unstable name are
+/// migrated, if their results can be converted
+pub fn load(file_path: &str, config: &Config) -> Option<CachedAnalysis> {
+    let (content, legacy) = match fs::read(cache_file(file_path)?) {
+        Ok(content) => (content, None),
+        Err(_) => {
+            let legacy = legacy_cache_file(file_path)?;
+            (fs::read(&legacy).ok()?, Some(legacy))
+        }
+    };
+    let (analyzer_version, payload) = if content.starts_with(MAGIC) {
+        let mut reader = Reader(&content[MAGIC.len()..]);
+        if reader.u32()? != FORMAT_VERSION {
+            return None;
+        }
+        let analyzer_version = reader.u32()?;
+        let len = usize::try_from(reader.u64()?).ok()?;
+        let payload = reader.take(len)?;
+        if reader.u64()? != checksum(payload) {
+            warn!("discarding the corrupt cached analysis of {}", file_path);
+            return None;
+        }
+        (analyzer_version, payload)
+    } else {
+        // the unversioned layout equals the payload of the first analyzer version
+        (1, &content[..])
+    };
+    if analyzer_version == ANALYZER_VERSION && legacy.is_none() {
+        return parse_payload(payload, &stamp(file_path, config)?);
+    }
+    let analysis = migrate(analyzer_version, payload, file_path, config)?;
+    let mut migrated = store(file_path, config, &analysis);
+    if let (Ok(()), Some(legacy)) = (&migrated, legacy) {
+        migrated = fs::remove_file(legacy);
+    }
+    if let Err(err) = migrated {
+        warn!(
+            "failed to migrate the cached analysis of {}: {}",
+            file_path, err
+        );
+    }
+    Some(analysis)
+}
+
+/// converts the payload of an entry, that was written by the given analyzer version, into the
+/// results of the current one. Returns None, if they can't be converted, so the file gets
+/// analyzed again
+fn migrate(
+    analyzer_version: u32,
+    payload: &[u8],
+    file_path: &str,
+    config: &Config,
+) -> Option<CachedAnalysis> {
+    match analyzer_version {
+        ANALYZER_VERSION => parse_payload(payload, &stamp(file_path, config)?),
+        // the first version detected the tempo on the whole track and didn't fold it into one
+        // octave. Its settings only covered the preview and no plugins
+        1 if config.bpm_skip_intro == 0.
+            && config.bpm_skip_outro == 0.
+            && config.bpm_window == 0.
+            && analyzer::plugin_names().is_empty() =>
+        {
+            let mut stamp = file_stamp(file_path)?;
+            stamp.extend(preview_settings(config));
+            let mut analysis = parse_payload(payload, &stamp)?;
+            analysis.bpm = fold_bpm(analysis.bpm, config.bpm_min);
+            Some(analysis)
+        }
+        _ => None,
+    }
+}
+
+/// parses the payload of a cache entry, if its stamp matches
+fn parse_payload(payload: &[u8], stamp: &[u8]) -> Option<CachedAnalysis> {
+    let mut reader = Reader(payload);
+    if reader.bytes()? != stamp {
+        return None;
+    }
+    CachedAnalysis::from_bytes(reader.0)
+}
+
+impl CachedAnalysis {
+    /// encodes the results without any stamp
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(self.bpm.to_le_bytes());
+        bytes.extend((self.extra.len() as u32).to_le_bytes());
+        for (key, value) in self.extra.iter() {
+            push_bytes(&mut bytes, key.as_bytes());
+            push_bytes(&mut bytes, value.as_bytes());
+        }
+        for sample in self.preview.iter() {
+            bytes.extend(sample.lows.to_le_bytes());
+            bytes.extend(sample.mids.to_le_bytes());
+            bytes.extend(sample.highs.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// decodes results, that were encoded by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        let bpm = reader.u32()?;
+        let mut extra = BTreeMap::new();
+        for _ in 0..reader.u32()? {
+            let key = reader.string()?;
+            extra.insert(key, reader.string()?);
+        }
+        let mut preview = Vec::with_capacity(reader.0.len() / 12);
+        while !reader.0.is_empty() {
+            let lows = reader.f32()?;
+            let mids = reader.f32()?;
+            let highs = reader.f32()?;
+            preview.push(PreviewSample { lows, mids, highs });
+        }
+        Some(CachedAnalysis {
+            bpm,
+            extra,
+            preview,
+        })
+    }
+}
+
+/// stores the analysis of a file
+pub fn store(file_path: &str, config: &Config, analysis: &CachedAnalysis) -> io::Result<()> {
+    let (path, stamp) = match (cache_file(file_path), stamp(file_path, config)) {
+        (Some(path), Some(stamp)) => (path, stamp),
+        _ => return Ok(()),
+    };
+    let mut payload = vec![];
+    push_bytes(&mut payload, &stamp);
+    payload.extend(analysis.to_bytes());
+    let mut content = Vec::with_capacity(HEADER_LEN + payload.len() + 8);
+    content.extend(MAGIC);
+    content.extend(FORMAT_VERSION.to_le_bytes());
+    content.extend(ANALYZER_VERSION.to_le_bytes());
+    content.extend((payload.len() as u64).to_le_bytes());
+    content.extend(&payload);
+    content.extend(checksum(&payload).to_le_bytes());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // never leave a half written entry behind
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(tmp, path)
+}
+
+/// computes the 64 bit FNV-1a hash of some bytes. Unlike the std hashers, its result is stable
+/// between builds
+pub fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// removes the cached analysis of a file, so it is analyzed again on the next scan
+pub fn remove(file_path: &str) -> io::Result<()> {
+    match cache_file(file_path) {
+        Some(path) if path.exists() => fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
+/// returns the path of the cache entry for a file. Its name is the checksum of the path, so it
+/// stays the same between builds
+fn cache_file(file_path: &str) -> Option<PathBuf> {
+    let name = format!("{:016x}", checksum(file_path.as_bytes()));
+    config::data_dir().map(|dir| dir.join("cache").join(name))
+}
+
+/// returns the path, that the cache entry for a file had, while it was named by the std hasher.
+/// That name changes between Rust releases, so it is only looked at for migration
+fn legacy_cache_file(file_path: &str) -> Option<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    file_path.hash(&mut hasher);
+    config::data_dir().map(|dir| dir.join("cache").join(format!("{:016x}", hasher.finish())))
+}
+
+/// identifies the state of a file and the settings, that it was analyzed with. A cache entry is
+/// only valid, if its stamp matches
+fn stamp(file_path: &str, config: &Config) -> Option<Vec<u8>> {
+    let mut stamp = file_stamp(file_path)?;
+    stamp.extend(settings(config));
+    Some(stamp)
+}
+
+/// identifies the state of a file by its path, size and modification time
+fn file_stamp(file_path: &str) -> Option<Vec<u8>> {
+    let metadata = fs::metadata(file_path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    let mut stamp = vec![];
+    push_bytes(&mut stamp, file_path.as_bytes());
+    stamp.extend(metadata.len().to_le_bytes());
+    stamp.extend(modified.as_nanos().to_le_bytes());
+    Some(stamp)
+}
+
+/// identifies the analysis settings and the registered plugins. Results are only comparable, if
+/// they were analyzed with the same settings and plugins
+pub fn settings(config: &Config) -> Vec<u8> {
+    let mut settings = preview_settings(config);
+    for name in analyzer::plugin_names() {
+        push_bytes(&mut settings, name.as_bytes());
+    }
+    settings.extend(config.bpm_min.to_le_bytes());
+    for seconds in [
+        config.bpm_skip_intro,
+        config.bpm_skip_outro,
+        config.bpm_window,
+    ] {
+        settings.extend(seconds.to_le_bytes());
+    }
+    settings
+}
+
+/// identifies the settings of the preview, which were the only analysis settings of the first
+/// analyzer version
+fn preview_settings(config: &Config) -> Vec<u8> {
+    let mut settings = vec![];
+    settings.extend(config.preview_sample_rate.to_le_bytes());
+    for crossover in [
+        config.crossover_low,
+        config.crossover_mid_low,
+        config.crossover_mid_high,
+        config.crossover_high,
+    ] {
+        settings.extend(crossover.to_le_bytes());
+    }
+    settings
+}
+
+/// appends a length prefixed byte string
+pub fn push_bytes(content: &mut Vec<u8>, bytes: &[u8]) {
+    content.extend((bytes.len() as u32).to_le_bytes());
+    content.extend(bytes);
+}
+
+/// reads little endian values from the front of a byte slice
+pub struct Reader<'a>(pub &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Some(head)
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    pub fn string(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?.to_vec()).ok()
+    }
+}
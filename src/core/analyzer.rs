@@ -1,10 +1,15 @@
+use crate::core::analysis_cache::{self, CachedAnalysis};
 use crate::core::analyzer;
+use crate::core::bpm_window;
+use crate::core::config::Config;
+use crate::core::probe;
 use crate::view::model;
 use samplerate::{ConverterType, Samplerate};
 use std::{
+    fmt,
     iter::Sum,
     ops::Range,
-    sync::Arc,
+    sync::{Arc, Mutex},
     thread::{spawn, JoinHandle},
 };
 use synthrs::filter::{bandpass_filter, convolve, cutoff_from_frequency, lowpass_filter};
@@ -28,10 +33,44 @@ use symphonia::core::{
 //------------------------------------------------------------------//
 //                             Analyzer                             //
 //------------------------------------------------------------------//
-/// Determines the number of samples in the preview buffer per packet of the original source.
-/// Should be a multiple of number of channels
+/// Default number of preview samples per second of the original source. Can be changed with the
+/// `preview_sample_rate` setting
 pub const PREVIEW_SAMPLE_RATE: u32 = 2205;
 
+/// Version of the analysis results. Has to be increased, whenever a change of the analyzer
+/// changes its results, so cached results of older versions are not used anymore
+pub const ANALYZER_VERSION: u32 = 2;
+
+/// doubles or halves a detected tempo, until it lies between `min` and twice `min`. Beat
+/// trackers often lock onto half or double the tempo, that a track is mixed at
+pub fn fold_bpm(mut bpm: u32, min: u32) -> u32 {
+    if bpm == 0 {
+        return 0;
+    }
+    while bpm < min {
+        bpm *= 2;
+    }
+    while bpm >= min * 2 {
+        bpm /= 2;
+    }
+    bpm
+}
+
+/// key of the spectral balance within the analysis results
+pub const BALANCE_KEY: &str = "spectrum.balance";
+
+/// returns the spectral balance of a track, from -1 if it only has lows to 1 if it only has
+/// highs
+pub fn spectral_balance(preview: &[PreviewSample]) -> f32 {
+    let sum: PreviewSample = preview.iter().copied().sum();
+    let total = sum.lows + sum.highs;
+    if total > 0. {
+        (sum.highs - sum.lows) / total
+    } else {
+        0.
+    }
+}
+
 /// This is a mono-summed, downsampled version of a number of decoded samples
 #[derive(Copy, Clone, Debug)]
 pub struct PreviewSample {
@@ -61,6 +100,54 @@ pub enum AnalyzerError {
     NoTrackFound,
 }
 
+impl fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzerError::ReaderError => write!(f, "file could not be read"),
+            AnalyzerError::UnsupportedFormat => write!(f, "unsupported or encrypted format"),
+            AnalyzerError::NoTrackFound => write!(f, "no audio track found"),
+        }
+    }
+}
+
+//------------------------------------------------------------------//
+//                          AnalyzerPlugin                          //
+//------------------------------------------------------------------//
+
+/// An additional analysis pass, that runs on every analyzed track. Every analyzer thread creates
+/// its own plugin instances, so plugins can keep per-track state.
+pub trait AnalyzerPlugin: Send {
+    /// name of the plugin, which prefixes the keys of its results
+    fn name(&self) -> &str;
+    /// called once before the first packet
+    fn init(&mut self, _codec_params: &CodecParameters) {}
+    /// receives the decoded samples of every packet in interleaved form
+    fn process(&mut self, samples: &[f32]);
+    /// called after the last packet. The returned key value pairs are stored in the track's
+    /// meta data as `<name>.<key>`
+    fn finish(&mut self) -> Vec<(String, String)>;
+}
+
+/// creates a fresh plugin instance for a track
+pub type PluginFactory = fn() -> Box<dyn AnalyzerPlugin>;
+
+/// registered plugins, that get instantiated for every analyzed track
+static PLUGINS: Mutex<Vec<PluginFactory>> = Mutex::new(Vec::new());
+
+/// registers an analysis pass for all tracks, that are analyzed afterwards. Cached results
+/// don't include it, so cached tracks are analyzed again
+pub fn register_plugin(factory: PluginFactory) {
+    PLUGINS.lock().unwrap().push(factory);
+}
+
+/// returns the names of the registered plugins in the order of their registration
+pub fn plugin_names() -> Vec<String> {
+    PLUGINS.lock().unwrap().iter().map(|factory| String::from(factory().name())).collect()
+}
+
+/// a format reader with the tags, that were found while probing its file
+type ProbedReader = (Box<dyn FormatReader>, Option<Vec<Tag>>);
+
 pub enum Event {
     /// This event fires, when a analyzer is done analyzing
     DoneAnalyzing(String),
@@ -74,6 +161,8 @@ pub struct Analyzer {
     track: Arc<model::track::Track>,
     /// Codec Parameters
     codec_params: CodecParameters,
+    /// id of the analyzed track within the container
+    track_id: u32,
     /// FormatReader
     reader: Box<dyn FormatReader>,
     /// Decoder
@@ -87,12 +176,50 @@ pub struct Analyzer {
     mids_moving_avg_filter: SMA,
     highs_moving_avg_filter: SMA,
     peak_intersample_filter: PeakIntersampleFilter,
+    /// additional analysis passes
+    plugins: Vec<Box<dyn AnalyzerPlugin>>,
+    /// user settings for the preview resolution and band splitting
+    config: Config,
+    /// part of the track in seconds, that its BPM is detected from instead of the configured
+    /// window
+    bpm_window: Option<(f64, f64)>,
 }
 
 impl Analyzer {
-    pub fn spawn(file_path: String, analyzer_event_out: Sender<analyzer::Event>) -> JoinHandle<()> {
+    /// analyzes a file in a new thread. Its BPM is detected from `bpm_window`, if the track has
+    /// a window of its own
+    pub fn spawn(
+        file_path: String,
+        config: Config,
+        bpm_window: Option<(f64, f64)>,
+        analyzer_event_out: Sender<analyzer::Event>,
+    ) -> JoinHandle<()> {
         spawn(move || {
-            let mut analyzer = Analyzer::new(file_path.clone(), analyzer_event_out);
+            let mut analyzer = match Analyzer::new(
+                file_path.clone(),
+                config,
+                bpm_window,
+                analyzer_event_out.clone(),
+            ) {
+                Ok(analyzer) => analyzer,
+                Err(err) => {
+                    // list the file anyway, so it can be marked as unsupported
+                    let track = model::track::Track::unsupported(file_path, err.to_string());
+                    analyzer_event_out
+                        .send(Event::NewTrack(Arc::new(track)))
+                        .unwrap();
+                    return;
+                }
+            };
+            // reuse the results of an earlier scan, if the file did not change since
+            if let Some(cached) = analysis_cache::load(&file_path, &analyzer.config) {
+                analyzer.restore(cached);
+                analyzer
+                    .analyzer_event_out
+                    .send(analyzer::Event::DoneAnalyzing(file_path))
+                    .unwrap();
+                return;
+            }
             // messages
             loop {
                 match analyzer.decode() {
@@ -102,11 +229,14 @@ impl Analyzer {
                     Err(_) => {
                         // Error decoding
                         // this means the stream is done?
+                        analyzer.analyze_bpm(150..200);
+                        analyzer.finish_plugins();
+                        analyzer.finish_balance();
+                        analyzer.store();
                         analyzer
                             .analyzer_event_out
                             .send(analyzer::Event::DoneAnalyzing(file_path))
                             .unwrap();
-                        analyzer.analyze_bpm(150..200);
                         break;
                     }
                 }
@@ -114,23 +244,40 @@ impl Analyzer {
         })
     }
 
-    fn new(file_path: String, analyzer_event_out: Sender<analyzer::Event>) -> Self {
-        let reader_and_tags = Analyzer::get_reader(file_path.clone());
+    fn new(
+        file_path: String,
+        config: Config,
+        bpm_window: Option<(f64, f64)>,
+        analyzer_event_out: Sender<analyzer::Event>,
+    ) -> Result<Self, AnalyzerError> {
+        let reader_and_tags = Analyzer::get_reader(file_path.clone())?;
         let mut reader = reader_and_tags.0;
         let tags = reader_and_tags.1;
-        let default_track = reader.default_track().unwrap().clone();
-        let decoder = Analyzer::get_decoder(&default_track.codec_params).unwrap();
-        let track = Arc::new(model::track::Track::new(
-            file_path,
-            default_track.codec_params.clone(),
-        ));
+        let default_track = probe::default_audio_track(reader.as_ref())
+            .ok_or(AnalyzerError::NoTrackFound)?
+            .clone();
+        let decoder = Analyzer::get_decoder(&default_track.codec_params)?;
+        let format = probe::FormatInfo::new(&file_path, &default_track);
+        let mut track = model::track::Track::new(file_path, default_track.codec_params.clone());
+        track.format = Some(format);
+        track.preview_sample_rate = config.preview_sample_rate;
+        track.audio_track_ids = probe::audio_tracks(reader.as_ref())
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        let track = Arc::new(track);
         if let Some(tags) = tags{
             track.meta.write().unwrap().parse_from(tags);
         }
         analyzer_event_out
             .send(Event::NewTrack(Arc::clone(&track)))
             .unwrap();
-        Self {
+        let mut plugins: Vec<Box<dyn AnalyzerPlugin>> =
+            PLUGINS.lock().unwrap().iter().map(|factory| factory()).collect();
+        for plugin in plugins.iter_mut() {
+            plugin.init(&default_track.codec_params);
+        }
+        Ok(Self {
             reader,
             decoder,
             sample_buf: vec![],
@@ -141,40 +288,50 @@ impl Analyzer {
             mids_moving_avg_filter: SMA::new(50, &0.).unwrap(),
             highs_moving_avg_filter: SMA::new(3, &0.).unwrap(),
             peak_intersample_filter: PeakIntersampleFilter::new(),
+            plugins,
+            config,
+            bpm_window,
+            track_id: default_track.id,
             codec_params: default_track.codec_params,
-        }
+        })
     }
 
     /// returns a sample buffer, that contains one packet of samples in decoded, interleaved form
     fn decode(&mut self) -> Result<SampleBuffer<f32>, Error> {
-        let packet = self.reader.next_packet()?;
-        match self.decoder.decode(&packet) {
-            Ok(decoded) => {
-                // Get the audio buffer specification. This is a description of the decoded
-                // audio buffer's sample format and sample rate.
-                let spec = *decoded.spec();
-
-                // Get the capacity of the decoded buffer. Note that this is capacity, not
-                // length! The capacity of the decoded buffer is constant for the life of the
-                // decoder, but the length is not.
-                let duration = decoded.capacity() as u64;
-                let mut sample_buf = SampleBuffer::<f32>::new(duration, spec);
-                // store sample data in interleaved format
-                sample_buf.copy_interleaved_ref(decoded.clone());
-                Ok(sample_buf)
+        loop {
+            let packet = self.reader.next_packet()?;
+            // skip packets of other streams in the container
+            if packet.track_id() != self.track_id {
+                continue;
             }
-            Err(err) => {
-                // Decode errors are not fatal. Print the error message and try to decode the next
-                // packet as usual.
-                warn!("decode error: {}", err);
-                panic!("error")
+            match self.decoder.decode(&packet) {
+                Ok(decoded) => {
+                    // Get the audio buffer specification. This is a description of the decoded
+                    // audio buffer's sample format and sample rate.
+                    let spec = *decoded.spec();
+
+                    // Get the capacity of the decoded buffer. Note that this is capacity, not
+                    // length! The capacity of the decoded buffer is constant for the life of the
+                    // decoder, but the length is not.
+                    let duration = decoded.capacity() as u64;
+                    let mut sample_buf = SampleBuffer::<f32>::new(duration, spec);
+                    // store sample data in interleaved format
+                    sample_buf.copy_interleaved_ref(decoded.clone());
+                    return Ok(sample_buf);
+                }
+                Err(Error::DecodeError(err)) => {
+                    // Decode errors are not fatal. Print the error message and try to decode the
+                    // next packet as usual.
+                    warn!("decode error: {}", err);
+                }
+                Err(err) => return Err(err),
             }
         }
     }
 
     /// creates reader from a given path
-    fn get_reader(path: String) -> (Box<dyn FormatReader>, Option<Vec<Tag>>) {
-        let src = std::fs::File::open(path).expect("failed to open media");
+    fn get_reader(path: String) -> Result<ProbedReader, AnalyzerError> {
+        let src = std::fs::File::open(path).map_err(|_| AnalyzerError::ReaderError)?;
         let mss = MediaSourceStream::new(Box::new(src), Default::default());
         let mut hint = Hint::new();
         hint.with_extension("mp3");
@@ -182,13 +339,13 @@ impl Analyzer {
         let fmt_opts: FormatOptions = Default::default();
         let mut probed = symphonia::default::get_probe()
             .format(&hint, mss, &fmt_opts, &meta_opts)
-            .expect("unsupported format");
+            .map_err(|_| AnalyzerError::UnsupportedFormat)?;
         let mut tags = probed
             .metadata
             .get()
             .map(|meta| meta.current().map(|x| x.tags().to_vec()))
             .flatten();
-        (probed.format, tags)
+        Ok((probed.format, tags))
     }
 
     /// creates decoder from codec parameters
@@ -197,10 +354,9 @@ impl Analyzer {
             verify: false,
             ..Default::default()
         };
-        let decoder = symphonia::default::get_codecs()
+        symphonia::default::get_codecs()
             .make(&codec_params, &dec_opts)
-            .unwrap();
-        Ok(decoder)
+            .map_err(|_| AnalyzerError::UnsupportedFormat)
     }
 
     /// analyze a decoded packet
@@ -208,6 +364,9 @@ impl Analyzer {
         // this is the interleaved sample buffer, which means for each point in time there are n
         // samples where n is the number of channels in the track (for stereo that's 2)
         let samples = sample_buffer.samples();
+        for plugin in self.plugins.iter_mut() {
+            plugin.process(samples);
+        }
         // cache decoded frames
         self.sample_buf.extend_from_slice(samples);
         // let mut samples =
@@ -220,7 +379,7 @@ impl Analyzer {
             let converter = Samplerate::new(
                 ConverterType::SincFastest,
                 sample_rate,
-                PREVIEW_SAMPLE_RATE,
+                self.config.preview_sample_rate,
                 num_channels,
             )
             .unwrap();
@@ -231,20 +390,23 @@ impl Analyzer {
             // let samples = self.smoothing(&self.preview_buf);
             let samples = converter.process_last(&samples).unwrap();
             let mut preview_samples =
-                self.samples_2_preview_samples(&samples, PREVIEW_SAMPLE_RATE as usize);
+                self.samples_2_preview_samples(&samples, self.config.preview_sample_rate as usize);
             self.track.append_preview_samples(&mut preview_samples);
             self.preview_buf = vec![];
         }
     }
 
     fn analyze_bpm(&mut self, bpm_range: Range<usize>) {
-        let samples = self
-            .sample_buf
+        let sample_rate = self.track.codec_params.sample_rate.unwrap();
+        // only the configured part of the track, so beatless intros and outros don't skew it
+        let num_channels = self.track.codec_params.channels.unwrap().count();
+        let n_frames = self.sample_buf.len() / num_channels;
+        let frames = bpm_window::frames(&self.config, self.bpm_window, n_frames, sample_rate);
+        let samples = self.sample_buf[frames.start * num_channels..frames.end * num_channels]
             // .to_vec()
             .iter()
             .map(|s| *s as f64)
             .collect_vec();
-        let sample_rate = self.track.codec_params.sample_rate.unwrap();
         let low_crossover = cutoff_from_frequency(200., sample_rate as usize);
         let high_crossover = cutoff_from_frequency(400., sample_rate as usize);
         let low_band_filter = bandpass_filter(low_crossover, high_crossover, 0.01);
@@ -273,7 +435,7 @@ impl Analyzer {
                 //         self.analyze_bpm(bpm_range.clone(), hop_s << 2);
                 //     };
                 // }
-                self.track.change_bpm(t as u32);
+                self.track.change_bpm(fold_bpm(t as u32, self.config.bpm_min));
                 // println!("{}", t);
             }
             Err(err) => {
@@ -282,6 +444,49 @@ impl Analyzer {
         };
     }
 
+    /// stores the results of all plugins in the track's meta data
+    fn finish_plugins(&mut self) {
+        let mut meta = self.track.meta.write().unwrap();
+        for plugin in self.plugins.iter_mut() {
+            for (key, value) in plugin.finish() {
+                meta.extra.insert(format!("{}.{}", plugin.name(), key), value);
+            }
+        }
+    }
+
+    /// stores the spectral balance of the whole track in the track's meta data
+    fn finish_balance(&mut self) {
+        let balance = spectral_balance(&self.track.preview_samples());
+        let mut meta = self.track.meta.write().unwrap();
+        meta.extra.insert(String::from(BALANCE_KEY), format!("{:.3}", balance));
+    }
+
+    /// fills the track with the results of an earlier analysis
+    fn restore(&mut self, mut cached: CachedAnalysis) {
+        self.track.append_preview_samples(&mut cached.preview);
+        let mut meta = self.track.meta.write().unwrap();
+        meta.bpm = cached.bpm;
+        meta.extra.append(&mut cached.extra);
+        // results cached before the balance was computed
+        if !meta.extra.contains_key(BALANCE_KEY) {
+            drop(meta);
+            self.finish_balance();
+        }
+    }
+
+    /// caches the results of the finished analysis, so unchanged files are not analyzed again
+    fn store(&self) {
+        let meta = self.track.meta.read().unwrap();
+        let analysis = CachedAnalysis {
+            bpm: meta.bpm,
+            extra: meta.extra.clone(),
+            preview: self.track.preview_samples(),
+        };
+        if let Err(err) = analysis_cache::store(&self.track.file_path, &self.config, &analysis) {
+            warn!("failed to cache the analysis of {}: {}", self.track.file_path, err);
+        }
+    }
+
     fn sum_to_mono(&mut self, samples: &[f32]) -> Vec<f32> {
         let num_channels = self.track.codec_params.channels.unwrap().count();
         samples
@@ -332,13 +537,12 @@ impl Analyzer {
         let samples = samples.iter().map(|s| *s as f64).collect_vec();
         // let sample_rate = 44100 / 2;
         // let low_low_crossover = cutoff_from_frequency(20., sample_rate * 4);
-        let high_low_crossover = cutoff_from_frequency(65., sample_rate);
-        let low_mid_crossover = cutoff_from_frequency(100., sample_rate);
-        let high_mid_crossover = cutoff_from_frequency(400., sample_rate);
-        let low_high_crossover = cutoff_from_frequency(800., sample_rate);
+        let high_low_crossover = cutoff_from_frequency(self.config.crossover_low, sample_rate);
+        let low_mid_crossover = cutoff_from_frequency(self.config.crossover_mid_low, sample_rate);
+        let high_mid_crossover = cutoff_from_frequency(self.config.crossover_mid_high, sample_rate);
+        let low_high_crossover = cutoff_from_frequency(self.config.crossover_high, sample_rate);
         // the maximum high frequency is given by the nyquist freq = sample_rate /2
-        let high_high_crossover =
-            cutoff_from_frequency(PREVIEW_SAMPLE_RATE as f64 / 2., sample_rate);
+        let high_high_crossover = cutoff_from_frequency(sample_rate as f64 / 2., sample_rate);
         let low_band_filter = lowpass_filter(high_low_crossover, 0.01);
         let lows = convolve(&low_band_filter, &samples);
         let lows = self.peak_intersample_filter.smoothing(&lows);
@@ -410,3 +614,55 @@ impl PeakIntersampleFilter {
         peaks
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{config, test_signal::TestSignal};
+    use std::sync::mpsc::channel;
+
+    /// an example plugin, that counts the samples of a track
+    struct SampleCounter(usize);
+
+    impl AnalyzerPlugin for SampleCounter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn process(&mut self, samples: &[f32]) {
+            self.0 += samples.len();
+        }
+
+        fn finish(&mut self) -> Vec<(String, String)> {
+            vec![(String::from("samples"), self.0.to_string())]
+        }
+    }
+
+    /// analyzes a file and returns the result of the counter plugin
+    fn analyze(file_path: &str) -> Option<String> {
+        let (events_out, events_in) = channel();
+        Analyzer::spawn(String::from(file_path), Config::default(), None, events_out)
+            .join()
+            .unwrap();
+        let track = events_in.try_iter().find_map(|ev| match ev {
+            Event::NewTrack(track) => Some(track),
+            _ => None,
+        })?;
+        let meta = track.meta.read().unwrap();
+        meta.extra.get("counter.samples").cloned()
+    }
+
+    #[test]
+    fn runs_plugins_on_cached_tracks_after_they_were_registered() {
+        let dir = config::use_test_data_dir().join("analyzer");
+        let path = TestSignal::Sweep.write(&dir).unwrap();
+        let path = path.to_string_lossy();
+
+        // the results are cached without the plugin
+        assert_eq!(analyze(&path), None);
+        register_plugin(|| Box::new(SampleCounter(0)));
+        // so the cache is not used, once it was registered
+        assert_eq!(analyze(&path), Some((60 * 44100 * 2).to_string()));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,108 @@
+use std::{collections::HashMap, fs, io, ops::Range, path::PathBuf};
+
+use crate::core::config::{self, Config};
+
+//------------------------------------------------------------------//
+//                            BpmWindow                             //
+//------------------------------------------------------------------//
+
+/// Holds the parts of tracks in seconds, that their BPM is detected from instead of the
+/// configured window, keyed by file path. They are persisted to `<data dir>/bpm_windows` with
+/// one `start<TAB>end<TAB>file path` line per track.
+pub struct BpmWindowStore {
+    path: Option<PathBuf>,
+    windows: HashMap<String, (f64, f64)>,
+}
+
+impl BpmWindowStore {
+    /// loads the persisted windows
+    pub fn load() -> Self {
+        let path = config::data_dir().map(|dir| dir.join("bpm_windows"));
+        let mut windows = HashMap::new();
+        if let Some(content) = path.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+            for line in content.lines() {
+                let mut fields = line.splitn(3, '\t');
+                if let (Some(start), Some(end), Some(file_path)) =
+                    (fields.next(), fields.next(), fields.next())
+                {
+                    if let (Ok(start), Ok(end)) = (start.parse(), end.parse()) {
+                        windows.insert(String::from(file_path), (start, end));
+                    }
+                }
+            }
+        }
+        Self { path, windows }
+    }
+
+    /// returns the window of a track, if it has its own
+    pub fn get(&self, file_path: &str) -> Option<(f64, f64)> {
+        self.windows.get(file_path).copied()
+    }
+
+    /// sets the window of a track, or goes back to the configured window, and persists all
+    /// windows
+    pub fn set(&mut self, file_path: &str, window: Option<(f64, f64)>) -> io::Result<()> {
+        match window {
+            Some(window) => self.windows.insert(String::from(file_path), window),
+            None => self.windows.remove(file_path),
+        };
+        self.save()
+    }
+
+    /// writes all windows to the windows file
+    fn save(&self) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut content = String::new();
+            for (file_path, (start, end)) in &self.windows {
+                content.push_str(&format!("{}\t{}\t{}\n", start, end, file_path));
+            }
+            fs::write(path, content)?;
+        }
+        Ok(())
+    }
+}
+
+/// parses a window like `60-180` in seconds
+pub fn parse_window(text: &str) -> Result<(f64, f64), String> {
+    let (start, end) = text
+        .split_once('-')
+        .ok_or_else(|| format!("`{}` is not a window like 60-180", text))?;
+    match (start.trim().parse::<f64>(), end.trim().parse::<f64>()) {
+        (Ok(start), Ok(end)) if start >= 0. && end > start => Ok((start, end)),
+        _ => Err(format!("`{}` is not a window like 60-180", text)),
+    }
+}
+
+/// returns the frames of a track, that its BPM is detected from. Without a window of its own,
+/// `bpm_skip_intro` and `bpm_skip_outro` seconds are skipped and at most `bpm_window` seconds
+/// from the middle of the rest are used. The whole track is used, if nothing would be left
+pub fn frames(
+    config: &Config,
+    window: Option<(f64, f64)>,
+    n_frames: usize,
+    sample_rate: u32,
+) -> Range<usize> {
+    let to_frame = |seconds: f64| ((seconds * sample_rate as f64) as usize).min(n_frames);
+    let range = match window {
+        Some((start, end)) => to_frame(start)..to_frame(end),
+        None => {
+            let start = to_frame(config.bpm_skip_intro);
+            let end = n_frames.saturating_sub(to_frame(config.bpm_skip_outro));
+            let len = end.saturating_sub(start);
+            let max_len = match to_frame(config.bpm_window) {
+                0 => len,
+                max_len => max_len.min(len),
+            };
+            let start = start + (len - max_len) / 2;
+            start..start + max_len
+        }
+    };
+    if range.is_empty() {
+        0..n_frames
+    } else {
+        range
+    }
+}
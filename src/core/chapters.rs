@@ -0,0 +1,324 @@
+use std::{borrow::Cow, fs, io, ops::Range, path::Path, time::Duration};
+
+use lofty::{
+    config::{ParseOptions, WriteOptions},
+    file::AudioFile,
+    id3::v2::{BinaryFrame, Frame, FrameId},
+    mpeg::MpegFile,
+    tag::TagExt,
+};
+
+//------------------------------------------------------------------//
+//                             Chapters                             //
+//------------------------------------------------------------------//
+
+/// most chapters, that a table of contents lists
+const MAX_CHAPTERS: usize = 255;
+
+/// A navigable section of a recorded mix
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chapter {
+    /// position in the mix, at which the chapter starts
+    pub start: Duration,
+    pub title: String,
+}
+
+/// returns the length of an audio file, as the file's headers tell it
+pub fn duration(path: &Path) -> io::Result<Duration> {
+    let tagged_file = lofty::read_from_path(path).map_err(io::Error::other)?;
+    Ok(tagged_file.properties().duration())
+}
+
+/// embeds chapters into a recorded mix. MP3 files get ID3v2 chapter frames with a table of
+/// contents, M4A files a Nero chapter list, which podcast apps read as well. Chapters, that the
+/// file had before, are replaced
+pub fn write(path: &Path, chapters: &[Chapter]) -> io::Result<()> {
+    let chapters = &chapters[..chapters.len().min(MAX_CHAPTERS)];
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("mp3") => write_id3(path, chapters),
+        Some("m4a" | "m4b" | "mp4") => {
+            let content = fs::read(path)?;
+            fs::write(path, with_nero_chapters(&content, chapters)?)
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "chapters can only be written to MP3 and M4A files",
+        )),
+    }
+}
+
+//------------------------------------------------------------------//
+//                               ID3                                //
+//------------------------------------------------------------------//
+
+fn write_id3(path: &Path, chapters: &[Chapter]) -> io::Result<()> {
+    let end = duration(path)?;
+    let mut file = fs::File::open(path)?;
+    let mpeg = MpegFile::read_from(&mut file, ParseOptions::new()).map_err(io::Error::other)?;
+    let mut tag = mpeg.id3v2().cloned().unwrap_or_default();
+    for id in ["CHAP", "CTOC"] {
+        tag.remove(&FrameId::Valid(Cow::Borrowed(id)))
+            .for_each(drop);
+    }
+    // the top-level table of contents lists the chapters in order
+    let mut toc = b"toc\0\x03".to_vec();
+    toc.push(chapters.len() as u8);
+    for i in 0..chapters.len() {
+        toc.extend(format!("chp{}\0", i).bytes());
+    }
+    tag.insert(binary_frame("CTOC", toc));
+    for (i, chapter) in chapters.iter().enumerate() {
+        let chapter_end = chapters.get(i + 1).map_or(end, |next| next.start);
+        tag.insert(binary_frame("CHAP", chap_frame(i, chapter, chapter_end)));
+    }
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(io::Error::other)
+}
+
+fn binary_frame(id: &'static str, data: Vec<u8>) -> Frame<'static> {
+    Frame::Binary(BinaryFrame::new(FrameId::Valid(Cow::Borrowed(id)), data))
+}
+
+/// returns the content of a CHAP frame, whose title is an embedded ID3v2.4 TIT2 frame
+fn chap_frame(i: usize, chapter: &Chapter, end: Duration) -> Vec<u8> {
+    let mut data = format!("chp{}\0", i).into_bytes();
+    data.extend((chapter.start.as_millis() as u32).to_be_bytes());
+    data.extend((end.as_millis() as u32).to_be_bytes());
+    // the chapter isn't given by byte offsets
+    data.extend([0xff; 8]);
+    let mut title = vec![3];
+    title.extend(chapter.title.bytes());
+    data.extend(b"TIT2");
+    data.extend(synchsafe(title.len() as u32));
+    data.extend([0, 0]);
+    data.extend(title);
+    data
+}
+
+/// encodes a size with 7 bits per byte, as ID3v2.4 frame headers do
+fn synchsafe(n: u32) -> [u8; 4] {
+    [
+        (n >> 21) as u8 & 0x7f,
+        (n >> 14) as u8 & 0x7f,
+        (n >> 7) as u8 & 0x7f,
+        n as u8 & 0x7f,
+    ]
+}
+
+//------------------------------------------------------------------//
+//                               MP4                                //
+//------------------------------------------------------------------//
+
+/// returns the atoms in `data` with their type and the range of their content
+fn atoms(data: &[u8]) -> io::Result<Vec<([u8; 4], Range<usize>)>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed MP4 atom");
+    let mut atoms = vec![];
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as u64;
+        let kind: [u8; 4] = data[pos + 4..pos + 8].try_into().unwrap();
+        let (header, size) = match size {
+            // the atom reaches to the end of the file
+            0 => (8, (data.len() - pos) as u64),
+            1 => {
+                let large = data.get(pos + 8..pos + 16).ok_or_else(invalid)?;
+                (16, u64::from_be_bytes(large.try_into().unwrap()))
+            }
+            size => (8, size),
+        };
+        let end = pos
+            .checked_add(usize::try_from(size).map_err(|_| invalid())?)
+            .filter(|end| *end <= data.len() && size >= header)
+            .ok_or_else(invalid)?;
+        atoms.push((kind, pos + header as usize..end));
+        pos = end;
+    }
+    Ok(atoms)
+}
+
+/// returns an atom with a 32 bit size
+fn atom(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut atom = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+    atom.extend(kind);
+    atom.extend(content);
+    atom
+}
+
+/// returns a Nero `chpl` atom. Its start times count 100 nanosecond units
+fn chpl_atom(chapters: &[Chapter]) -> Vec<u8> {
+    let mut content = vec![1, 0, 0, 0, 0, 0, 0, 0, chapters.len() as u8];
+    for chapter in chapters {
+        content.extend(((chapter.start.as_nanos() / 100) as u64).to_be_bytes());
+        let title = &chapter.title.as_bytes()[..chapter.title.len().min(255)];
+        content.push(title.len() as u8);
+        content.extend(title);
+    }
+    atom(b"chpl", &content)
+}
+
+/// returns the file with the chapter list replaced in `moov.udta`. If the media data follows
+/// the `moov` atom, the chunk offsets of all tracks are moved by the change in its size
+fn with_nero_chapters(content: &[u8], chapters: &[Chapter]) -> io::Result<Vec<u8>> {
+    let top = atoms(content)?;
+    let (moov_start, moov) = top
+        .iter()
+        .find(|(kind, _)| kind == b"moov")
+        .map(|(_, range)| (range.start - 8, range.clone()))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no moov atom"))?;
+    let mdat_follows = top
+        .iter()
+        .any(|(kind, range)| kind == b"mdat" && range.start > moov.end);
+
+    let mut moov_content = vec![];
+    let mut has_udta = false;
+    for (kind, range) in atoms(&content[moov.clone()])? {
+        let data = &content[moov.start + range.start..moov.start + range.end];
+        if &kind == b"udta" {
+            has_udta = true;
+            let mut udta_content = vec![];
+            for (kind, range) in atoms(data)? {
+                if &kind != b"chpl" {
+                    udta_content.extend(atom(&kind, &data[range]));
+                }
+            }
+            udta_content.extend(chpl_atom(chapters));
+            moov_content.extend(atom(b"udta", &udta_content));
+        } else {
+            moov_content.extend(atom(&kind, data));
+        }
+    }
+    if !has_udta {
+        moov_content.extend(atom(b"udta", &chpl_atom(chapters)));
+    }
+    let mut new_moov = atom(b"moov", &moov_content);
+    if mdat_follows {
+        let delta = new_moov.len() as i64 - (moov.end - moov_start) as i64;
+        move_chunk_offsets(&mut new_moov[8..], delta)?;
+    }
+    let mut res = content[..moov_start].to_vec();
+    res.extend(new_moov);
+    res.extend(&content[moov.end..]);
+    Ok(res)
+}
+
+/// adds `delta` to the entries of all `stco` and `co64` atoms below the given atoms
+fn move_chunk_offsets(data: &mut [u8], delta: i64) -> io::Result<()> {
+    for (kind, range) in atoms(data)? {
+        let content = &mut data[range];
+        match &kind {
+            b"trak" | b"mdia" | b"minf" | b"stbl" => move_chunk_offsets(content, delta)?,
+            // version, flags and the number of entries come before the entries
+            b"stco" => {
+                for entry in content.get_mut(8..).unwrap_or_default().chunks_exact_mut(4) {
+                    let offset = u32::from_be_bytes((&*entry).try_into().unwrap()) as i64;
+                    entry.copy_from_slice(&((offset + delta) as u32).to_be_bytes());
+                }
+            }
+            b"co64" => {
+                for entry in content.get_mut(8..).unwrap_or_default().chunks_exact_mut(8) {
+                    let offset = u64::from_be_bytes((&*entry).try_into().unwrap()) as i64;
+                    entry.copy_from_slice(&((offset + delta) as u64).to_be_bytes());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config;
+    use lofty::id3::v2::Id3v2Tag;
+
+    fn chapters() -> Vec<Chapter> {
+        vec![
+            Chapter {
+                start: Duration::ZERO,
+                title: String::from("First Artist - Intro"),
+            },
+            Chapter {
+                start: Duration::from_millis(1500),
+                title: String::from("Second Track"),
+            },
+        ]
+    }
+
+    #[test]
+    fn adds_nero_chapters_and_moves_the_chunk_offsets() {
+        let stco = atom(b"stco", &[0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0]);
+        let stbl = atom(b"stbl", &stco);
+        let trak = atom(b"trak", &atom(b"mdia", &atom(b"minf", &stbl)));
+        let mut file = atom(b"ftyp", b"M4A \0\0\0\0");
+        file.extend(atom(b"moov", &trak));
+        let mdat_offset = file.len() + 8;
+        file.extend(atom(b"mdat", b"audio"));
+        // point the only chunk at the media data
+        let stco_entry = file.len() - 13 - 4;
+        file[stco_entry..stco_entry + 4].copy_from_slice(&(mdat_offset as u32).to_be_bytes());
+
+        let res = with_nero_chapters(&file, &chapters()).unwrap();
+        let top = atoms(&res).unwrap();
+        let kinds: Vec<_> = top.iter().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds, [b"ftyp", b"moov", b"mdat"]);
+        let moov = &res[top[1].1.clone()];
+        let udta = atoms(moov).unwrap().pop().unwrap();
+        assert_eq!(&udta.0, b"udta");
+        let chpl = &moov[udta.1];
+        assert_eq!(chpl, &chpl_atom(&chapters())[..]);
+        assert_eq!(&chpl[17..25], &[0; 8]);
+        assert_eq!(&chpl[25..46], b"\x14First Artist - Intro");
+        assert_eq!(&chpl[46..54], &15_000_000u64.to_be_bytes());
+
+        // the chunk still points at the media data
+        let mdat = top[2].1.start;
+        let stco_entry = res.windows(4).position(|w| w == b"stco").unwrap() + 12;
+        let offset = u32::from_be_bytes(res[stco_entry..stco_entry + 4].try_into().unwrap());
+        assert_eq!(offset as usize, mdat);
+        assert_eq!(&res[mdat..], b"audio");
+
+        // chapters, that were written before, are replaced
+        let again = with_nero_chapters(&res, &chapters()[..1]).unwrap();
+        assert_eq!(again.windows(4).filter(|w| w == b"chpl").count(), 1);
+        assert_eq!(again.len(), res.len() - 8 - 1 - 12);
+    }
+
+    #[test]
+    fn adds_id3_chapters_with_a_table_of_contents() {
+        let dir = config::use_test_data_dir().join("chapters");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mix.mp3");
+        // silent MPEG-1 layer III frames at 128 kbit/s and 44.1 kHz, 417 bytes each
+        let mut frame = vec![0; 417];
+        frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        fs::write(&path, frame.repeat(200)).unwrap();
+
+        write(&path, &chapters()).unwrap();
+        let mut file = fs::File::open(&path).unwrap();
+        let mpeg = MpegFile::read_from(&mut file, ParseOptions::new()).unwrap();
+        let tag: &Id3v2Tag = mpeg.id3v2().unwrap();
+        let frames: Vec<Vec<u8>> = tag
+            .into_iter()
+            .filter_map(|frame| match frame {
+                Frame::Binary(frame) => Some(frame.data.to_vec()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(frames.len(), 3);
+        assert!(frames.contains(&b"toc\0\x03\x02chp0\0chp1\0".to_vec()));
+        let end = duration(&path).unwrap();
+        assert!(frames.contains(&chap_frame(0, &chapters()[0], Duration::from_millis(1500))));
+        assert!(frames.contains(&chap_frame(1, &chapters()[1], end)));
+
+        // writing again replaces the chapters
+        write(&path, &chapters()[..1]).unwrap();
+        let mut file = fs::File::open(&path).unwrap();
+        let mpeg = MpegFile::read_from(&mut file, ParseOptions::new()).unwrap();
+        assert_eq!(mpeg.id3v2().unwrap().len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,83 @@
+use std::{io, path::PathBuf, process::Command};
+
+use crate::core::probe;
+
+//------------------------------------------------------------------//
+//                            Clipboard                             //
+//------------------------------------------------------------------//
+
+/// Tools, that print the system clipboard, tried in this order. The first one works on Wayland,
+/// the others on X11
+const PASTE_COMMANDS: [(&str, &[&str]); 3] = [
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-o", "-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--output"]),
+];
+
+/// returns the text on the system clipboard
+pub fn read() -> io::Result<String> {
+    for (program, args) in PASTE_COMMANDS {
+        match Command::new(program).args(args).output() {
+            Ok(output) if output.status.success() => {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+            // the tool exists, but the clipboard is empty or unavailable
+            Ok(_) => continue,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard tool found, install wl-clipboard, xclip or xsel",
+    ))
+}
+
+/// turns clipboard text into the path of a playable file. Accepts plain paths and `file://`
+/// URLs, as copied from file managers. Returns the reason, if the text doesn't name one
+pub fn parse_track_path(text: &str) -> Result<PathBuf, String> {
+    // file managers put every copied file on its own line
+    let text = text.lines().next().unwrap_or("").trim();
+    if text.is_empty() {
+        return Err(String::from("the clipboard is empty"));
+    }
+    let path = match text.split_once("://") {
+        Some(("file", rest)) => PathBuf::from(percent_decode(
+            // skip the host, which is usually empty
+            &rest[rest.find('/').unwrap_or(rest.len())..],
+        )),
+        Some((scheme, _)) => return Err(format!("{} URLs can't be played", scheme)),
+        None => PathBuf::from(text),
+    };
+    if !path.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    if !probe::has_supported_extension(&path) {
+        return Err(format!("{} is not a supported format", path.display()));
+    }
+    Ok(path)
+}
+
+/// decodes `%XX` escapes of an URL
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
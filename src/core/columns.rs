@@ -0,0 +1,116 @@
+use std::{fmt, str::FromStr};
+
+//------------------------------------------------------------------//
+//                             Columns                              //
+//------------------------------------------------------------------//
+
+/// A column of the track table
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    FileName,
+    Title,
+    Artist,
+    Album,
+    Analysis,
+    Bpm,
+    Key,
+    Format,
+    Plays,
+    LastPlayed,
+}
+
+impl Column {
+    /// all columns in the order, that the column picker lists them
+    pub const ALL: [Column; 10] = [
+        Column::FileName,
+        Column::Title,
+        Column::Artist,
+        Column::Album,
+        Column::Analysis,
+        Column::Bpm,
+        Column::Key,
+        Column::Format,
+        Column::Plays,
+        Column::LastPlayed,
+    ];
+
+    /// returns the name, that the column has in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::FileName => "file_name",
+            Column::Title => "title",
+            Column::Artist => "artist",
+            Column::Album => "album",
+            Column::Analysis => "analysis",
+            Column::Bpm => "bpm",
+            Column::Key => "key",
+            Column::Format => "format",
+            Column::Plays => "plays",
+            Column::LastPlayed => "last_played",
+        }
+    }
+}
+
+/// A shown column with its width in characters. Columns without a width share the space, that
+/// is left
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColumnSetting {
+    pub column: Column,
+    pub width: Option<u16>,
+}
+
+impl ColumnSetting {
+    /// returns the columns, that are shown by default
+    pub fn defaults() -> Vec<ColumnSetting> {
+        [
+            Column::FileName,
+            Column::Title,
+            Column::Artist,
+            Column::Analysis,
+            Column::Bpm,
+            Column::Format,
+            Column::Plays,
+            Column::LastPlayed,
+        ]
+        .into_iter()
+        .map(|column| ColumnSetting {
+            column,
+            width: None,
+        })
+        .collect()
+    }
+}
+
+impl FromStr for ColumnSetting {
+    type Err = String;
+
+    /// parses `<name>` or `<name>:<width>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, width) = match s.split_once(':') {
+            Some((name, width)) => (
+                name.trim(),
+                Some(
+                    width
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("invalid column width `{}`", width))?,
+                ),
+            ),
+            None => (s.trim(), None),
+        };
+        let column = Column::ALL
+            .into_iter()
+            .find(|column| column.name() == name)
+            .ok_or_else(|| format!("unknown column `{}`", name))?;
+        Ok(ColumnSetting { column, width })
+    }
+}
+
+impl fmt::Display for ColumnSetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.width {
+            Some(width) => write!(f, "{}:{}", self.column.name(), width),
+            None => write!(f, "{}", self.column.name()),
+        }
+    }
+}
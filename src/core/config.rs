@@ -0,0 +1,534 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use log::warn;
+
+use crate::core::{analyzer::PREVIEW_SAMPLE_RATE, columns::ColumnSetting, glob};
+
+/// Number of quick-load slots
+pub const QUICK_LOAD_SLOTS: usize = 10;
+
+//------------------------------------------------------------------//
+//                              Config                              //
+//------------------------------------------------------------------//
+
+/// User settings. They are read from `$XDG_CONFIG_HOME/flow/config`, which consists of
+/// `key = value` lines. Empty lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// remaining seconds of the playing track, below which an end-of-track warning is shown
+    pub end_of_track_warning: f64,
+    /// directories, that are scanned for tracks and watched for changes. The first one is set
+    /// with `library`, further ones with `library.<name> = <path>`. Settings of a single root are
+    /// set with `library.<name>.<setting>` before or after its path, or without the prefix for the
+    /// first one
+    pub libraries: Vec<LibraryRoot>,
+    /// file, that the library gets exported to and imported from. The format is JSON for `.json`
+    /// files and CSV otherwise
+    pub library_export: PathBuf,
+    /// whether a copy of a file is kept as `<file>.bak`, before its tags are written the first
+    /// time
+    pub tag_backup: bool,
+    /// columns of the library's track table in their order, as a comma separated list of
+    /// `<name>` or `<name>:<width>`. Can also be changed with the column picker
+    pub library_columns: Vec<ColumnSetting>,
+    /// tracks, that can be loaded with two key presses. Set with `quick_load.<slot> = <path>`
+    /// for slots 0 to 9
+    pub quick_load: [Option<PathBuf>; QUICK_LOAD_SLOTS],
+    /// file, that the planned set is exported to as an M3U playlist
+    pub set_playlist: PathBuf,
+    /// file, that analysis results, memory cues and bookmarks of a library group are bundled
+    /// into, so they can be moved to another machine
+    pub analysis_bundle: PathBuf,
+    /// number of preview samples per second of audio. Higher values give more waveform detail
+    /// at the cost of memory
+    pub preview_sample_rate: u32,
+    /// upper edge of the low band of the waveform in Hz
+    pub crossover_low: f64,
+    /// lower edge of the mid band of the waveform in Hz
+    pub crossover_mid_low: f64,
+    /// upper edge of the mid band of the waveform in Hz
+    pub crossover_mid_high: f64,
+    /// lower edge of the high band of the waveform in Hz. The high band reaches up to half the
+    /// preview sample rate
+    pub crossover_high: f64,
+    /// size of the buffer in KiB, that the played file is read into ahead of the decoder.
+    /// Playback pauses, when reading from slow storage can't keep up. 0 reads directly
+    pub read_ahead: usize,
+    /// maximum number of frames, that the UI draws per second. When drawing takes longer than a
+    /// frame, the waveform is drawn with less detail until there is headroom again
+    pub frame_rate: u32,
+    /// whether the waveforms are replaced by plain status lines about the loaded track, that
+    /// terminal screen readers can read out
+    pub accessible: bool,
+    /// whether waveforms are tinted by the spectral balance of their track: bass-heavy tracks
+    /// are drawn warm and bright tracks cool
+    pub waveform_tint: bool,
+    /// whether a track is picked from the library and appended to the set plan, once the loaded
+    /// track nears its end and nothing is planned after it. Picks follow the key and tempo of
+    /// the loaded track and avoid tracks, that were played within the last `fresh_days`
+    pub auto_pick: bool,
+    /// language of the interface. Translations other than `en` are read from
+    /// `locales/<locale>.txt` next to the config file
+    pub locale: String,
+    /// whether generated test signals are listed along with the library: a sine sweep, clicks at
+    /// `test_signal_bpm` and pink noise
+    pub test_signals: bool,
+    /// tempo of the generated click track
+    pub test_signal_bpm: u32,
+    /// time in milliseconds, that played audio takes to become audible. The playhead is drawn
+    /// this much behind the decoder, so it matches what is heard. Measured with Ctrl+l
+    pub latency: f64,
+    /// number of days, that tracks stay hidden after they were played, while only fresh tracks
+    /// are listed
+    pub fresh_days: u32,
+    /// lowest BPM, that detected tempos are reported at. Tempos are doubled or halved, until
+    /// they lie between this and twice this value
+    pub bpm_min: u32,
+    /// seconds at the start of tracks, that are not used to detect their BPM, so beatless
+    /// intros don't skew it
+    pub bpm_skip_intro: f64,
+    /// seconds at the end of tracks, that are not used to detect their BPM
+    pub bpm_skip_outro: f64,
+    /// seconds from the middle of the rest of a track, that its BPM is detected from. The whole
+    /// rest is used, if this is 0. Tracks can override the window with Alt+w
+    pub bpm_window: f64,
+    /// loudness in LUFS, that is marked on the loudness meter
+    pub loudness_target: f64,
+    /// hotkey like `ctrl+alt+KeyP`, that toggles playback, while another window has focus.
+    /// Only used, if flow was built with the `global-hotkeys` feature
+    pub global_play_key: Option<String>,
+    /// hotkey, that presses cue, while another window has focus. Only used, if flow was built
+    /// with the `global-hotkeys` feature
+    pub global_cue_key: Option<String>,
+    /// name of the PulseAudio sink, that playback goes to. The default sink is used, if this is
+    /// not set
+    pub output_device: Option<String>,
+    /// name of the PulseAudio sink, that tracks are pre-listened on. The default sink is used,
+    /// if this is not set
+    pub prelisten_device: Option<String>,
+    /// name of the PulseAudio sink, that the booth output is played on. The booth output is off,
+    /// if this is not set
+    pub booth_device: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            end_of_track_warning: 30.,
+            libraries: vec![LibraryRoot::new(
+                "library",
+                env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join("Music"))
+                    .unwrap_or_default(),
+            )],
+            library_export: data_dir()
+                .map(|dir| dir.join("library.csv"))
+                .unwrap_or_else(|| PathBuf::from("library.csv")),
+            tag_backup: true,
+            library_columns: ColumnSetting::defaults(),
+            quick_load: Default::default(),
+            set_playlist: data_dir()
+                .map(|dir| dir.join("set.m3u"))
+                .unwrap_or_else(|| PathBuf::from("set.m3u")),
+            analysis_bundle: data_dir()
+                .map(|dir| dir.join("analysis.bundle"))
+                .unwrap_or_else(|| PathBuf::from("analysis.bundle")),
+            preview_sample_rate: PREVIEW_SAMPLE_RATE,
+            crossover_low: 65.,
+            crossover_mid_low: 100.,
+            crossover_mid_high: 400.,
+            crossover_high: 800.,
+            read_ahead: 1024,
+            frame_rate: 30,
+            accessible: false,
+            waveform_tint: false,
+            auto_pick: false,
+            locale: String::from("en"),
+            test_signals: false,
+            latency: 0.,
+            test_signal_bpm: 120,
+            fresh_days: 30,
+            bpm_min: 70,
+            bpm_skip_intro: 0.,
+            bpm_skip_outro: 0.,
+            bpm_window: 0.,
+            loudness_target: -14.,
+            global_play_key: None,
+            global_cue_key: None,
+            output_device: None,
+            prelisten_device: None,
+            booth_device: None,
+        }
+    }
+}
+
+impl Config {
+    /// returns the path of the config file
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("flow").join("config"))
+    }
+
+    /// loads the config file. Missing or invalid entries fall back to their default value
+    pub fn load() -> Self {
+        let mut config = Config::default();
+        let path = match Config::path() {
+            Some(path) => path,
+            None => return config,
+        };
+        if let Ok(content) = fs::read_to_string(&path) {
+            for (i, line) in content.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let res = match line.split_once('=') {
+                    Some((key, value)) => config.set(key.trim(), value.trim()),
+                    None => Err(String::from("expected `key = value`")),
+                };
+                if let Err(err) = res {
+                    warn!("{}:{}: {}", path.display(), i + 1, err);
+                }
+            }
+        }
+        if let Err(err) = config.check_libraries() {
+            warn!("{}: {}", path.display(), err);
+        }
+        if let Err(err) = config.check_crossovers() {
+            warn!("{}: {}", path.display(), err);
+            let default = Config::default();
+            config.crossover_low = default.crossover_low;
+            config.crossover_mid_low = default.crossover_mid_low;
+            config.crossover_mid_high = default.crossover_mid_high;
+            config.crossover_high = default.crossover_high;
+        }
+        config
+    }
+
+    /// overrides settings with command line arguments of the form `--some-setting value`
+    pub fn apply_args<I: Iterator<Item = String>>(&mut self, mut args: I) -> Result<(), String> {
+        while let Some(arg) = args.next() {
+            let key = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument `{}`", arg))?
+                .replace('-', "_");
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for `{}`", arg))?;
+            self.set(&key, &value)?;
+        }
+        self.check_libraries()?;
+        self.check_crossovers()
+    }
+
+    /// removes the further library roots, that got settings but never a path
+    pub fn check_libraries(&mut self) -> Result<(), String> {
+        let missing = self.libraries[1..]
+            .iter()
+            .filter(|root| root.path.as_os_str().is_empty())
+            .map(|root| format!("`library.{}`", root.name))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let first = self.libraries.remove(0);
+        self.libraries
+            .retain(|root| !root.path.as_os_str().is_empty());
+        self.libraries.insert(0, first);
+        Err(format!("missing path for {}", missing.join(", ")))
+    }
+
+    /// checks, that the crossover frequencies are ascending and below the highest frequency of
+    /// the preview
+    pub fn check_crossovers(&self) -> Result<(), String> {
+        let nyquist = self.preview_sample_rate as f64 / 2.;
+        let frequencies = [
+            0.,
+            self.crossover_low,
+            self.crossover_mid_low,
+            self.crossover_mid_high,
+            self.crossover_high,
+            nyquist,
+        ];
+        if frequencies.windows(2).all(|pair| pair[0] < pair[1]) {
+            Ok(())
+        } else {
+            Err(format!(
+                "crossovers must be ascending and below {} Hz",
+                nyquist
+            ))
+        }
+    }
+
+    /// writes a single setting to the config file. An existing line for the setting is replaced,
+    /// the rest of the file is kept as it is
+    pub fn save_setting(key: &str, value: &str) -> io::Result<()> {
+        let path = Config::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        let line = format!("{} = {}", key, value);
+        let mut found = false;
+        let mut lines: Vec<String> = content
+            .lines()
+            .map(|l| match l.split_once('=') {
+                Some((k, _)) if k.trim() == key && !l.trim_start().starts_with('#') => {
+                    found = true;
+                    line.clone()
+                }
+                _ => String::from(l),
+            })
+            .collect();
+        if !found {
+            lines.push(line);
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, lines.join("\n") + "\n")
+    }
+
+    /// returns true, if tracks have to be analyzed again to apply the other settings
+    pub fn analysis_differs(&self, other: &Config) -> bool {
+        self.preview_sample_rate != other.preview_sample_rate
+            || self.crossover_low != other.crossover_low
+            || self.crossover_mid_low != other.crossover_mid_low
+            || self.crossover_mid_high != other.crossover_mid_high
+            || self.crossover_high != other.crossover_high
+            || self.bpm_min != other.bpm_min
+            || self.bpm_skip_intro != other.bpm_skip_intro
+            || self.bpm_skip_outro != other.bpm_skip_outro
+            || self.bpm_window != other.bpm_window
+    }
+
+    /// returns the enabled library root, that a file belongs to. Nested roots take precedence
+    /// over the roots, that contain them
+    pub fn root_of(&self, path: &Path) -> Option<&LibraryRoot> {
+        self.libraries
+            .iter()
+            .filter(|root| root.enabled && path.starts_with(&root.path))
+            .max_by_key(|root| root.path.as_os_str().len())
+    }
+
+    /// returns whether a file belongs to an enabled library root and passes its patterns
+    pub fn scans(&self, path: &Path) -> bool {
+        self.root_of(path).is_some_and(|root| root.scans(path))
+    }
+
+    /// sets a single setting from its string representation
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "end_of_track_warning" => self.end_of_track_warning = parse(key, value)?,
+            "library" => self.libraries[0].path = parse(key, value)?,
+            "enabled" | "include" | "exclude" | "follow_symlinks" => {
+                self.libraries[0].set(key, value)?
+            }
+            "library_export" => self.library_export = parse(key, value)?,
+            "tag_backup" => self.tag_backup = parse(key, value)?,
+            "library_columns" => {
+                self.library_columns = parse_list(value)
+                    .iter()
+                    .map(|column| column.parse())
+                    .collect::<Result<_, _>>()?
+            }
+            "set_playlist" => self.set_playlist = parse(key, value)?,
+            "analysis_bundle" => self.analysis_bundle = parse(key, value)?,
+            "preview_sample_rate" => match parse(key, value)? {
+                0 => return Err(format!("`{}` must be positive", key)),
+                rate => self.preview_sample_rate = rate,
+            },
+            "crossover_low" => self.crossover_low = parse(key, value)?,
+            "crossover_mid_low" => self.crossover_mid_low = parse(key, value)?,
+            "crossover_mid_high" => self.crossover_mid_high = parse(key, value)?,
+            "crossover_high" => self.crossover_high = parse(key, value)?,
+            "read_ahead" => self.read_ahead = parse(key, value)?,
+            "frame_rate" => match parse(key, value)? {
+                0 => return Err(format!("`{}` must be positive", key)),
+                rate => self.frame_rate = rate,
+            },
+            "test_signals" => self.test_signals = parse(key, value)?,
+            "test_signal_bpm" => match parse(key, value)? {
+                0 => return Err(format!("`{}` must be positive", key)),
+                bpm => self.test_signal_bpm = bpm,
+            },
+            "latency" => match parse(key, value)? {
+                latency if latency < 0. => return Err(format!("`{}` must not be negative", key)),
+                latency => self.latency = latency,
+            },
+            "fresh_days" => self.fresh_days = parse(key, value)?,
+            "accessible" => self.accessible = parse(key, value)?,
+            "waveform_tint" => self.waveform_tint = parse(key, value)?,
+            "auto_pick" => self.auto_pick = parse(key, value)?,
+            "locale" => self.locale = String::from(value),
+            "bpm_min" => match parse(key, value)? {
+                0 => return Err(format!("`{}` must be positive", key)),
+                bpm => self.bpm_min = bpm,
+            },
+            "bpm_skip_intro" => match parse(key, value)? {
+                skip if skip < 0. => return Err(format!("`{}` must not be negative", key)),
+                skip => self.bpm_skip_intro = skip,
+            },
+            "bpm_skip_outro" => match parse(key, value)? {
+                skip if skip < 0. => return Err(format!("`{}` must not be negative", key)),
+                skip => self.bpm_skip_outro = skip,
+            },
+            "bpm_window" => match parse(key, value)? {
+                window if window < 0. => return Err(format!("`{}` must not be negative", key)),
+                window => self.bpm_window = window,
+            },
+            "loudness_target" => self.loudness_target = parse(key, value)?,
+            "global_play_key" => {
+                self.global_play_key = Some(String::from(value)).filter(|k| !k.is_empty())
+            }
+            "global_cue_key" => {
+                self.global_cue_key = Some(String::from(value)).filter(|k| !k.is_empty())
+            }
+            "output_device" => {
+                self.output_device = Some(String::from(value)).filter(|d| !d.is_empty())
+            }
+            "prelisten_device" => {
+                self.prelisten_device = Some(String::from(value)).filter(|d| !d.is_empty())
+            }
+            "booth_device" => {
+                self.booth_device = Some(String::from(value)).filter(|d| !d.is_empty())
+            }
+            _ if key.starts_with("quick_load.") => {
+                let slot = key["quick_load.".len()..]
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|slot| *slot < QUICK_LOAD_SLOTS)
+                    .ok_or_else(|| format!("unknown quick-load slot in `{}`", key))?;
+                self.quick_load[slot] = Some(PathBuf::from(value)).filter(|_| !value.is_empty());
+            }
+            _ => match key
+                .strip_prefix("library.")
+                .map(|rest| rest.split_once('.'))
+            {
+                // a new library root
+                Some(None) => {
+                    let name = &key["library.".len()..];
+                    let path = parse(key, value)?;
+                    match self.libraries.iter_mut().find(|root| root.name == name) {
+                        Some(root) => root.path = path,
+                        None => self.libraries.push(LibraryRoot::new(name, path)),
+                    }
+                }
+                // a setting of a library root, whose path may follow later
+                Some(Some((name, setting))) => {
+                    let index = match self.libraries.iter().position(|root| root.name == name) {
+                        Some(index) => index,
+                        None => {
+                            self.libraries.push(LibraryRoot::new(name, PathBuf::new()));
+                            self.libraries.len() - 1
+                        }
+                    };
+                    self.libraries[index].set(setting, value)?
+                }
+                None => return Err(format!("unknown setting `{}`", key)),
+            },
+        }
+        Ok(())
+    }
+}
+
+//------------------------------------------------------------------//
+//                           LibraryRoot                            //
+//------------------------------------------------------------------//
+
+/// A directory, that is scanned for tracks, with its own scan settings
+#[derive(Clone, Debug)]
+pub struct LibraryRoot {
+    /// name of the group, that the tracks of the root are listed in
+    pub name: String,
+    pub path: PathBuf,
+    /// disabled roots are neither scanned nor watched, e.g. for drives, that aren't always
+    /// mounted
+    pub enabled: bool,
+    /// glob patterns relative to the root. If any are given, only matching files are scanned
+    pub include: Vec<String>,
+    /// glob patterns relative to the root, whose matching files are skipped
+    pub exclude: Vec<String>,
+    /// whether symbolic links below the root are followed while scanning
+    pub follow_symlinks: bool,
+}
+
+impl LibraryRoot {
+    pub fn new(name: &str, path: PathBuf) -> Self {
+        Self {
+            name: String::from(name),
+            path,
+            enabled: true,
+            include: vec![],
+            exclude: vec![],
+            follow_symlinks: true,
+        }
+    }
+
+    /// returns whether a file below the root passes the include and exclude patterns
+    pub fn scans(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.path).unwrap_or(path);
+        let relative = relative.to_string_lossy();
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| glob::matches(pattern, &relative));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| glob::matches(pattern, &relative))
+    }
+
+    /// sets a single scan setting from its string representation
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "enabled" => self.enabled = parse(key, value)?,
+            "include" => self.include = parse_list(value),
+            "exclude" => self.exclude = parse_list(value),
+            "follow_symlinks" => self.follow_symlinks = parse(key, value)?,
+            _ => return Err(format!("unknown library setting `{}`", key)),
+        }
+        Ok(())
+    }
+}
+
+/// returns the directory, in which data is kept between sessions
+pub fn data_dir() -> Option<PathBuf> {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(data_dir.join("flow"))
+}
+
+/// moves the data dir of the test process into a temporary directory, so tests don't touch the
+/// user's data. Returns the temporary directory, which the tests can keep their files in as well
+#[cfg(test)]
+pub fn use_test_data_dir() -> PathBuf {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    let dir = env::temp_dir().join(format!("flow-test-{}", std::process::id()));
+    INIT.call_once(|| env::set_var("XDG_DATA_HOME", dir.join("data")));
+    dir
+}
+
+/// parses a comma separated list
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// parses a setting value and reports the key on failure
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for `{}`", value, key))
+}
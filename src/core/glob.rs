@@ -0,0 +1,75 @@
+//------------------------------------------------------------------//
+//                               Glob                               //
+//------------------------------------------------------------------//
+
+/// matches a path against a glob pattern. `*` matches within a path component, `**` across
+/// components and `?` a single character. Patterns without a `/` are matched against the file
+/// name only, so `*.flac` matches flac files in every folder.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let path = match pattern.contains('/') {
+        true => path,
+        false => path.rsplit('/').next().unwrap_or(path),
+    };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    match_from(&pattern, &path)
+}
+
+fn match_from(pattern: &[char], path: &[char]) -> bool {
+    match pattern.first() {
+        None => path.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` also matches no folder at all
+            if rest.first() == Some(&'/') && match_from(&rest[1..], path) {
+                return true;
+            }
+            (0..=path.len()).any(|i| match_from(rest, &path[i..]))
+        }
+        Some('*') => {
+            for i in 0..=path.len() {
+                if match_from(&pattern[1..], &path[i..]) {
+                    return true;
+                }
+                if path.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => match path.first() {
+            Some(c) if *c != '/' => match_from(&pattern[1..], &path[1..]),
+            _ => false,
+        },
+        Some(c) => path.first() == Some(c) && match_from(&pattern[1..], &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_file_names_without_a_folder_in_the_pattern() {
+        assert!(matches("*.flac", "sets/2021/intro.flac"));
+        assert!(!matches("*.flac", "sets/2021/intro.mp3"));
+        assert!(matches("intro.???", "sets/intro.wav"));
+    }
+
+    #[test]
+    fn matches_zero_or_more_folders_with_a_double_star() {
+        assert!(matches("**/*.flac", "intro.flac"));
+        assert!(matches("**/*.flac", "sets/2021/intro.flac"));
+        assert!(matches("sets/**/intro.flac", "sets/intro.flac"));
+        assert!(matches("sets/**/intro.flac", "sets/2021/live/intro.flac"));
+        assert!(!matches("sets/**/intro.flac", "live/intro.flac"));
+    }
+
+    #[test]
+    fn matches_single_stars_and_question_marks_within_a_folder() {
+        assert!(matches("sets/*.flac", "sets/intro.flac"));
+        assert!(!matches("sets/*.flac", "sets/2021/intro.flac"));
+        assert!(matches("sets/202?/intro.flac", "sets/2021/intro.flac"));
+        assert!(!matches("sets?intro.flac", "sets/intro.flac"));
+    }
+}
@@ -0,0 +1,80 @@
+use global_hotkey::{hotkey::HotKey, GlobalHotKeyEvent, GlobalHotKeyManager};
+use log::warn;
+
+use crate::core::config::Config;
+
+//------------------------------------------------------------------//
+//                          GlobalHotkeys                           //
+//------------------------------------------------------------------//
+
+/// Deck controls, that can be bound to a global hotkey
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    TogglePlay,
+    Cue,
+}
+
+/// Hotkeys, that are grabbed from the window system, so the deck can be controlled while
+/// another window has focus. They stay registered, until this is dropped
+pub struct GlobalHotkeys {
+    /// unregisters the hotkeys, when it is dropped
+    _manager: GlobalHotKeyManager,
+    /// ids of the registered hotkeys and their actions
+    bindings: Vec<(u32, Action)>,
+}
+
+impl GlobalHotkeys {
+    /// registers the hotkeys of the `global_play_key` and `global_cue_key` settings. Returns
+    /// None, if neither is set or none of them could be registered
+    pub fn register(config: &Config) -> Option<Self> {
+        let keys = [
+            (&config.global_play_key, Action::TogglePlay),
+            (&config.global_cue_key, Action::Cue),
+        ];
+        if keys.iter().all(|(key, _)| key.is_none()) {
+            return None;
+        }
+        let manager = match GlobalHotKeyManager::new() {
+            Ok(manager) => manager,
+            Err(err) => {
+                warn!("global hotkeys are not available: {}", err);
+                return None;
+            }
+        };
+        let mut bindings = vec![];
+        for (key, action) in keys {
+            let key = match key {
+                Some(key) => key,
+                None => continue,
+            };
+            let hotkey: HotKey = match key.parse() {
+                Ok(hotkey) => hotkey,
+                Err(err) => {
+                    warn!("invalid global hotkey `{}`: {}", key, err);
+                    continue;
+                }
+            };
+            match manager.register(hotkey) {
+                Ok(()) => bindings.push((hotkey.id(), action)),
+                Err(err) => warn!("failed to register global hotkey `{}`: {}", key, err),
+            }
+        }
+        (!bindings.is_empty()).then(|| Self {
+            _manager: manager,
+            bindings,
+        })
+    }
+
+    /// returns the actions of the hotkeys, that were pressed since the last call
+    pub fn poll(&self) -> Vec<Action> {
+        GlobalHotKeyEvent::receiver()
+            .try_iter()
+            .filter_map(|event| {
+                self.bindings
+                    .iter()
+                    .find(|(id, _)| *id == event.id)
+                    .map(|(_, action)| *action)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use symphonia::core::audio::SignalSpec;
+
+//------------------------------------------------------------------//
+//                          LoudnessMeter                           //
+//------------------------------------------------------------------//
+
+/// Number of 100ms steps in a gating block (400ms)
+const BLOCK_STEPS: usize = 4;
+/// Number of 100ms steps in the short-term window (3s)
+const SHORT_TERM_STEPS: usize = 30;
+/// Blocks below this loudness are ignored for the integrated loudness
+const ABSOLUTE_GATE: f64 = -70.;
+/// Blocks more than this far below the ungated loudness are ignored for the integrated loudness
+const RELATIVE_GATE: f64 = -10.;
+
+/// A second order IIR filter section
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// Measures the loudness of a signal in LUFS as described in ITU-R BS.1770. All channels are
+/// weighted equally.
+pub struct LoudnessMeter {
+    spec: SignalSpec,
+    /// K-weighting filters per channel
+    filters: Vec<[Biquad; 2]>,
+    /// number of frames per 100ms step
+    step_frames: usize,
+    /// sum of the weighted squares of the current step
+    step_sum: f64,
+    /// number of frames in the current step
+    step_len: usize,
+    /// mean square of the last steps, newest last
+    steps: VecDeque<f64>,
+    /// mean square of every gating block since the meter was created
+    blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(spec: SignalSpec) -> Self {
+        let rate = spec.rate as f64;
+        // high shelf, that models the head
+        let k = (PI * 1681.974450955533 / rate).tan();
+        let q = 0.7071752369554196;
+        let vh = 10f64.powf(3.999843853973347 / 20.);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1. + k / q + k * k;
+        let shelf = Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2. * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+            z: [0.; 2],
+        };
+        // high pass, that removes the lowest frequencies
+        let k = (PI * 38.13547087602444 / rate).tan();
+        let q = 0.5003270373238773;
+        let a0 = 1. + k / q + k * k;
+        let high_pass = Biquad {
+            b: [1., -2., 1.],
+            a: [2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+            z: [0.; 2],
+        };
+        Self {
+            spec,
+            filters: vec![[shelf, high_pass]; spec.channels.count()],
+            step_frames: (spec.rate / 10) as usize,
+            step_sum: 0.,
+            step_len: 0,
+            steps: VecDeque::with_capacity(SHORT_TERM_STEPS),
+            blocks: vec![],
+        }
+    }
+
+    /// returns the signal spec, that the meter was created for
+    pub fn spec(&self) -> SignalSpec {
+        self.spec
+    }
+
+    /// feeds interleaved samples into the meter
+    pub fn process<I: Iterator<Item = f32>>(&mut self, samples: I) {
+        let num_channels = self.filters.len();
+        for (i, sample) in samples.enumerate() {
+            let [shelf, high_pass] = &mut self.filters[i % num_channels];
+            let weighted = high_pass.process(shelf.process(sample as f64));
+            self.step_sum += weighted * weighted;
+            if i % num_channels == num_channels - 1 {
+                self.step_len += 1;
+                if self.step_len == self.step_frames {
+                    self.finish_step();
+                }
+            }
+        }
+    }
+
+    /// returns the loudness of the last three seconds
+    pub fn short_term(&self) -> Option<f64> {
+        if self.steps.is_empty() {
+            return None;
+        }
+        let power = self.steps.iter().sum::<f64>() / self.steps.len() as f64;
+        Some(loudness(power))
+    }
+
+    /// returns the gated loudness since the meter was created
+    pub fn integrated(&self) -> Option<f64> {
+        let above_absolute: Vec<f64> = self
+            .blocks
+            .iter()
+            .copied()
+            .filter(|power| loudness(*power) > ABSOLUTE_GATE)
+            .collect();
+        if above_absolute.is_empty() {
+            return None;
+        }
+        let gate = loudness(mean(&above_absolute)) + RELATIVE_GATE;
+        let above_relative: Vec<f64> = above_absolute
+            .into_iter()
+            .filter(|power| loudness(*power) > gate)
+            .collect();
+        if above_relative.is_empty() {
+            return None;
+        }
+        Some(loudness(mean(&above_relative)))
+    }
+
+    /// stores the mean square of a finished 100ms step and the gating block, that ends with it
+    fn finish_step(&mut self) {
+        if self.steps.len() == SHORT_TERM_STEPS {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(self.step_sum / self.step_len as f64);
+        self.step_sum = 0.;
+        self.step_len = 0;
+        // gating blocks overlap by 75%
+        if self.steps.len() >= BLOCK_STEPS {
+            let block = self.steps.iter().rev().take(BLOCK_STEPS).sum::<f64>() / BLOCK_STEPS as f64;
+            self.blocks.push(block);
+        }
+    }
+}
+
+/// converts a mean square into LUFS
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10. * power.log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
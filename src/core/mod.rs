@@ -1,2 +1,21 @@
+pub mod analysis_cache;
 pub mod analyzer;
+pub mod bpm_window;
+pub mod chapters;
+pub mod clipboard;
+pub mod columns;
+pub mod config;
+pub mod glob;
+#[cfg(feature = "global-hotkeys")]
+pub mod hotkeys;
+pub mod loudness;
+pub mod mounts;
 pub mod player;
+pub mod prelisten;
+pub mod read_ahead;
+pub mod probe;
+pub mod sampler;
+pub mod sink;
+pub mod tags;
+pub mod test_signal;
+pub mod watcher;
@@ -0,0 +1,68 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::PathBuf,
+    sync::mpsc::Sender,
+    thread::{sleep, spawn, JoinHandle},
+    time::Duration,
+};
+
+//------------------------------------------------------------------//
+//                          MountWatcher                            //
+//------------------------------------------------------------------//
+
+/// Time between two looks at the mounted file systems
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Folders, below which removable drives get mounted
+const REMOVABLE_PREFIXES: [&str; 3] = ["/media/", "/run/media/", "/mnt/"];
+
+pub enum Event {
+    /// A removable drive was mounted at the given folder
+    Mounted(PathBuf),
+    /// The removable drive at the given folder was unmounted
+    Unmounted(PathBuf),
+}
+
+/// polls the mounted file systems and reports removable drives, that come and go. Drives, that
+/// are already mounted on start, are not reported. Polling stops, once the receiver is dropped
+pub fn watch(event_out: Sender<Event>) -> JoinHandle<()> {
+    spawn(move || {
+        let mut mounted = removable_mounts();
+        loop {
+            sleep(POLL_INTERVAL);
+            let current = removable_mounts();
+            let events = current
+                .difference(&mounted)
+                .map(|path| Event::Mounted(path.clone()))
+                .chain(
+                    mounted
+                        .difference(&current)
+                        .map(|path| Event::Unmounted(path.clone())),
+                );
+            for event in events {
+                if event_out.send(event).is_err() {
+                    return;
+                }
+            }
+            mounted = current;
+        }
+    })
+}
+
+/// returns the mount points of removable drives, as listed in `/proc/mounts`
+fn removable_mounts() -> HashSet<PathBuf> {
+    let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        // spaces in mount points are escaped as \040
+        .map(|mount_point| mount_point.replace("\\040", " "))
+        .filter(|mount_point| {
+            REMOVABLE_PREFIXES
+                .iter()
+                .any(|prefix| mount_point.starts_with(prefix))
+        })
+        .map(PathBuf::from)
+        .collect()
+}
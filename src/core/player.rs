@@ -1,19 +1,26 @@
+use std::io;
 use std::sync::{Arc, Mutex};
-use std::thread::{spawn, JoinHandle};
+use std::thread::{sleep, spawn, JoinHandle};
+use std::time::{Duration, Instant};
 
+use crate::core::config::Config;
+use crate::core::loudness::LoudnessMeter;
 use crate::core::player;
-use libpulse_binding as pulse;
-use libpulse_simple_binding as psimple;
+use crate::core::probe;
+use crate::core::read_ahead::{ReadAhead, ReadAheadHandle};
+use crate::core::sampler::Voice;
+use crate::core::sink::{AudioSink, OpenSink, PulseSink};
 
 use log::warn;
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use symphonia::core::audio::RawSampleBuffer;
-use symphonia::core::audio::{Channels, SignalSpec};
+use symphonia::core::audio::SignalSpec;
 use symphonia::core::codecs::DecoderOptions;
 use symphonia::core::codecs::{CodecParameters, Decoder};
+use symphonia::core::errors::Error;
 use symphonia::core::formats::FormatReader;
 use symphonia::core::formats::{FormatOptions, Track};
-use symphonia::core::io::MediaSourceStream;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use symphonia::core::units::{Time, TimeStamp};
@@ -23,18 +30,118 @@ pub enum Message {
     Load(String),
     /// Toggle playback
     TogglePlay,
+    /// Pause playback, if it is playing
+    Pause,
     /// Same as Cue button on CDJ
     Cue,
+    /// The Cue button was released
+    CueRelease,
     /// Skip forward a number of millis
     SkipForward(Time),
     /// Skip backwards a number of millis
     SkipBackward(Time),
+    /// Move the playhead to a position
+    JumpTo(TimeMarker),
+    /// Switch playback to another audio track of the loaded file, given by its track id
+    SelectStream(u32),
     /// Get missing preview Data. The parameter tells the player how many preview samples the app
     /// already has
     GetPreview(usize),
+    /// Stop playback after the given duration in the given way, or cancel the sleep timer
+    SetSleepTimer(Option<(Duration, SleepMode)>),
+    /// Loop between point A and point B, or stop repeating
+    SetRepeat(Option<(TimeMarker, TimeMarker)>),
+    /// Leave the A-B repeat in the given way
+    ExitRepeat(RepeatExit),
+    /// Set the volume of the booth output
+    SetBoothVolume(f32),
+    /// Measure the latency of the output
+    MeasureLatency,
+    /// Mix a triggered sample into the output
+    TriggerSample(Voice),
 }
 
-pub enum Event {}
+pub enum Event {
+    /// The playing track has less than the configured number of seconds left
+    EndOfTrackWarning(f64),
+    /// The sleep timer ran out and playback was stopped
+    SleepTimerExpired,
+    /// The output stream was (re)opened with the given signal spec. Samples are always written
+    /// as 32 bit floats, so the sound server does the only quantization step.
+    OutputFormat(SignalSpec),
+    /// Opening a file for playback started
+    LoadStarted(String),
+    /// The file was opened and is ready for playback
+    Loaded(String),
+    /// The file could not be opened. Holds the reason
+    LoadFailed(String, String),
+    /// Writing to the output device failed, playback was paused
+    OutputLost,
+    /// The output was reopened on the default device after it was lost
+    OutputRestored,
+    /// Playback waits for slow storage to fill the read-ahead buffer, or continues afterwards
+    Buffering(bool),
+    /// Loudness of the output in LUFS: short-term and integrated since the output format last
+    /// changed
+    Loudness(Option<f64>, Option<f64>),
+    /// Load of the player over the last second: the share of the played audio's duration, that
+    /// was spent decoding and processing it, and the seconds of audio decoded per second
+    Stats(f64, f64),
+    /// The latency of the output, as reported by the output device. None, if no output is open
+    /// or the device does not report it
+    Latency(Option<Duration>),
+    /// The loaded track was played long enough to count as played
+    Played,
+    /// Playback passed point B of the A-B repeat, after leaving it at its end was requested
+    RepeatExited,
+}
+
+/// Ways to leave an A-B repeat
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RepeatExit {
+    /// play on past point B, once it is reached
+    AtEnd,
+    /// stop repeating right away, playback continues where it is
+    Immediately,
+    /// stop repeating and jump ahead by the length of the loop, so playback continues where it
+    /// would be without the loop
+    Roll,
+}
+
+/// Ways, in which the sleep timer stops playback
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SleepMode {
+    /// fade out over the last seconds of the timer and stop, once it runs out
+    FadeOut,
+    /// play the track, that plays when the timer runs out, to its end and stop there. An A-B
+    /// repeat is left at point B
+    AfterTrack,
+}
+
+/// Duration over which playback fades out before the sleep timer stops it
+const SLEEP_TIMER_FADE_OUT: Duration = Duration::from_secs(10);
+
+/// Time between two attempts to reopen a lost output device
+const OUTPUT_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time to wait for the read-ahead buffer, before checking it again
+const BUFFERING_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Time to wait for a message, while nothing plays, before checking the pending load, the output
+/// and the timers again
+const IDLE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Time between two loudness reports
+const LOUDNESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time between two reports of the player load
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Seconds of playback, after which a track counts as played
+const PLAYED_AFTER: f64 = 30.;
+
+/// Frames, that are written at once, while triggered samples play without the track
+const VOICE_BLOCK_FRAMES: usize = 512;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum PlayerState {
@@ -76,6 +183,13 @@ impl TimeMarker {
         Self { ts, codec_params }
     }
 
+    pub fn from_seconds(seconds: f64, codec_params: CodecParameters) -> Self {
+        let seconds = seconds.max(0.);
+        let time = Time::new(seconds.trunc() as u64, seconds.fract());
+        let ts = codec_params.time_base.unwrap().calc_timestamp(time);
+        Self { ts, codec_params }
+    }
+
     fn skip(&mut self, offset: Time, direction: SkipDirection) {
         let current = self.codec_params.time_base.unwrap().calc_time(self.ts);
         let new_time = match direction {
@@ -131,10 +245,25 @@ impl TimeMarker {
         (time.seconds as f64) + (time.frac)
     }
 
-    pub fn get_progress(&self) -> f64 {
-        let t_dur = self.codec_params.n_frames.unwrap() as f64
-            / self.codec_params.sample_rate.unwrap() as f64;
-        self.get_time_in_seconds() / t_dur
+    /// returns the length of the track, if the file states it
+    pub fn get_duration_in_seconds(&self) -> Option<f64> {
+        Some(self.codec_params.n_frames? as f64 / self.codec_params.sample_rate? as f64)
+    }
+
+    /// returns a marker, that is the given number of seconds later
+    pub fn advanced_by(&self, seconds: f64) -> Self {
+        Self::from_seconds(
+            self.get_time_in_seconds() + seconds,
+            self.codec_params.clone(),
+        )
+    }
+
+    pub fn get_remaining_seconds(&self) -> Option<f64> {
+        Some((self.get_duration_in_seconds()? - self.get_time_in_seconds()).max(0.))
+    }
+
+    pub fn get_progress(&self) -> Option<f64> {
+        Some(self.get_time_in_seconds() / self.get_duration_in_seconds()?)
     }
 }
 
@@ -145,16 +274,78 @@ pub struct Player {
     position_marker: Arc<Mutex<Option<TimeMarker>>>,
     /// cue point as a TimeMarker
     cue_point_marker: Option<TimeMarker>,
+    /// whether playback only lasts, while the cue button is held
+    cue_preview: bool,
+    /// timestamp, that the last seek went to. Frames before it are dropped from the packet, that
+    /// contains it, so playback starts exactly there
+    trim_to: Option<TimeStamp>,
     /// Formatreader
     reader: Option<Box<dyn FormatReader>>,
     /// Decoder
     decoder: Option<Box<dyn Decoder>>,
-    /// PulseAudio output
-    output: Option<psimple::Simple>,
+    /// audio output
+    output: Option<Box<dyn AudioSink>>,
+    /// audio output on the booth device, that mirrors the main output
+    booth: Option<Box<dyn AudioSink>>,
+    /// opens the outputs
+    open_sink: OpenSink,
+    /// volume of the booth output
+    booth_volume: f32,
+    /// triggered samples, that are mixed into the output
+    voices: Vec<Voice>,
     /// Signal Spec
     spec: Option<SignalSpec>,
     /// Symphonia track information
     track: Option<Track>,
+    /// length of the track in seconds, if the container tells it
+    duration: Option<f64>,
+    /// user settings
+    config: Config,
+    /// whether the end-of-track warning was already sent for the current position
+    end_of_track_warned: bool,
+    /// seconds of the loaded track, that were played so far
+    played: f64,
+    /// whether the loaded track was already reported as played
+    play_reported: bool,
+    /// point in time, at which the sleep timer stops playback, and the way it does
+    sleep_timer: Option<(Instant, SleepMode)>,
+    /// whether playback stops at the end of the track, because the sleep timer ran out
+    stop_after_track: bool,
+    /// A-B repeat points
+    repeat: Option<(TimeMarker, TimeMarker)>,
+    /// whether the repeat is left, once point B is reached
+    exit_repeat_at_end: bool,
+    /// whether the repeat was left at point B, but the app wasn't told yet
+    repeat_exited: bool,
+    /// signal spec of the output stream, that was last reported to the app
+    reported_spec: Option<SignalSpec>,
+    /// point in time of the last attempt to reopen a lost output
+    reconnect_attempt: Option<Instant>,
+    /// file, that is being opened in the background
+    pending_load: Option<(String, Receiver<Result<OpenedFile, Error>>)>,
+    /// fill level of the read-ahead buffer of the loaded file
+    read_ahead: Option<ReadAheadHandle>,
+    /// whether playback waits for the read-ahead buffer
+    buffering: bool,
+    /// loudness of the samples written to the output
+    loudness: Option<LoudnessMeter>,
+    /// point in time, at which the loudness was last reported to the app
+    loudness_reported: Instant,
+    /// point in time, at which the load was last reported to the app
+    stats_reported: Instant,
+    /// time spent decoding and processing audio since the last load report
+    busy: Duration,
+    /// seconds of audio decoded since the last load report
+    decoded: f64,
+}
+
+/// A file, that was opened for playback
+struct OpenedFile {
+    reader: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track: Track,
+    spec: SignalSpec,
+    read_ahead: Option<ReadAheadHandle>,
 }
 
 impl Player {
@@ -168,26 +359,70 @@ impl Player {
         player_position: Arc<Mutex<Option<TimeMarker>>>,
         player_message_in: Receiver<player::Message>,
         player_event_out: Sender<player::Event>,
+        config: Config,
     ) -> JoinHandle<()> {
         // The async channel for Events from the reader
         // Start the command handler thread
+        Player::spawn_with_sink(
+            player_position,
+            player_message_in,
+            player_event_out,
+            config,
+            Box::new(PulseSink::open),
+        )
+    }
+
+    /// like `spawn`, but plays on the outputs, that `open_sink` opens instead of PulseAudio
+    pub fn spawn_with_sink(
+        player_position: Arc<Mutex<Option<TimeMarker>>>,
+        player_message_in: Receiver<player::Message>,
+        player_event_out: Sender<player::Event>,
+        config: Config,
+        open_sink: OpenSink,
+    ) -> JoinHandle<()> {
         spawn(move || {
-            let mut player = Player::new(player_position);
+            let mut player = Player::new(player_position, config, open_sink);
             player.event_loop(player_message_in, player_event_out)
         })
     }
 
-    fn new(position: Arc<Mutex<Option<TimeMarker>>>) -> Self {
+    fn new(position: Arc<Mutex<Option<TimeMarker>>>, config: Config, open_sink: OpenSink) -> Self {
         // the frame buffer. TODO: use sensible vector sizes
         Self {
             state: PlayerState::Unloaded,
             reader: None,
             decoder: None,
             output: None,
+            booth: None,
+            open_sink,
+            booth_volume: 1.,
+            voices: vec![],
             spec: None,
             track: None,
+            duration: None,
             cue_point_marker: None,
+            cue_preview: false,
+            trim_to: None,
             position_marker: position,
+            config,
+            end_of_track_warned: false,
+            played: 0.,
+            play_reported: false,
+            sleep_timer: None,
+            stop_after_track: false,
+            repeat: None,
+            exit_repeat_at_end: false,
+            repeat_exited: false,
+            reported_spec: None,
+            reconnect_attempt: None,
+            pending_load: None,
+            read_ahead: None,
+            buffering: false,
+            loudness: None,
+            loudness_reported: Instant::now(),
+            stats_reported: Instant::now(),
+            busy: Duration::ZERO,
+            decoded: 0.,
         }
     }
 
@@ -197,48 +432,337 @@ impl Player {
         player_event_out: Sender<player::Event>,
     ) {
         while self.state != PlayerState::Closed {
+            // block for a while, when there is nothing to play, instead of spinning
+            let message = if self.is_idle() {
+                player_message_in.recv_timeout(IDLE_INTERVAL).ok()
+            } else {
+                player_message_in.try_recv().ok()
+            };
             // command handlers
-            match player_message_in.try_recv() {
+            match message {
                 //------------------------------------------------------------------//
                 //                           App Messages                           //
                 //------------------------------------------------------------------//
-                Ok(Message::Load(path)) => {
+                Some(Message::Load(path)) => {
                     // Communicate to the reader, that we want to load a track
-                    self.load(path);
+                    self.start_loading(path, &player_event_out);
                 }
-                Ok(Message::TogglePlay) => {
+                Some(Message::TogglePlay) => {
                     self.toggle_play();
                 }
-                Ok(Message::Cue) => {
+                Some(Message::Pause) => {
+                    self.stop_playing();
+                }
+                Some(Message::Cue) => {
                     self.cue();
                 }
-                Ok(Message::SkipForward(time)) => {
+                Some(Message::CueRelease) => {
+                    self.cue_release();
+                }
+                Some(Message::SkipForward(time)) => {
                     self.skip(time, SkipDirection::Forward);
                 }
-                Ok(Message::SkipBackward(time)) => {
+                Some(Message::SkipBackward(time)) => {
                     self.skip(time, SkipDirection::Backward);
                 }
-                Ok(_msg) => {
+                Some(Message::JumpTo(marker)) => {
+                    self.jump_to(marker);
+                }
+                Some(Message::SelectStream(track_id)) => {
+                    self.select_stream(track_id);
+                }
+                Some(Message::SetSleepTimer(timer)) => {
+                    self.sleep_timer = timer.map(|(d, mode)| (Instant::now() + d, mode));
+                    self.stop_after_track = false;
+                }
+                Some(Message::SetRepeat(repeat)) => {
+                    self.repeat = repeat;
+                    self.exit_repeat_at_end = false;
+                }
+                Some(Message::ExitRepeat(exit)) => {
+                    self.exit_repeat(exit);
+                }
+                Some(Message::SetBoothVolume(volume)) => {
+                    self.booth_volume = volume;
+                }
+                Some(Message::MeasureLatency) => {
+                    let latency = self.output.as_ref().and_then(|output| output.latency());
+                    let _ = player_event_out.send(Event::Latency(latency));
+                }
+                Some(Message::TriggerSample(voice)) => {
+                    self.voices.push(voice);
+                }
+                Some(_msg) => {
                     todo!()
                 }
-                Err(_) => {
+                None => {
                     // This happens, when there are still outstanding channels, but the message
                     // queue is empty, so just ignore this
                 }
             }
+            self.check_pending_load(&player_event_out);
+            self.check_output(&player_event_out);
+            self.report_output_format(&player_event_out);
+            self.check_sleep_timer(&player_event_out);
+            self.report_stats(&player_event_out);
             // play buffered packets
             if let PlayerState::Playing = self.state {
                 if let Some(_) = &mut self.output {
-                    self.play();
+                    if self.check_buffering(&player_event_out) {
+                        // don't block in the middle of a packet, while slow storage catches up
+                        sleep(BUFFERING_INTERVAL);
+                        continue;
+                    }
+                    if let Err(err) = self.play() {
+                        self.end_track(err, &player_event_out);
+                    }
+                    if self.output.is_none() {
+                        let _ = player_event_out.send(Event::OutputLost);
+                    }
+                    self.check_end_of_track(&player_event_out);
+                    self.check_played(&player_event_out);
+                    self.check_repeat_exited(&player_event_out);
+                    self.report_loudness(&player_event_out);
+                }
+            } else if !self.voices.is_empty() {
+                self.play_voices();
+            }
+        }
+    }
+
+    /// returns whether there is nothing to write to the output
+    fn is_idle(&self) -> bool {
+        let playing = self.state == PlayerState::Playing && self.output.is_some();
+        !playing && self.voices.is_empty()
+    }
+
+    /// reopens the output on the default device, after it was lost or could not be opened
+    fn check_output(&mut self, player_event_out: &Sender<player::Event>) {
+        if self.output.is_some() || self.spec.is_none() {
+            return;
+        }
+        if let Some(attempt) = self.reconnect_attempt {
+            if attempt.elapsed() < OUTPUT_RECONNECT_INTERVAL {
+                return;
+            }
+        }
+        self.reconnect_attempt = Some(Instant::now());
+        if self.init_output().is_ok() {
+            self.reconnect_attempt = None;
+            let _ = player_event_out.send(Event::OutputRestored);
+        }
+    }
+
+    /// tells the app about the format of the output stream, whenever it changed
+    fn report_output_format(&mut self, player_event_out: &Sender<player::Event>) {
+        if self.spec != self.reported_spec {
+            self.reported_spec = self.spec;
+            if let Some(spec) = self.spec {
+                let _ = player_event_out.send(Event::OutputFormat(spec));
+            }
+        }
+    }
+
+    /// holds back playback, while the read-ahead buffer runs empty and until it is filled again.
+    /// Returns true, while buffering
+    fn check_buffering(&mut self, player_event_out: &Sender<player::Event>) -> bool {
+        let read_ahead = match &self.read_ahead {
+            Some(read_ahead) => read_ahead,
+            None => return false,
+        };
+        if self.buffering && read_ahead.is_filled() {
+            self.buffering = false;
+            let _ = player_event_out.send(Event::Buffering(false));
+        } else if !self.buffering && read_ahead.is_starved() {
+            self.buffering = true;
+            let _ = player_event_out.send(Event::Buffering(true));
+        }
+        self.buffering
+    }
+
+    /// tells the app about the loudness of the output in regular intervals
+    fn report_loudness(&mut self, player_event_out: &Sender<player::Event>) {
+        if self.loudness_reported.elapsed() < LOUDNESS_INTERVAL {
+            return;
+        }
+        self.loudness_reported = Instant::now();
+        if let Some(meter) = &self.loudness {
+            let _ = player_event_out.send(Event::Loudness(meter.short_term(), meter.integrated()));
+        }
+    }
+
+    /// tells the app about the load of the player in regular intervals
+    fn report_stats(&mut self, player_event_out: &Sender<player::Event>) {
+        let elapsed = self.stats_reported.elapsed();
+        if elapsed < STATS_INTERVAL {
+            return;
+        }
+        let load = if self.decoded > 0. {
+            self.busy.as_secs_f64() / self.decoded
+        } else {
+            0.
+        };
+        let _ = player_event_out.send(Event::Stats(load, self.decoded / elapsed.as_secs_f64()));
+        self.stats_reported = Instant::now();
+        self.busy = Duration::ZERO;
+        self.decoded = 0.;
+    }
+
+    /// stops playback once the sleep timer ran out, or lets the track play to its end
+    fn check_sleep_timer(&mut self, player_event_out: &Sender<player::Event>) {
+        if let Some((deadline, mode)) = self.sleep_timer {
+            if Instant::now() >= deadline {
+                self.sleep_timer = None;
+                match mode {
+                    SleepMode::FadeOut => {
+                        self.stop_playing();
+                        let _ = player_event_out.send(Event::SleepTimerExpired);
+                    }
+                    SleepMode::AfterTrack => {
+                        self.stop_after_track = true;
+                        self.exit_repeat_at_end = self.repeat.is_some();
+                    }
                 }
             }
         }
     }
-    fn load(&mut self, path: String) {
-        self.init_reader(path);
-        self.init_decoder();
-        self.init_output();
+
+    /// pauses playback, once the stream of the track ended or can't be read anymore
+    fn end_track(&mut self, err: Error, player_event_out: &Sender<player::Event>) {
+        match err {
+            Error::IoError(err) if err.kind() == io::ErrorKind::UnexpectedEof => {}
+            err => warn!("failed to read the next packet: {}", err),
+        }
+        self.stop_playing();
+        if self.stop_after_track {
+            self.stop_after_track = false;
+            let _ = player_event_out.send(Event::SleepTimerExpired);
+        }
+    }
+
+    /// returns the gain applied to the output. Playback fades out during the last seconds
+    /// of the sleep timer, unless it plays the track to its end
+    fn output_gain(&self) -> f32 {
+        match self.sleep_timer {
+            Some((deadline, SleepMode::FadeOut)) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                (remaining.as_secs_f32() / SLEEP_TIMER_FADE_OUT.as_secs_f32()).min(1.)
+            }
+            _ => 1.,
+        }
+    }
+
+    /// sends an end-of-track warning once the remaining time drops below the configured
+    /// threshold. The warning is re-armed, when the playhead moves out of the warning range.
+    fn check_end_of_track(&mut self, player_event_out: &Sender<player::Event>) {
+        // without a known length, there is no end to warn about
+        let duration = match self.duration {
+            Some(duration) => duration,
+            None => return,
+        };
+        if let Some(pos) = &(*self.position_marker.lock().unwrap()) {
+            let remaining = (duration - pos.get_time_in_seconds()).max(0.);
+            if remaining > self.config.end_of_track_warning {
+                self.end_of_track_warned = false;
+            } else if !self.end_of_track_warned {
+                self.end_of_track_warned = true;
+                let _ = player_event_out.send(Event::EndOfTrackWarning(remaining));
+            }
+        }
+    }
+    /// reports the loaded track as played, once enough of it was played
+    fn check_played(&mut self, player_event_out: &Sender<player::Event>) {
+        if !self.play_reported && self.played >= PLAYED_AFTER {
+            self.play_reported = true;
+            let _ = player_event_out.send(Event::Played);
+        }
+    }
+
+    /// tells the app, that the repeat was left at point B
+    fn check_repeat_exited(&mut self, player_event_out: &Sender<player::Event>) {
+        if self.repeat_exited {
+            self.repeat_exited = false;
+            let _ = player_event_out.send(Event::RepeatExited);
+        }
+    }
+
+    /// leaves the A-B repeat
+    fn exit_repeat(&mut self, exit: RepeatExit) {
+        match exit {
+            RepeatExit::AtEnd => self.exit_repeat_at_end = self.repeat.is_some(),
+            RepeatExit::Immediately => self.repeat = None,
+            RepeatExit::Roll => {
+                if let Some((a, b)) = self.repeat.take() {
+                    let length = b.get_time_in_seconds() - a.get_time_in_seconds();
+                    let position = self.position_marker.lock().unwrap().clone();
+                    if let Some(position) = position {
+                        self.jump_to(position.advanced_by(length));
+                    }
+                }
+            }
+        }
+    }
+
+    /// opens a file in a background thread, so the player keeps running while large files are
+    /// probed. A pending load gets cancelled by dropping its channel.
+    fn start_loading(&mut self, path: String, player_event_out: &Sender<player::Event>) {
+        let (loaded_out, loaded_in) = channel();
+        let file_path = path.clone();
+        let read_ahead = self.config.read_ahead * 1024;
+        spawn(move || {
+            let _ = loaded_out.send(Player::open_file(&file_path, read_ahead));
+        });
+        self.pending_load = Some((path.clone(), loaded_in));
+        let _ = player_event_out.send(Event::LoadStarted(path));
+    }
+
+    /// switches to the pending file, once it was opened
+    fn check_pending_load(&mut self, player_event_out: &Sender<player::Event>) {
+        let res = match &self.pending_load {
+            Some((_, loaded_in)) => match loaded_in.try_recv() {
+                Ok(res) => res,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => Err(Error::Unsupported("loader stopped")),
+            },
+            None => return,
+        };
+        let path = match self.pending_load.take() {
+            Some((path, _)) => path,
+            None => return,
+        };
+        match res {
+            Ok(file) => {
+                self.load(file);
+                let _ = player_event_out.send(Event::Loaded(path));
+            }
+            Err(err) => {
+                warn!("failed to load {}: {}", path, err);
+                let _ = player_event_out.send(Event::LoadFailed(path, err.to_string()));
+            }
+        }
+    }
+
+    fn load(&mut self, file: OpenedFile) {
+        self.reader = Some(file.reader);
+        self.decoder = Some(file.decoder);
+        self.duration = duration(&file.track);
+        self.track = Some(file.track);
+        self.spec = Some(file.spec);
+        self.read_ahead = file.read_ahead;
+        self.buffering = false;
+        // keep measuring across tracks, the meter only restarts if the output format changes
+        if self.loudness.as_ref().map(|meter| meter.spec()) != self.spec {
+            self.loudness = Some(LoudnessMeter::new(file.spec));
+        }
+        let _ = self.init_output();
         self.state = PlayerState::Paused;
+        self.end_of_track_warned = false;
+        self.played = 0.;
+        self.play_reported = false;
+        self.repeat = None;
+        self.exit_repeat_at_end = false;
+        self.cue_preview = false;
+        self.trim_to = None;
         if let Some(track) = &self.track {
             *self.position_marker.lock().unwrap() =
                 Some(TimeMarker::new(track.codec_params.clone()));
@@ -246,30 +770,56 @@ impl Player {
         }
     }
 
+    /// CDJ style cue: while paused at the cue point, playback previews from it, as long as the
+    /// button is held. While paused elsewhere, the cue point is set to the playhead. While
+    /// playing, the playhead returns to the cue point and playback stops
     fn cue(&mut self) {
-        if self.state != PlayerState::Playing {
-            let curr_position = &(*self.position_marker.lock().unwrap());
-            if let (Some(curr_position), Some(curr_cue)) = (curr_position, &self.cue_point_marker) {
-                if curr_position == curr_cue {
+        match self.state {
+            // the held button repeats, while previewing
+            PlayerState::Playing if self.cue_preview => {}
+            PlayerState::Playing => {
+                self.return_to_cue();
+                self.state = PlayerState::Paused;
+                self.pause();
+            }
+            PlayerState::Paused => {
+                let curr_position = (*self.position_marker.lock().unwrap()).clone();
+                if curr_position.is_some() && curr_position == self.cue_point_marker {
                     self.state = PlayerState::Playing;
+                    self.cue_preview = true;
+                } else {
+                    // set cue new point
+                    self.cue_point_marker = curr_position;
                 }
             }
-            // set cue new point
-            self.cue_point_marker = curr_position.to_owned();
-        } else {
-            // return to last cue point
-            if let (Some(track), Some(reader), Some(cue)) =
-                (&self.track, &mut self.reader, &self.cue_point_marker)
-            {
-                *self.position_marker.lock().unwrap() = self.cue_point_marker.clone();
-                reader.seek(
-                    symphonia::core::formats::SeekMode::Accurate,
-                    symphonia::core::formats::SeekTo::TimeStamp {
-                        ts: cue.ts,
-                        track_id: track.id,
-                    },
-                );
-            }
+            PlayerState::Unloaded | PlayerState::Closed => {}
+        }
+    }
+
+    /// ends the cue preview, which snaps the playhead back to the cue point
+    fn cue_release(&mut self) {
+        if self.cue_preview {
+            self.cue_preview = false;
+            self.state = PlayerState::Paused;
+            self.pause();
+            self.return_to_cue();
+        }
+    }
+
+    /// moves the playhead to the cue point
+    fn return_to_cue(&mut self) {
+        if let (Some(track), Some(reader), Some(cue)) =
+            (&self.track, &mut self.reader, &self.cue_point_marker)
+        {
+            *self.position_marker.lock().unwrap() = self.cue_point_marker.clone();
+            let _ = reader.seek(
+                symphonia::core::formats::SeekMode::Accurate,
+                symphonia::core::formats::SeekTo::TimeStamp {
+                    ts: cue.ts,
+                    track_id: track.id,
+                },
+            );
+            self.trim_to = Some(cue.ts);
         }
     }
 
@@ -279,7 +829,21 @@ impl Player {
         }
     }
 
+    /// pauses playback, if it is playing
+    fn stop_playing(&mut self) {
+        self.cue_preview = false;
+        if self.state == PlayerState::Playing {
+            self.state = PlayerState::Paused;
+            self.pause();
+        }
+    }
+
     fn toggle_play(&mut self) {
+        // pressing play during the cue preview keeps playing after the cue button is released
+        if self.cue_preview {
+            self.cue_preview = false;
+            return;
+        }
         // check if audio output is valid
         if let Some(_) = &mut self.output {
             match self.state {
@@ -316,32 +880,153 @@ impl Player {
                     track_id,
                 },
             );
+            self.trim_to = Some(playhead.ts);
+        }
+    }
+
+    /// move the playhead to a given position
+    fn jump_to(&mut self, marker: TimeMarker) {
+        if let (Some(track), Some(reader)) = (&self.track, &mut self.reader) {
+            let ts = marker.get_timestamp();
+            *self.position_marker.lock().unwrap() = Some(marker);
+            let _ = reader.seek(
+                symphonia::core::formats::SeekMode::Accurate,
+                symphonia::core::formats::SeekTo::TimeStamp {
+                    ts,
+                    track_id: track.id,
+                },
+            );
+            self.trim_to = Some(ts);
+        }
+    }
+
+    /// switch playback to another audio track of the loaded file, keeping the playhead position
+    fn select_stream(&mut self, track_id: u32) {
+        let track = match &self.reader {
+            Some(reader) => match reader.tracks().iter().find(|t| t.id == track_id) {
+                Some(track) => track.clone(),
+                None => return,
+            },
+            None => return,
+        };
+        let dec_opts: DecoderOptions = DecoderOptions { verify: false };
+        match symphonia::default::get_codecs().make(&track.codec_params, &dec_opts) {
+            Ok(decoder) => {
+                let seconds = (*self.position_marker.lock().unwrap())
+                    .as_ref()
+                    .map(|pos| pos.get_time_in_seconds())
+                    .unwrap_or(0.);
+                let marker = TimeMarker::from_seconds(seconds, track.codec_params.clone());
+                self.decoder = Some(decoder);
+                // the new stream might need a different output format
+                if let (Some(rate), Some(channels)) =
+                    (track.codec_params.sample_rate, track.codec_params.channels)
+                {
+                    let spec = SignalSpec::new(rate, channels);
+                    if self.spec != Some(spec) {
+                        self.spec = Some(spec);
+                        let _ = self.init_output();
+                    }
+                }
+                self.duration = duration(&track);
+                self.track = Some(track);
+                self.jump_to(marker);
+            }
+            Err(err) => {
+                warn!("failed to select stream {}: {}", track_id, err);
+            }
         }
     }
 
     fn play(&mut self) -> Result<(), symphonia::core::errors::Error> {
+        let started = Instant::now();
+        let gain = self.output_gain();
         match (&mut self.reader, &mut self.decoder, &mut self.output) {
             (Some(reader), Some(decoder), Some(out)) => {
                 let packet = reader.next_packet()?;
-                if let Some(pos) = &mut (*self.position_marker.lock().unwrap()) {
-                    pos.go_to_timestamp(packet.ts());
+                // skip packets of other streams in the container
+                if let Some(track) = &self.track {
+                    if packet.track_id() != track.id {
+                        return Ok(());
+                    }
+                }
+                // play on past point B, if the repeat is left there
+                if self.exit_repeat_at_end
+                    && self
+                        .repeat
+                        .as_ref()
+                        .is_some_and(|(_, b)| packet.ts() >= b.get_timestamp())
+                {
+                    self.repeat = None;
+                    self.exit_repeat_at_end = false;
+                    self.repeat_exited = true;
+                }
+                // jump back to point A, once point B is reached
+                if let (Some((a, b)), Some(track)) = (&self.repeat, &self.track) {
+                    if packet.ts() >= b.get_timestamp() {
+                        *self.position_marker.lock().unwrap() = Some(a.clone());
+                        reader.seek(
+                            symphonia::core::formats::SeekMode::Accurate,
+                            symphonia::core::formats::SeekTo::TimeStamp {
+                                ts: a.get_timestamp(),
+                                track_id: track.id,
+                            },
+                        )?;
+                        self.trim_to = Some(a.get_timestamp());
+                        return Ok(());
+                    }
                 }
                 let decoded = decoder.decode(&packet).unwrap();
+                let spec = *decoded.spec();
+                let seconds = decoded.frames() as f64 / decoded.spec().rate as f64;
+                self.decoded += seconds;
+                // an accurate seek lands on the packet containing the target, so the frames
+                // before the target are dropped. Timestamps count frames
+                let skip = match self.trim_to {
+                    Some(target) if packet.ts() < target => (target - packet.ts()) as usize,
+                    _ => 0,
+                };
+                if skip >= decoded.frames() {
+                    return Ok(());
+                }
+                self.trim_to = None;
+                self.played += (decoded.frames() - skip) as f64 / spec.rate as f64;
+                if let Some(pos) = &mut (*self.position_marker.lock().unwrap()) {
+                    pos.go_to_timestamp(packet.ts() + skip as u64);
+                }
+                let frame_len = decoded.spec().channels.count() * 4;
                 let mut raw_sample_buf =
                     RawSampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
                 raw_sample_buf.copy_interleaved_ref(decoded);
-                match out.write(raw_sample_buf.as_bytes()) {
-                    Ok(_) => {
-                        Ok(())
-                        // successfully wrote buffer
-                        // println!("success");
-                    }
-                    Err(err) => {
-                        panic!("Failed to write to output device");
-                        // PAErr
-                        // println!("Error: {}", err);
+                let samples = &raw_sample_buf.as_bytes()[skip * frame_len..];
+                let mixed = mix_voices(&mut self.voices, samples, spec);
+                let samples = mixed.as_deref().unwrap_or(samples);
+                if let Some(meter) = &mut self.loudness {
+                    meter.process(
+                        samples
+                            .chunks_exact(4)
+                            .map(|b| f32::from_ne_bytes(b.try_into().unwrap()) * gain),
+                    );
+                }
+                let faded = (gain < 1.).then(|| apply_gain(samples, gain));
+                // writing blocks until the output has room, which is not part of the load
+                self.busy += started.elapsed();
+                let res = out.write(faded.as_deref().unwrap_or(samples));
+                if let Err(err) = res {
+                    // the device is gone, pause until it could be reopened
+                    warn!("failed to write to output device: {}", err);
+                    self.output = None;
+                    self.state = PlayerState::Paused;
+                }
+                if let Some(booth) = &mut self.booth {
+                    let volume = gain * self.booth_volume;
+                    if let Err(err) = booth.write(&apply_gain(samples, volume)) {
+                        // keep playing on the main output
+                        warn!("failed to write to booth device: {}", err);
+                        self.booth = None;
                     }
                 }
+                Ok(())
             }
             _ => {
                 panic!("Not everything was initialized");
@@ -349,104 +1034,345 @@ impl Player {
         }
     }
 
-    /// Maps a set of Symphonia `Channels` to a PulseAudio channel map.
-    fn map_channels_to_pa_channelmap(channels: Channels) -> Option<pulse::channelmap::Map> {
-        let mut map: pulse::channelmap::Map = Default::default();
-        map.init();
-        map.set_len(channels.count() as u8);
-
-        let is_mono = channels.count() == 1;
-
-        for (i, channel) in channels.iter().enumerate() {
-            map.get_mut()[i] = match channel {
-                Channels::FRONT_LEFT if is_mono => pulse::channelmap::Position::Mono,
-                Channels::FRONT_LEFT => pulse::channelmap::Position::FrontLeft,
-                Channels::FRONT_RIGHT => pulse::channelmap::Position::FrontRight,
-                Channels::FRONT_CENTRE => pulse::channelmap::Position::FrontCenter,
-                Channels::REAR_LEFT => pulse::channelmap::Position::RearLeft,
-                Channels::REAR_CENTRE => pulse::channelmap::Position::RearCenter,
-                Channels::REAR_RIGHT => pulse::channelmap::Position::RearRight,
-                Channels::LFE1 => pulse::channelmap::Position::Lfe,
-                Channels::FRONT_LEFT_CENTRE => pulse::channelmap::Position::FrontLeftOfCenter,
-                Channels::FRONT_RIGHT_CENTRE => pulse::channelmap::Position::FrontRightOfCenter,
-                Channels::SIDE_LEFT => pulse::channelmap::Position::SideLeft,
-                Channels::SIDE_RIGHT => pulse::channelmap::Position::SideRight,
-                Channels::TOP_CENTRE => pulse::channelmap::Position::TopCenter,
-                Channels::TOP_FRONT_LEFT => pulse::channelmap::Position::TopFrontLeft,
-                Channels::TOP_FRONT_CENTRE => pulse::channelmap::Position::TopFrontCenter,
-                Channels::TOP_FRONT_RIGHT => pulse::channelmap::Position::TopFrontRight,
-                Channels::TOP_REAR_LEFT => pulse::channelmap::Position::TopRearLeft,
-                Channels::TOP_REAR_CENTRE => pulse::channelmap::Position::TopRearCenter,
-                Channels::TOP_REAR_RIGHT => pulse::channelmap::Position::TopRearRight,
-                _ => {
-                    // If a Symphonia channel cannot map to a PulseAudio position then return None
-                    // because PulseAudio will not be able to open a stream with invalid channels.
-                    warn!("failed to map channel {:?} to output", channel);
-                    return None;
-                }
+    /// plays the triggered samples, while the track doesn't play. The samples are written in
+    /// short blocks, so playback of the track can start right away. Without an output, they are
+    /// dropped
+    fn play_voices(&mut self) {
+        // without a loaded track, the output is opened in the format of the first sample
+        if let (None, Some(voice)) = (self.spec, self.voices.first()) {
+            self.spec = Some(voice.spec());
+            let _ = self.init_output();
+        }
+        let gain = self.output_gain();
+        let (spec, out) = match (self.spec, &mut self.output) {
+            (Some(spec), Some(out)) => (spec, out),
+            _ => {
+                self.voices.clear();
+                return;
+            }
+        };
+        let silence = vec![0; VOICE_BLOCK_FRAMES * spec.channels.count() * 4];
+        let samples = mix_voices(&mut self.voices, &silence, spec).unwrap_or(silence);
+        if let Err(err) = out.write(&apply_gain(&samples, gain)) {
+            warn!("failed to write to output device: {}", err);
+            self.output = None;
+        }
+        if let Some(booth) = &mut self.booth {
+            if let Err(err) = booth.write(&apply_gain(&samples, gain * self.booth_volume)) {
+                warn!("failed to write to booth device: {}", err);
+                self.booth = None;
             }
         }
-
-        Some(map)
     }
 
-    pub fn init_output(&mut self) {
+    /// opens the output stream on the configured or default device. On failure the output is left closed and
+    /// reopening it is retried from the event loop. The booth output is opened as well, if a
+    /// booth device is configured
+    pub fn init_output(&mut self) -> io::Result<()> {
         let spec = self.spec.unwrap();
-        let pa_spec = pulse::sample::Spec {
-            format: pulse::sample::Format::FLOAT32NE,
-            channels: spec.channels.count() as u8,
-            rate: spec.rate,
-        };
-        assert!(pa_spec.is_valid());
-
-        let pa_ch_map = Player::map_channels_to_pa_channelmap(spec.channels);
-        let pa = psimple::Simple::new(
-            None,                               // Use default server
-            "Symphonia Player",                 // Application name
-            pulse::stream::Direction::Playback, // Playback stream
-            None,                               // Default playback device
-            "Music",                            // Description of the stream
-            &pa_spec,                           // Signal specificaiton
-            pa_ch_map.as_ref(),                 // Channel map
-            None,                               // Custom buffering attributes
-        )
-        .unwrap();
-        self.output = Some(pa)
+        if let Some(device) = &self.config.booth_device {
+            self.booth = match (self.open_sink)(spec, Some(device.as_str()), "Booth") {
+                Ok(booth) => Some(booth),
+                Err(err) => {
+                    warn!("failed to open booth device {}: {}", device, err);
+                    None
+                }
+            };
+        }
+        match (self.open_sink)(spec, self.config.output_device.as_deref(), "Music") {
+            Ok(output) => {
+                self.output = Some(output);
+                Ok(())
+            }
+            Err(err) => {
+                warn!("failed to open output device: {}", err);
+                self.output = None;
+                Err(err)
+            }
+        }
     }
 
-    fn init_reader(&mut self, path: String) {
-        let src = std::fs::File::open(path).expect("failed to open media");
-        let mss = MediaSourceStream::new(Box::new(src), Default::default());
+    /// probes a file and creates a decoder for its default audio track. The file is read ahead
+    /// into a buffer of the given size in bytes, unless it is 0
+    fn open_file(path: &str, read_ahead: usize) -> Result<OpenedFile, Error> {
+        let (src, read_ahead): (Box<dyn MediaSource>, _) = match read_ahead {
+            0 => (Box::new(std::fs::File::open(path)?), None),
+            capacity => {
+                let (src, handle) = ReadAhead::open(path, capacity)?;
+                (Box::new(src), Some(handle))
+            }
+        };
+        let mss = MediaSourceStream::new(src, Default::default());
         let mut hint = Hint::new();
         hint.with_extension("mp3");
         let meta_opts: MetadataOptions = Default::default();
         let fmt_opts: FormatOptions = Default::default();
-        let probed = symphonia::default::get_probe()
-            .format(&hint, mss, &fmt_opts, &meta_opts)
-            .expect("unsupported format");
-        self.reader = Some(probed.format);
+        let mut reader = symphonia::default::get_probe()
+            .format(&hint, mss, &fmt_opts, &meta_opts)?
+            .format;
+        let dec_opts: DecoderOptions = DecoderOptions { verify: false };
+        let track = probe::default_audio_track(reader.as_ref())
+            .ok_or(Error::Unsupported("no audio track found"))?
+            .clone();
+        let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &dec_opts)?;
+        // decode the first packet of the track to get the signal spec
+        let mut packet = reader.next_packet()?;
+        while packet.track_id() != track.id {
+            packet = reader.next_packet()?;
+        }
+        let spec = *decoder.decode(&packet)?.spec();
+        Ok(OpenedFile {
+            reader,
+            decoder,
+            track,
+            spec,
+            read_ahead,
+        })
     }
+}
 
-    fn init_decoder(&mut self) {
-        let dec_opts: DecoderOptions = DecoderOptions {
-            verify: false,
-            ..Default::default()
-        };
-        if let Some(reader) = &mut self.reader {
-            let track = reader.default_track().unwrap();
-            if let None = self.track {
-                self.track = Some(track.clone());
+/// returns the length of a track in seconds, if the container tells it
+fn duration(track: &Track) -> Option<f64> {
+    TimeMarker::new(track.codec_params.clone()).get_duration_in_seconds()
+}
+
+/// adds the triggered samples to a buffer of interleaved, native endian f32 samples with the
+/// given signal spec and drops the ones, that played to their end. Returns None, if no samples
+/// are playing
+fn mix_voices(voices: &mut Vec<Voice>, bytes: &[u8], spec: SignalSpec) -> Option<Vec<u8>> {
+    if voices.is_empty() {
+        return None;
+    }
+    let mut samples: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_ne_bytes(b.try_into().unwrap()))
+        .collect();
+    voices.retain_mut(|voice| voice.mix(&mut samples, spec));
+    Some(samples.iter().flat_map(|s| s.to_ne_bytes()).collect())
+}
+
+/// scales a buffer of interleaved, native endian f32 samples by a gain
+fn apply_gain(bytes: &[u8], gain: f32) -> Vec<u8> {
+    bytes
+        .chunks_exact(4)
+        .flat_map(|b| (f32::from_ne_bytes(b.try_into().unwrap()) * gain).to_ne_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config;
+    use crate::core::sink::NullSink;
+    use crate::core::test_signal::TestSignal;
+    use std::path::Path;
+    use symphonia::core::audio::Channels;
+
+    /// waits for an event, that `accept` returns true for. Returns false, if none arrives in time
+    fn wait_for(events: &Receiver<Event>, accept: impl Fn(&Event) -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            match events.recv_timeout(timeout) {
+                Ok(ev) if accept(&ev) => return true,
+                Ok(_) => {}
+                Err(_) => return false,
+            }
+        }
+        false
+    }
+
+    /// waits, until the sink stopped receiving samples, and returns the number of bytes written
+    fn wait_until_drained(sink: &NullSink) -> usize {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut written = sink.written();
+        while Instant::now() < deadline {
+            sleep(Duration::from_millis(200));
+            if written > 0 && sink.written() == written {
+                break;
             }
-            let codec_params = &track.codec_params;
-            let mut decoder = symphonia::default::get_codecs()
-                .make(&codec_params, &dec_opts)
-                .unwrap();
-            let packet = reader.next_packet().unwrap();
-            // self.decoder = Some(decoder);
-            let decoded = decoder.decode(&packet).unwrap();
-            let spec = decoded.spec();
-            self.spec = Some(*spec);
-            self.decoder = Some(decoder);
+            written = sink.written();
+        }
+        written
+    }
+
+    /// playhead position, that the player shares with the tests
+    type Position = Arc<Mutex<Option<TimeMarker>>>;
+
+    /// writes a test signal, spawns a player with a null sink and loads the signal. Returns the
+    /// channels to the player, the shared position and the codec parameters of the signal
+    fn load_sweep(
+        dir: &Path,
+        sink: &NullSink,
+    ) -> (Sender<Message>, Receiver<Event>, Position, CodecParameters) {
+        let path = TestSignal::Sweep.write(dir).unwrap();
+        let position = Arc::new(Mutex::new(None));
+        let (messages_out, messages_in) = channel();
+        let (events_out, events_in) = channel();
+        Player::spawn_with_sink(
+            Arc::clone(&position),
+            messages_in,
+            events_out,
+            Config::default(),
+            sink.opener(),
+        );
+        messages_out
+            .send(Message::Load(path.to_string_lossy().into_owned()))
+            .unwrap();
+        assert!(wait_for(&events_in, |ev| matches!(ev, Event::Loaded(_))));
+        let codec_params = position
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .codec_params
+            .clone();
+        (messages_out, events_in, position, codec_params)
+    }
+
+    /// returns the position of the playhead in seconds
+    fn seconds(position: &Mutex<Option<TimeMarker>>) -> f64 {
+        position
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .get_time_in_seconds()
+    }
+
+    #[test]
+    fn mixes_triggered_samples_into_the_track() {
+        let spec = SignalSpec::new(44100, Channels::FRONT_CENTRE);
+        let mut voices = vec![
+            Voice::new(Arc::new(vec![0.5, 0.5]), spec, 1.),
+            Voice::new(Arc::new(vec![0.25; 6]), spec, 0.5),
+        ];
+        let track: Vec<u8> = [0.125f32, 0.25, 0.375, 0.5]
+            .iter()
+            .flat_map(|s| s.to_ne_bytes())
+            .collect();
+        let mix = |voices: &mut Vec<Voice>| -> Vec<f32> {
+            mix_voices(voices, &track, spec)
+                .unwrap()
+                .chunks_exact(4)
+                .map(|b| f32::from_ne_bytes(b.try_into().unwrap()))
+                .collect()
         };
+        assert_eq!(mix(&mut voices), [0.75, 0.875, 0.5, 0.625]);
+        // the shorter sample ended, the longer one ends within the next block
+        assert_eq!(voices.len(), 1);
+        assert_eq!(mix(&mut voices), [0.25, 0.375, 0.375, 0.5]);
+        assert!(voices.is_empty());
+        assert_eq!(mix_voices(&mut voices, &track, spec), None);
+    }
+
+    #[test]
+    fn wraps_around_at_the_end_of_the_repeat() {
+        let dir = config::use_test_data_dir().join("player-repeat");
+        let sink = NullSink::default();
+        let (messages_out, events_in, position, codec_params) = load_sweep(&dir, &sink);
+        let rate = codec_params.sample_rate.unwrap() as usize;
+        let a = TimeMarker::from_seconds(10., codec_params.clone());
+        let b = TimeMarker::from_seconds(11., codec_params);
+
+        messages_out
+            .send(Message::SetRepeat(Some((a.clone(), b))))
+            .unwrap();
+        messages_out.send(Message::JumpTo(a)).unwrap();
+        messages_out.send(Message::TogglePlay).unwrap();
+        // the null sink never blocks, so the loop is played a few times in no time
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while sink.written() < 4 * rate * 8 && Instant::now() < deadline {
+            sleep(Duration::from_millis(10));
+        }
+        assert!(sink.written() >= 4 * rate * 8);
+        let looped = seconds(&position);
+        assert!((10. ..11.1).contains(&looped), "playhead at {}", looped);
+
+        // once left at point B, the rest of the track plays
+        messages_out
+            .send(Message::ExitRepeat(RepeatExit::AtEnd))
+            .unwrap();
+        assert!(wait_for(&events_in, |ev| matches!(ev, Event::RepeatExited)));
+        wait_until_drained(&sink);
+        let _ = std::fs::remove_dir_all(&dir);
+        let end = seconds(&position);
+        assert!(end > 59.9 && end <= 60., "playhead at {}", end);
+    }
+
+    #[test]
+    fn counts_only_frames_after_the_seek_target_as_played() {
+        let dir = config::use_test_data_dir().join("player-played");
+        let sink = NullSink::default();
+        let (messages_out, events_in, _, codec_params) = load_sweep(&dir, &sink);
+        let duration = TimeMarker::new(codec_params.clone())
+            .get_duration_in_seconds()
+            .unwrap();
+
+        // a few milliseconds less than needed to count as played remain after the target, the
+        // frames of the packet before the target must not make up for them
+        let target = duration - PLAYED_AFTER + 0.005;
+        messages_out
+            .send(Message::JumpTo(TimeMarker::from_seconds(
+                target,
+                codec_params,
+            )))
+            .unwrap();
+        messages_out.send(Message::TogglePlay).unwrap();
+        wait_until_drained(&sink);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(!events_in.try_iter().any(|ev| matches!(ev, Event::Played)));
+    }
+
+    #[test]
+    fn plays_triggered_samples_without_a_track() {
+        let sink = NullSink::default();
+        let (messages_out, messages_in) = channel();
+        let (events_out, events_in) = channel();
+        Player::spawn_with_sink(
+            Arc::new(Mutex::new(None)),
+            messages_in,
+            events_out,
+            Config::default(),
+            sink.opener(),
+        );
+        let spec = SignalSpec::new(44100, Channels::FRONT_CENTRE);
+        let voice = Voice::new(Arc::new(vec![0.5; 1000]), spec, 1.);
+        messages_out.send(Message::TriggerSample(voice)).unwrap();
+
+        // the output is opened in the format of the sample
+        assert!(wait_for(
+            &events_in,
+            |ev| matches!(ev, Event::OutputFormat(format) if *format == spec)
+        ));
+        // two blocks of mono 32 bit float frames cover the sample
+        assert_eq!(wait_until_drained(&sink), 2 * VOICE_BLOCK_FRAMES * 4);
+    }
+
+    #[test]
+    fn plays_from_the_seek_target_to_the_end() {
+        let dir = std::env::temp_dir().join(format!("flow-player-test-{}", std::process::id()));
+        let sink = NullSink::default();
+        let (messages_out, _, position, codec_params) = load_sweep(&dir, &sink);
+        let (rate, n_frames) = (
+            codec_params.sample_rate.unwrap() as u64,
+            codec_params.n_frames.unwrap(),
+        );
+
+        messages_out
+            .send(Message::JumpTo(TimeMarker::from_seconds(45., codec_params)))
+            .unwrap();
+        messages_out.send(Message::TogglePlay).unwrap();
+        // the null sink never blocks, so the rest of the track is written right away
+        let written = wait_until_drained(&sink);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // stereo 32 bit float frames from the seek target to the end, none are skipped
+        assert_eq!(written as u64 / 8, n_frames - 45 * rate);
+        let position = seconds(&position);
+        assert!(
+            position > 59.9 && position <= 60.,
+            "playhead at {}",
+            position
+        );
     }
 }
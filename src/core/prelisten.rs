@@ -0,0 +1,123 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use symphonia::core::codecs::CodecParameters;
+
+use crate::core::config::Config;
+use crate::core::player::{Event, Message, Player, TimeMarker};
+
+//------------------------------------------------------------------//
+//                            Prelisten                             //
+//------------------------------------------------------------------//
+
+/// positions within a track as fraction of its duration, that pre-listening cycles through
+pub const PRELISTEN_POSITIONS: [f64; 3] = [0.25, 0.5, 0.75];
+
+/// A second player, that previews tracks from the library on the pre-listen device without
+/// touching the deck
+pub struct Prelisten {
+    player_messages_out: Sender<Message>,
+    player_events_in: Receiver<Event>,
+    /// previewed track, its codec parameters and the index of the previewed position
+    current: Option<(String, CodecParameters, usize)>,
+    /// whether the previewed track is still being opened
+    loading: bool,
+}
+
+impl Prelisten {
+    /// spawns the pre-listen player. It plays on `prelisten_device` and never on the booth
+    pub fn spawn(config: &Config) -> Self {
+        let (player_events_out, player_events_in) = channel();
+        let (player_messages_out, player_messages_in) = channel();
+        let config = Config {
+            output_device: config.prelisten_device.clone(),
+            booth_device: None,
+            ..config.clone()
+        };
+        Player::spawn(
+            Arc::new(Mutex::new(None)),
+            player_messages_in,
+            player_events_out,
+            config,
+        );
+        Self {
+            player_messages_out,
+            player_events_in,
+            current: None,
+            loading: false,
+        }
+    }
+
+    /// starts previewing a track from its first position. Calling it again for the same track
+    /// moves on to the next position, until pre-listening stops after the last one. Returns the
+    /// previewed position. Tracks of unknown length can't be pre-listened
+    pub fn cycle(&mut self, file_path: &str, codec_params: &CodecParameters) -> Option<f64> {
+        if codec_params.n_frames.is_none() {
+            self.stop();
+            return None;
+        }
+        let index = match &self.current {
+            Some((path, _, index)) if path == file_path => index + 1,
+            _ => 0,
+        };
+        if index == 0 {
+            self.stop();
+            let _ = self
+                .player_messages_out
+                .send(Message::Load(String::from(file_path)));
+            self.loading = true;
+        } else if index == PRELISTEN_POSITIONS.len() {
+            self.stop();
+            return None;
+        } else if let (false, Some(marker)) = (self.loading, marker(codec_params, index)) {
+            let _ = self.player_messages_out.send(Message::JumpTo(marker));
+        }
+        self.current = Some((String::from(file_path), codec_params.clone(), index));
+        Some(PRELISTEN_POSITIONS[index])
+    }
+
+    /// stops pre-listening
+    pub fn stop(&mut self) {
+        if self.current.take().is_some() {
+            let _ = self.player_messages_out.send(Message::Pause);
+        }
+        self.loading = false;
+    }
+
+    /// starts playback, once the previewed track was opened. Returns the reason, if it could
+    /// not be opened
+    pub fn update(&mut self) -> Option<String> {
+        while let Ok(ev) = self.player_events_in.try_recv() {
+            match ev {
+                Event::Loaded(path) if self.loading => match &self.current {
+                    Some((file_path, codec_params, index)) if *file_path == path => {
+                        self.loading = false;
+                        if let Some(marker) = marker(codec_params, *index) {
+                            let _ = self.player_messages_out.send(Message::JumpTo(marker));
+                        }
+                        let _ = self.player_messages_out.send(Message::TogglePlay);
+                    }
+                    _ => {}
+                },
+                Event::LoadFailed(path, reason)
+                    if self.current.as_ref().map(|(p, _, _)| p) == Some(&path) =>
+                {
+                    self.current = None;
+                    self.loading = false;
+                    return Some(reason);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// returns the marker of a pre-listen position, or None for tracks of unknown length
+fn marker(codec_params: &CodecParameters, index: usize) -> Option<TimeMarker> {
+    let duration = TimeMarker::new(codec_params.clone()).get_duration_in_seconds()?;
+    Some(TimeMarker::from_seconds(
+        duration * PRELISTEN_POSITIONS[index],
+        codec_params.clone(),
+    ))
+}
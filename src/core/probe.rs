@@ -0,0 +1,86 @@
+use std::{fmt, path::Path};
+
+use symphonia::core::{
+    codecs::CODEC_TYPE_NULL,
+    formats::{FormatReader, Track},
+};
+
+//------------------------------------------------------------------//
+//                              Probe                               //
+//------------------------------------------------------------------//
+
+/// File extensions of the containers, that get picked up by the library scan
+pub const SUPPORTED_EXTENSIONS: [&str; 4] = ["mp3", "wav", "flac", "ogg"];
+
+/// Describes the container and codec of a probed file
+#[derive(Clone, Debug)]
+pub struct FormatInfo {
+    pub container: String,
+    pub codec: String,
+    pub sample_rate: Option<u32>,
+    pub bits_per_sample: Option<u32>,
+}
+
+impl FormatInfo {
+    /// collects the format information of an audio track
+    pub fn new(file_path: &str, track: &Track) -> Self {
+        let container = Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("unknown")
+            .to_lowercase();
+        let codec = symphonia::default::get_codecs()
+            .get_codec(track.codec_params.codec)
+            .map(|descriptor| descriptor.short_name)
+            .unwrap_or("unknown");
+        Self {
+            container,
+            codec: String::from(codec),
+            sample_rate: track.codec_params.sample_rate,
+            bits_per_sample: track.codec_params.bits_per_sample,
+        }
+    }
+}
+
+impl fmt::Display for FormatInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.container, self.codec)?;
+        if let Some(sample_rate) = self.sample_rate {
+            write!(f, " {:.1}kHz", sample_rate as f64 / 1000.)?;
+        }
+        if let Some(bits) = self.bits_per_sample {
+            write!(f, " {}bit", bits)?;
+        }
+        Ok(())
+    }
+}
+
+/// returns whether a file has the extension of a supported container
+pub fn has_supported_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// returns whether a track of a container contains decodable audio
+pub fn is_audio(track: &Track) -> bool {
+    track.codec_params.codec != CODEC_TYPE_NULL && track.codec_params.sample_rate.is_some()
+}
+
+/// returns the audio tracks of a container, starting with the default audio track
+pub fn audio_tracks(reader: &dyn FormatReader) -> Vec<&Track> {
+    let default_id = default_audio_track(reader).map(|track| track.id);
+    let mut tracks: Vec<&Track> = reader.tracks().iter().filter(|t| is_audio(t)).collect();
+    tracks.sort_by_key(|track| Some(track.id) != default_id);
+    tracks
+}
+
+/// returns the audio track, that gets played by default. This is the container's default track,
+/// if it contains audio, else the first audio track.
+pub fn default_audio_track(reader: &dyn FormatReader) -> Option<&Track> {
+    match reader.default_track() {
+        Some(track) if is_audio(track) => Some(track),
+        _ => reader.tracks().iter().find(|track| is_audio(track)),
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    sync::{Arc, Condvar, Mutex},
+    thread::spawn,
+};
+
+use symphonia::core::io::MediaSource;
+
+//------------------------------------------------------------------//
+//                            ReadAhead                             //
+//------------------------------------------------------------------//
+
+/// Number of bytes, that are read from the file at once
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A media source, that reads a file ahead of the decoder in a background thread. Reads from
+/// slow storage like network shares stall the background thread instead of the player, which
+/// can check the buffer through a `ReadAheadHandle`.
+pub struct ReadAhead {
+    shared: Arc<Shared>,
+    /// read position within the file
+    pos: u64,
+    len: u64,
+}
+
+/// Allows to check the fill level of a `ReadAhead` buffer
+#[derive(Clone)]
+pub struct ReadAheadHandle(Arc<Shared>);
+
+struct Shared {
+    state: Mutex<State>,
+    /// signalled, when data was added to the buffer
+    filled: Condvar,
+    /// signalled, when data was taken from the buffer or it was moved by a seek
+    drained: Condvar,
+    /// maximum number of buffered bytes
+    capacity: usize,
+}
+
+struct State {
+    /// file offset of the first buffered byte
+    start: u64,
+    data: VecDeque<u8>,
+    /// increased on every seek, so reads from the old position are dropped
+    generation: u64,
+    eof: bool,
+    /// error of the last read, that is reported to the decoder once
+    error: Option<io::Error>,
+    /// set, once the source is dropped
+    closed: bool,
+}
+
+impl ReadAhead {
+    /// opens a file and starts reading it into a buffer of the given size
+    pub fn open(path: &str, capacity: usize) -> io::Result<(Self, ReadAheadHandle)> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                start: 0,
+                data: VecDeque::with_capacity(capacity),
+                generation: 0,
+                eof: false,
+                error: None,
+                closed: false,
+            }),
+            filled: Condvar::new(),
+            drained: Condvar::new(),
+            capacity: capacity.max(CHUNK_SIZE),
+        });
+        let filler = Arc::clone(&shared);
+        spawn(move || fill(file, filler));
+        let handle = ReadAheadHandle(Arc::clone(&shared));
+        Ok((
+            Self {
+                shared,
+                pos: 0,
+                len,
+            },
+            handle,
+        ))
+    }
+}
+
+/// reads the file into the buffer, until the source is dropped
+fn fill(mut file: File, shared: Arc<Shared>) {
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut file_pos = 0;
+    loop {
+        let (offset, generation) = {
+            let mut state = shared.state.lock().unwrap();
+            while !state.closed
+                && (state.eof || state.error.is_some() || state.data.len() >= shared.capacity)
+            {
+                state = shared.drained.wait(state).unwrap();
+            }
+            if state.closed {
+                return;
+            }
+            (state.start + state.data.len() as u64, state.generation)
+        };
+        // the lock is not held while reading, so the decoder can take buffered data meanwhile
+        let res = if offset == file_pos {
+            file.read(&mut buf)
+        } else {
+            file.seek(SeekFrom::Start(offset))
+                .and_then(|_| file.read(&mut buf))
+        };
+        file_pos = match &res {
+            Ok(n) => offset + *n as u64,
+            // make sure to seek before the next read
+            Err(_) => u64::MAX,
+        };
+        let mut state = shared.state.lock().unwrap();
+        if state.generation != generation {
+            continue;
+        }
+        match res {
+            Ok(0) => state.eof = true,
+            Ok(n) => state.data.extend(&buf[..n]),
+            Err(err) => state.error = Some(err),
+        }
+        shared.filled.notify_all();
+    }
+}
+
+impl Read for ReadAhead {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        if self.pos < state.start || self.pos > state.start + state.data.len() as u64 {
+            // the decoder seeked out of the buffer, start reading from the new position
+            state.start = self.pos;
+            state.data.clear();
+            state.eof = false;
+            state.error = None;
+            state.generation += 1;
+            self.shared.drained.notify_all();
+        } else {
+            // the decoder skipped some bytes
+            let skipped = (self.pos - state.start) as usize;
+            state.data.drain(..skipped);
+            state.start = self.pos;
+        }
+        while state.data.is_empty() {
+            if let Some(err) = state.error.take() {
+                self.shared.drained.notify_all();
+                return Err(err);
+            }
+            if state.eof {
+                return Ok(0);
+            }
+            state = self.shared.filled.wait(state).unwrap();
+        }
+        let n = buf.len().min(state.data.len());
+        for (dst, src) in buf.iter_mut().zip(state.data.drain(..n)) {
+            *dst = src;
+        }
+        state.start += n as u64;
+        self.pos += n as u64;
+        self.shared.drained.notify_all();
+        Ok(n)
+    }
+}
+
+impl Seek for ReadAhead {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::Current(offset) => self.pos as i128 + offset as i128,
+            SeekFrom::End(offset) => self.len as i128 + offset as i128,
+        };
+        let pos = u64::try_from(pos)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "seek before start"))?;
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
+impl MediaSource for ReadAhead {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.len)
+    }
+}
+
+impl Drop for ReadAhead {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.drained.notify_all();
+    }
+}
+
+impl ReadAheadHandle {
+    /// returns true, if the buffer ran almost empty before the end of the file was read.
+    /// Playback should wait, until it is filled again
+    pub fn is_starved(&self) -> bool {
+        let state = self.0.state.lock().unwrap();
+        !state.eof && state.data.len() < self.0.capacity / 8
+    }
+
+    /// returns true, if enough data is buffered to continue playback
+    pub fn is_filled(&self) -> bool {
+        let state = self.0.state.lock().unwrap();
+        state.eof || state.data.len() >= self.0.capacity / 2
+    }
+}
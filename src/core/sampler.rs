@@ -0,0 +1,233 @@
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{spawn, JoinHandle};
+
+use log::warn;
+use symphonia::core::{
+    audio::{SampleBuffer, SignalSpec},
+    codecs::DecoderOptions,
+    errors::Error,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use crate::core::player::Message;
+use crate::core::probe;
+
+//------------------------------------------------------------------//
+//                             Sampler                              //
+//------------------------------------------------------------------//
+
+/// Number of sample slots available in the sampler
+pub const SAMPLER_SLOTS: usize = 4;
+
+/// Longest part of a file in seconds, that is loaded into a slot. Longer files are cut off, so
+/// loading a whole track by accident doesn't fill up the memory
+const MAX_SAMPLE_SECONDS: u64 = 30;
+
+/// A short sample, that is fully decoded into memory, so it can be triggered without delay
+#[derive(Clone)]
+pub struct SamplerSlot {
+    /// the file name of the loaded sample
+    pub name: String,
+    /// gain that is applied when the slot gets triggered
+    pub gain: f32,
+    /// decoded samples in interleaved form
+    samples: Arc<Vec<f32>>,
+    /// Signal Spec of the decoded samples
+    spec: SignalSpec,
+}
+
+/// Holds a number of one-shot sample slots. Every trigger sends a voice to the player, which
+/// mixes it into its output, so master and booth volume apply to it and triggering a slot
+/// multiple times layers the sample polyphonically.
+pub struct Sampler {
+    slots: Arc<Mutex<Vec<Option<SamplerSlot>>>>,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            slots: Arc::new(Mutex::new(vec![None; SAMPLER_SLOTS])),
+        }
+    }
+}
+
+impl Sampler {
+    /// returns the sampler slots
+    pub fn slots(&self) -> MutexGuard<Vec<Option<SamplerSlot>>> {
+        self.slots.lock().unwrap()
+    }
+
+    /// decodes the file at the given path into a slot in a background thread. Fails, if the path
+    /// doesn't end in a file name, that is valid UTF-8
+    pub fn load(&self, slot: usize, path: String) -> io::Result<JoinHandle<()>> {
+        let name = Path::new(&path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(String::from)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file name"))?;
+        let slots = Arc::clone(&self.slots);
+        Ok(spawn(move || match Sampler::decode(&path) {
+            Ok((samples, spec)) => {
+                if let Some(s) = slots.lock().unwrap().get_mut(slot) {
+                    *s = Some(SamplerSlot {
+                        name,
+                        gain: 1.,
+                        samples: Arc::new(samples),
+                        spec,
+                    });
+                }
+            }
+            Err(err) => {
+                warn!("failed to load sample {}: {}", path, err);
+            }
+        }))
+    }
+
+    /// plays the sample of a slot once on the player's output
+    pub fn trigger(&self, slot: usize, player_messages_out: &Sender<Message>) {
+        if let Some(Some(slot)) = self.slots().get(slot) {
+            let voice = Voice::new(Arc::clone(&slot.samples), slot.spec, slot.gain);
+            let _ = player_messages_out.send(Message::TriggerSample(voice));
+        }
+    }
+
+    /// change the gain of a slot by a given amount. The gain is clamped to [0, 2]
+    pub fn change_gain(&self, slot: usize, delta: f32) {
+        if let Some(Some(slot)) = self.slots().get_mut(slot) {
+            slot.gain = (slot.gain + delta).clamp(0., 2.);
+        }
+    }
+
+    /// decodes a file into an interleaved sample buffer. At most `MAX_SAMPLE_SECONDS` are decoded
+    fn decode(path: &str) -> Result<(Vec<f32>, SignalSpec), Error> {
+        let src = std::fs::File::open(path)?;
+        let mss = MediaSourceStream::new(Box::new(src), Default::default());
+        let meta_opts: MetadataOptions = Default::default();
+        let fmt_opts: FormatOptions = Default::default();
+        let mut reader = symphonia::default::get_probe()
+            .format(&Hint::new(), mss, &fmt_opts, &meta_opts)?
+            .format;
+        let track = probe::default_audio_track(reader.as_ref())
+            .ok_or(Error::Unsupported("no audio track found"))?;
+        let track_id = track.id;
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+        let mut samples = vec![];
+        let mut spec: Option<SignalSpec> = None;
+        loop {
+            if let Some(spec) = spec {
+                let max_len =
+                    (MAX_SAMPLE_SECONDS * spec.rate as u64) as usize * spec.channels.count();
+                if samples.len() >= max_len {
+                    warn!("{} is cut off after {} seconds", path, MAX_SAMPLE_SECONDS);
+                    samples.truncate(max_len);
+                    break;
+                }
+            }
+            let packet = match reader.next_packet() {
+                Ok(packet) => packet,
+                // end of stream
+                Err(Error::IoError(_)) => break,
+                Err(err) => return Err(err),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+            match decoder.decode(&packet) {
+                Ok(decoded) => {
+                    let mut sample_buf =
+                        SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+                    spec = Some(*decoded.spec());
+                    sample_buf.copy_interleaved_ref(decoded);
+                    samples.extend_from_slice(sample_buf.samples());
+                }
+                // Decode errors are not fatal, skip the packet
+                Err(Error::DecodeError(err)) => warn!("decode error: {}", err),
+                Err(err) => return Err(err),
+            }
+        }
+        let spec = spec.ok_or(Error::DecodeError("no audio decoded"))?;
+        Ok((samples, spec))
+    }
+}
+
+//------------------------------------------------------------------//
+//                              Voice                               //
+//------------------------------------------------------------------//
+
+/// A triggered sample, that is being played
+pub struct Voice {
+    /// decoded samples in interleaved form
+    samples: Arc<Vec<f32>>,
+    /// Signal Spec of the decoded samples
+    spec: SignalSpec,
+    /// gain of the slot at the time of the trigger
+    gain: f32,
+    /// frame of the sample, that is played next. It is fractional, if the sample rate differs
+    /// from the output
+    pos: f64,
+}
+
+impl Voice {
+    /// starts playing interleaved samples with the given spec and gain from their beginning
+    pub fn new(samples: Arc<Vec<f32>>, spec: SignalSpec, gain: f32) -> Self {
+        Self {
+            samples,
+            spec,
+            gain,
+            pos: 0.,
+        }
+    }
+
+    /// returns the signal spec of the sample
+    pub fn spec(&self) -> SignalSpec {
+        self.spec
+    }
+
+    /// adds the next frames of the sample to interleaved output samples with the given signal
+    /// spec. Other sample rates are converted by picking the nearest frame, missing channels are
+    /// repeated. Returns false, once the sample was played to its end
+    pub fn mix(&mut self, out: &mut [f32], spec: SignalSpec) -> bool {
+        let in_channels = self.spec.channels.count();
+        let step = self.spec.rate as f64 / spec.rate as f64;
+        let n_frames = self.samples.len() / in_channels;
+        for frame in out.chunks_exact_mut(spec.channels.count()) {
+            let i = self.pos as usize;
+            if i >= n_frames {
+                return false;
+            }
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample += self.samples[i * in_channels + channel % in_channels] * self.gain;
+            }
+            self.pos += step;
+        }
+        (self.pos as usize) < n_frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::audio::Channels;
+
+    #[test]
+    fn mixes_mono_samples_into_stereo_output_at_twice_the_rate() {
+        let mut voice = Voice {
+            samples: Arc::new(vec![1., 2.]),
+            spec: SignalSpec::new(22050, Channels::FRONT_CENTRE),
+            gain: 0.5,
+            pos: 0.,
+        };
+        let spec = SignalSpec::new(44100, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+        let mut out = vec![1.; 12];
+        // each frame of the sample is played twice on both channels, then the voice is done
+        assert!(!voice.mix(&mut out, spec));
+        assert_eq!(out, [1.5, 1.5, 1.5, 1.5, 2., 2., 2., 2., 1., 1., 1., 1.]);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use libpulse_binding as pulse;
+use libpulse_simple_binding as psimple;
+use log::warn;
+use symphonia::core::audio::{Channels, SignalSpec};
+
+//------------------------------------------------------------------//
+//                            AudioSink                             //
+//------------------------------------------------------------------//
+
+/// An audio output, that the player writes decoded samples to. Samples are interleaved 32 bit
+/// floats in native byte order.
+pub trait AudioSink {
+    /// writes samples to the output. Blocks, until the output has room for them
+    fn write(&mut self, samples: &[u8]) -> io::Result<()>;
+
+    /// drops samples, that were written but not played yet
+    fn flush(&mut self);
+
+    /// returns the time, that written samples take to become audible, if the output knows it
+    fn latency(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// opens a sink with a signal spec on a device, or on the default device. The last argument
+/// describes the stream
+pub type OpenSink =
+    Box<dyn Fn(SignalSpec, Option<&str>, &str) -> io::Result<Box<dyn AudioSink>> + Send>;
+
+//------------------------------------------------------------------//
+//                            PulseSink                             //
+//------------------------------------------------------------------//
+
+/// A playback stream on a PulseAudio sink
+pub struct PulseSink(psimple::Simple);
+
+impl PulseSink {
+    /// opens a playback stream on a device, or on the default device
+    pub fn open(
+        spec: SignalSpec,
+        device: Option<&str>,
+        description: &str,
+    ) -> io::Result<Box<dyn AudioSink>> {
+        let pa_spec = pulse::sample::Spec {
+            format: pulse::sample::Format::FLOAT32NE,
+            channels: spec.channels.count() as u8,
+            rate: spec.rate,
+        };
+        if !pa_spec.is_valid() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported signal spec {:?}", spec),
+            ));
+        }
+
+        let pa_ch_map = map_channels_to_pa_channelmap(spec.channels);
+        let stream = psimple::Simple::new(
+            None,                               // Use default server
+            "Symphonia Player",                 // Application name
+            pulse::stream::Direction::Playback, // Playback stream
+            device,                             // Playback device
+            description,                        // Description of the stream
+            &pa_spec,                           // Signal specificaiton
+            pa_ch_map.as_ref(),                 // Channel map
+            None,                               // Custom buffering attributes
+        )
+        .map_err(pa_error)?;
+        Ok(Box::new(PulseSink(stream)))
+    }
+}
+
+impl AudioSink for PulseSink {
+    fn write(&mut self, samples: &[u8]) -> io::Result<()> {
+        self.0.write(samples).map_err(pa_error)
+    }
+
+    fn flush(&mut self) {
+        let _ = self.0.flush();
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        self.0
+            .get_latency()
+            .ok()
+            .map(|latency| Duration::from_micros(latency.0))
+    }
+}
+
+fn pa_error(err: pulse::error::PAErr) -> io::Error {
+    io::Error::other(err)
+}
+
+/// Maps a set of Symphonia `Channels` to a PulseAudio channel map.
+pub fn map_channels_to_pa_channelmap(channels: Channels) -> Option<pulse::channelmap::Map> {
+    let mut map: pulse::channelmap::Map = Default::default();
+    map.init();
+    map.set_len(channels.count() as u8);
+
+    let is_mono = channels.count() == 1;
+
+    for (i, channel) in channels.iter().enumerate() {
+        map.get_mut()[i] = match channel {
+            Channels::FRONT_LEFT if is_mono => pulse::channelmap::Position::Mono,
+            Channels::FRONT_LEFT => pulse::channelmap::Position::FrontLeft,
+            Channels::FRONT_RIGHT => pulse::channelmap::Position::FrontRight,
+            Channels::FRONT_CENTRE => pulse::channelmap::Position::FrontCenter,
+            Channels::REAR_LEFT => pulse::channelmap::Position::RearLeft,
+            Channels::REAR_CENTRE => pulse::channelmap::Position::RearCenter,
+            Channels::REAR_RIGHT => pulse::channelmap::Position::RearRight,
+            Channels::LFE1 => pulse::channelmap::Position::Lfe,
+            Channels::FRONT_LEFT_CENTRE => pulse::channelmap::Position::FrontLeftOfCenter,
+            Channels::FRONT_RIGHT_CENTRE => pulse::channelmap::Position::FrontRightOfCenter,
+            Channels::SIDE_LEFT => pulse::channelmap::Position::SideLeft,
+            Channels::SIDE_RIGHT => pulse::channelmap::Position::SideRight,
+            Channels::TOP_CENTRE => pulse::channelmap::Position::TopCenter,
+            Channels::TOP_FRONT_LEFT => pulse::channelmap::Position::TopFrontLeft,
+            Channels::TOP_FRONT_CENTRE => pulse::channelmap::Position::TopFrontCenter,
+            Channels::TOP_FRONT_RIGHT => pulse::channelmap::Position::TopFrontRight,
+            Channels::TOP_REAR_LEFT => pulse::channelmap::Position::TopRearLeft,
+            Channels::TOP_REAR_CENTRE => pulse::channelmap::Position::TopRearCenter,
+            Channels::TOP_REAR_RIGHT => pulse::channelmap::Position::TopRearRight,
+            _ => {
+                // If a Symphonia channel cannot map to a PulseAudio position then return None
+                // because PulseAudio will not be able to open a stream with invalid channels.
+                warn!("failed to map channel {:?} to output", channel);
+                return None;
+            }
+        }
+    }
+
+    Some(map)
+}
+
+//------------------------------------------------------------------//
+//                             NullSink                             //
+//------------------------------------------------------------------//
+
+/// A sink, that discards all samples right away. It never blocks, so the player decodes as fast
+/// as it can, which makes it deterministic for tests without an audio device. Clones share the
+/// count of written bytes, so a test can keep a clone, while the player writes to another one
+#[derive(Clone, Default)]
+pub struct NullSink {
+    /// number of bytes written so far
+    written: Arc<AtomicUsize>,
+}
+
+impl NullSink {
+    /// returns the number of bytes written to this sink and its clones so far
+    pub fn written(&self) -> usize {
+        self.written.load(Ordering::SeqCst)
+    }
+
+    /// returns a function, that opens clones of this sink and ignores the signal spec and device
+    pub fn opener(&self) -> OpenSink {
+        let sink = self.clone();
+        Box::new(move |_, _, _| Ok(Box::new(sink.clone())))
+    }
+}
+
+impl AudioSink for NullSink {
+    fn write(&mut self, samples: &[u8]) -> io::Result<()> {
+        self.written.fetch_add(samples.len(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn flush(&mut self) {}
+}
@@ -0,0 +1,141 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use lofty::{config::WriteOptions, prelude::*, tag::Tag};
+
+//------------------------------------------------------------------//
+//                               Tags                               //
+//------------------------------------------------------------------//
+
+/// A tag, that can be written back to a file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TagField {
+    Artist,
+    Title,
+    Album,
+    Bpm,
+    Key,
+    Comment,
+}
+
+impl TagField {
+    /// all fields in the order, they are edited in
+    pub const ALL: [TagField; 6] = [
+        TagField::Artist,
+        TagField::Title,
+        TagField::Album,
+        TagField::Bpm,
+        TagField::Key,
+        TagField::Comment,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TagField::Artist => "artist",
+            TagField::Title => "title",
+            TagField::Album => "album",
+            TagField::Bpm => "bpm",
+            TagField::Key => "key",
+            TagField::Comment => "comment",
+        }
+    }
+}
+
+/// writes the given values to the tags of a file, in the file's main tag format, e.g. ID3v2 for
+/// MP3 and Vorbis comments for FLAC and Ogg. Empty values remove the tag. With `backup`, a copy
+/// of the file is kept next to it as `<file>.bak`, unless a copy was kept before
+pub fn write(path: &Path, changes: &[(TagField, String)], backup: bool) -> io::Result<()> {
+    let mut tagged_file = lofty::read_from_path(path).map_err(io::Error::other)?;
+    if tagged_file.primary_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    let tag = tagged_file.primary_tag_mut().unwrap();
+    for (field, value) in changes {
+        set(tag, *field, value.trim());
+    }
+    if backup {
+        let backup_path = backup_path(path);
+        if !backup_path.exists() {
+            fs::copy(path, backup_path)?;
+        }
+    }
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(io::Error::other)
+}
+
+/// returns the path of the copy, that is kept before the tags of a file are written
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+fn set(tag: &mut Tag, field: TagField, value: &str) {
+    let value = String::from(value);
+    match (field, value.is_empty()) {
+        (TagField::Artist, true) => tag.remove_artist(),
+        (TagField::Artist, false) => tag.set_artist(value),
+        (TagField::Title, true) => tag.remove_title(),
+        (TagField::Title, false) => tag.set_title(value),
+        (TagField::Album, true) => tag.remove_album(),
+        (TagField::Album, false) => tag.set_album(value),
+        (TagField::Comment, true) => tag.remove_comment(),
+        (TagField::Comment, false) => tag.set_comment(value),
+        (TagField::Key, true) => tag.remove_key(&ItemKey::InitialKey),
+        (TagField::Key, false) => {
+            tag.insert_text(ItemKey::InitialKey, value);
+        }
+        // ID3v2 and MP4 only know integer tempos, Vorbis comments only the plain one
+        (TagField::Bpm, _) => {
+            tag.remove_key(&ItemKey::IntegerBpm);
+            tag.remove_key(&ItemKey::Bpm);
+            if !value.is_empty() && !tag.insert_text(ItemKey::IntegerBpm, value.clone()) {
+                tag.insert_text(ItemKey::Bpm, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{config, test_signal::TestSignal};
+
+    /// reads a tag of a file, as another tagger would see it
+    fn read(path: &Path, key: ItemKey) -> Option<String> {
+        let tagged_file = lofty::read_from_path(path).unwrap();
+        let tag = tagged_file.primary_tag()?;
+        tag.get_string(&key).map(String::from)
+    }
+
+    #[test]
+    fn writes_and_removes_tags_and_keeps_the_original() {
+        let dir = config::use_test_data_dir().join("tags");
+        let path = TestSignal::Sweep.write(&dir).unwrap();
+        let original = fs::read(&path).unwrap();
+        let changes = [
+            (TagField::Artist, String::from("Some Artist")),
+            (TagField::Bpm, String::from("124")),
+            (TagField::Key, String::from("8A")),
+            (TagField::Comment, String::from("warm-up")),
+        ];
+        write(&path, &changes, true).unwrap();
+        assert_eq!(
+            read(&path, ItemKey::TrackArtist).as_deref(),
+            Some("Some Artist")
+        );
+        assert_eq!(read(&path, ItemKey::IntegerBpm).as_deref(), Some("124"));
+        assert_eq!(read(&path, ItemKey::InitialKey).as_deref(), Some("8A"));
+        assert_eq!(read(&path, ItemKey::Comment).as_deref(), Some("warm-up"));
+
+        // the backup keeps the file as it was before the first write
+        write(&path, &[(TagField::Artist, String::new())], true).unwrap();
+        assert_eq!(read(&path, ItemKey::TrackArtist), None);
+        assert_eq!(read(&path, ItemKey::InitialKey).as_deref(), Some("8A"));
+        assert_eq!(fs::read(backup_path(&path)).unwrap(), original);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    f32::consts::PI,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::core::config;
+
+//------------------------------------------------------------------//
+//                            TestSignal                            //
+//------------------------------------------------------------------//
+
+/// Sample rate of generated signals
+const SAMPLE_RATE: u32 = 44100;
+
+/// Length of generated signals in seconds
+const DURATION: u32 = 60;
+
+/// Peak amplitude of generated signals, which leaves headroom for gain changes
+const AMPLITUDE: f32 = 0.5;
+
+/// A generated signal, that can be played and analyzed like a track from the library. Signals
+/// are deterministic, so the same signal always gives the same file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestSignal {
+    /// a logarithmic sine sweep from 20 Hz to 20 kHz
+    Sweep,
+    /// a click on every beat at the given bpm, with a higher pitched click on every bar
+    Clicks(u32),
+    /// noise with equal energy per octave
+    PinkNoise,
+}
+
+impl TestSignal {
+    /// returns the file name of the signal
+    pub fn file_name(&self) -> String {
+        match self {
+            TestSignal::Sweep => String::from("sweep.wav"),
+            TestSignal::Clicks(bpm) => format!("clicks-{}bpm.wav", bpm),
+            TestSignal::PinkNoise => String::from("pink-noise.wav"),
+        }
+    }
+
+    /// generates the mono samples of the signal
+    pub fn samples(&self) -> Vec<f32> {
+        let len = (SAMPLE_RATE * DURATION) as usize;
+        let rate = SAMPLE_RATE as f32;
+        match *self {
+            TestSignal::Sweep => {
+                let (start, end) = (20_f32, 20000_f32);
+                let duration = DURATION as f32;
+                let k = (end / start).ln();
+                (0..len)
+                    .map(|i| {
+                        let t = i as f32 / rate;
+                        // phase of an exponential frequency increase
+                        let phase =
+                            2. * PI * start * duration / k * ((t / duration * k).exp() - 1.);
+                        phase.sin() * AMPLITUDE
+                    })
+                    .collect()
+            }
+            TestSignal::Clicks(bpm) => {
+                let beat_len = (rate * 60. / bpm.max(1) as f32) as usize;
+                let click_len = (rate * 0.01) as usize;
+                (0..len)
+                    .map(|i| {
+                        let (beat, offset) = (i / beat_len, i % beat_len);
+                        if offset >= click_len {
+                            return 0.;
+                        }
+                        let frequency = if beat % 4 == 0 { 2000. } else { 1000. };
+                        let t = offset as f32 / rate;
+                        let envelope = 1. - offset as f32 / click_len as f32;
+                        (2. * PI * frequency * t).sin() * envelope * AMPLITUDE
+                    })
+                    .collect()
+            }
+            TestSignal::PinkNoise => {
+                // white noise from a xorshift generator, shaped by Paul Kellet's pink filter
+                let mut state = 0x2545f491_u32;
+                let mut b = [0_f32; 7];
+                (0..len)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 17;
+                        state ^= state << 5;
+                        let white = state as f32 / u32::MAX as f32 * 2. - 1.;
+                        b[0] = 0.99886 * b[0] + white * 0.0555179;
+                        b[1] = 0.99332 * b[1] + white * 0.0750759;
+                        b[2] = 0.96900 * b[2] + white * 0.153852;
+                        b[3] = 0.86650 * b[3] + white * 0.3104856;
+                        b[4] = 0.55000 * b[4] + white * 0.5329522;
+                        b[5] = -0.7616 * b[5] - white * 0.0168980;
+                        let pink = b.iter().sum::<f32>() + white * 0.5362;
+                        b[6] = white * 0.115926;
+                        // the filter has a gain of about 5 at its loudest
+                        (pink * 0.11 * AMPLITUDE).clamp(-1., 1.)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// writes the signal as a stereo 32 bit float WAV file into a folder, unless it exists
+    /// already. Returns the path of the file
+    pub fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+        let path = dir.join(self.file_name());
+        if path.exists() {
+            return Ok(path);
+        }
+        let samples = self.samples();
+        let channels = 2_u16;
+        let data_len = (samples.len() * channels as usize * 4) as u32;
+        let mut content = Vec::with_capacity(44 + data_len as usize);
+        content.extend(b"RIFF");
+        content.extend((36 + data_len).to_le_bytes());
+        content.extend(b"WAVE");
+        content.extend(b"fmt ");
+        content.extend(16_u32.to_le_bytes());
+        // IEEE float samples
+        content.extend(3_u16.to_le_bytes());
+        content.extend(channels.to_le_bytes());
+        content.extend(SAMPLE_RATE.to_le_bytes());
+        content.extend((SAMPLE_RATE * channels as u32 * 4).to_le_bytes());
+        content.extend((channels * 4).to_le_bytes());
+        content.extend(32_u16.to_le_bytes());
+        content.extend(b"data");
+        content.extend(data_len.to_le_bytes());
+        for sample in samples {
+            for _ in 0..channels {
+                content.extend(sample.to_le_bytes());
+            }
+        }
+        fs::create_dir_all(dir)?;
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+}
+
+/// returns the folder, that generated signals are written to
+pub fn signals_dir() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("signals"))
+}
@@ -0,0 +1,71 @@
+use std::{path::Path, sync::mpsc::Sender};
+
+use log::warn;
+use notify::{
+    event::{AccessKind, AccessMode, ModifyKind, RenameMode},
+    EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+
+use crate::core::probe;
+
+//------------------------------------------------------------------//
+//                          LibraryWatcher                          //
+//------------------------------------------------------------------//
+
+pub enum Event {
+    /// A supported file appeared in the library
+    Created(String),
+    /// A supported file in the library was written to
+    Modified(String),
+    /// A file or directory was removed from the library
+    Removed(String),
+}
+
+/// watches the library folder recursively and reports changed files. Watching stops, when the
+/// returned watcher is dropped
+pub fn watch(root: &Path, event_out: Sender<Event>) -> notify::Result<RecommendedWatcher> {
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => handle_event(event, &event_out),
+            Err(err) => warn!("library watcher error: {}", err),
+        })?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+/// translates a file system event into library events
+fn handle_event(event: notify::Event, event_out: &Sender<Event>) {
+    for (i, path) in event.paths.iter().enumerate() {
+        let file_path = match path.to_str() {
+            Some(file_path) => String::from(file_path),
+            None => continue,
+        };
+        let supported = probe::has_supported_extension(path);
+        let library_event = match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To))
+                if supported =>
+            {
+                Event::Created(file_path)
+            }
+            // renames within the library report the old path first and the new path second
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if i == 0 => {
+                Event::Removed(file_path)
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if supported => {
+                Event::Created(file_path)
+            }
+            // files are probed again, once they were written completely
+            EventKind::Access(AccessKind::Close(AccessMode::Write)) if supported => {
+                Event::Modified(file_path)
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                Event::Removed(file_path)
+            }
+            _ => continue,
+        };
+        if event_out.send(library_event).is_err() {
+            // the app is gone
+            return;
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use flow::core::config::Config;
 use flow::view::app::App;
 extern crate crossterm;
 
@@ -5,7 +6,12 @@ extern crate crossterm;
 async fn main() {
     // let tempo = Analyzer::get_tempo("music/bass_symptom.mp3");
     // println!("{}", tempo);
-    let app = App::default();
+    let mut config = Config::load();
+    if let Err(err) = config.apply_args(std::env::args().skip(1)) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    let app = App::new(config);
     let res = app.run().await.unwrap();
     println!("App closed: {:#?}", res);
 }
@@ -1,38 +1,112 @@
 use crate::core::{
-    analyzer::{self, Analyzer},
-    player::{self, TimeMarker},
+    analysis_cache,
+    analyzer::{self, Analyzer, PreviewSample},
+    bpm_window::{self, BpmWindowStore},
+    chapters::{self, Chapter},
+    clipboard,
+    columns::{Column, ColumnSetting},
+    config::{Config, LibraryRoot},
+    mounts,
+    player::{self, SleepMode, TimeMarker},
+    prelisten::Prelisten,
+    probe,
+    sampler::{Sampler, SAMPLER_SLOTS},
+    sink::{OpenSink, PulseSink},
+    tags::{self, TagField},
+    test_signal::{self, TestSignal},
+    watcher,
 };
 use crossterm::{
-    event::{self, EnableMouseCapture, KeyCode, KeyEvent, KeyModifiers},
+    event::{
+        self, EnableMouseCapture, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{enable_raw_mode, EnterAlternateScreen},
 };
-use symphonia::core::units::Time;
+use log::warn;
+use notify::RecommendedWatcher;
+use symphonia::core::units::{Time, TimeStamp};
 
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::{
+    collections::{HashSet, VecDeque},
     fs, io,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant, UNIX_EPOCH},
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
-    widgets::{Block, Borders, Paragraph},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Table, Wrap},
 };
 use tui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     Frame, Terminal,
 };
 
-use crate::core::player::{Message, Player};
+#[cfg(feature = "global-hotkeys")]
+use crate::core::hotkeys::{Action, GlobalHotkeys};
+use crate::core::player::{Message, Player, RepeatExit};
 
+use super::i18n::{self, tr};
+use super::model::{
+    analysis_bundle,
+    bookmarks::BookmarkStore,
+    duplicates,
+    facets::Facets,
+    key_overrides::{self, KeyOverrideStore},
+    library_export,
+    play_stats::{self, PlayStatsStore},
+    playhead::Playhead,
+    render_cache::RenderCache,
+    scan_progress::ScanProgress,
+    session::Session,
+    set_plan::{self, SetPlan},
+    track::{Track, TrackMeta},
+    undo::{Edit, UndoStack},
+};
 use super::widgets::{
     live_preview::LivePreviewWidget,
-    preview::PreviewWidget,
-    track_table::{TrackList, TrackTableWidget},
+    loudness::LoudnessWidget,
+    popup::PopupWidget,
+    preview::{self, PreviewWidget},
+    prompt::{Prompt, PromptResult, PromptWidget},
+    sampler::SamplerWidget,
+    track_table::{self, TrackList, TrackTableWidget},
 };
 
+/// inputs of the live preview waveform: track, playhead, pane width, zoom level, downscale and
+/// number of analyzed samples
+type LivePreviewKey = (String, u32, usize, usize);
+
+/// narrowest width of a column in characters, that the column picker sets
+const MIN_COLUMN_WIDTH: u16 = 4;
+
+/// smallest terminal size in columns and rows, that the app is drawn in. Smaller terminals only
+/// show a hint
+const MIN_TERMINAL_SIZE: (u16, u16) = (60, 20);
+
+/// time to wait for the next frame, while events are still handled
+const FRAME_WAIT: Duration = Duration::from_millis(1);
+
+/// most columns, that a waveform line covers on slow terminals
+const MAX_WAVEFORM_DOWNSCALE: usize = 8;
+
+/// time without a repeat of the cue key, after which it counts as released. Terminals don't
+/// report releases, and the first repeat of a held key can take over half a second
+const CUE_RELEASE_TIMEOUT: Duration = Duration::from_millis(700);
+
+/// interval, in which the crash recovery journal is written
+const JOURNAL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// frames of the spinner, that is shown while a track is loading
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// sleep timer durations in minutes, that are cycled through
+const SLEEP_TIMER_PRESETS: [u64; 3] = [15, 30, 60];
+
 #[derive(Clone, Debug)]
 pub enum Event {
     /// Key event for Toggling playback
@@ -44,14 +118,75 @@ pub enum Event {
     /// Unknown key event
     Unknown,
 }
+/// Orders, that the track list can be sorted in
+#[derive(Clone, Copy, PartialEq)]
+enum SortOrder {
+    /// by file path
+    Path,
+    /// most played first
+    PlayCount,
+    /// least recently played first
+    LastPlayed,
+}
+
+/// Answers, that the app waits for
+enum PromptRequest {
+    /// whether to delete the file of a duplicate
+    DeleteDuplicate(Arc<Track>),
+    /// new name of a bookmark of the loaded track
+    RenameBookmark { ts: TimeStamp, name: String },
+    /// BPM of a track
+    SetBpm(Arc<Track>),
+    /// part of a track, that its BPM is detected from
+    SetBpmWindow(Arc<Track>),
+    /// value of the tag at index `field` of `TagField::ALL` for the given tracks. The fields
+    /// before it were answered with `changes`
+    EditTags {
+        tracks: Vec<Arc<Track>>,
+        field: usize,
+        changes: Vec<(TagField, String)>,
+    },
+    /// whether to write the changed tags to the files of the tracks
+    WriteTags {
+        tracks: Vec<Arc<Track>>,
+        changes: Vec<(TagField, String)>,
+    },
+    /// path of a recorded mix, that gets the played tracks as chapters
+    WriteChapters,
+}
+
 /// Abstraction layer for determining, which (key) events should get handled in which scope
 #[derive(PartialEq)]
 enum EventScope {
     Player,
     FileList,
+    BookmarkPicker,
+    DuplicateReview,
+    SetPlanner,
+    Facets,
+    Search,
+    QuickLoad,
+    ColumnPicker,
+    KeyPicker,
+    RestorePrompt,
+    MountPrompt,
+    Prompt,
+    Help,
+}
+
+/// ends of the channels, that the app talks to its workers through
+pub(crate) struct Channels {
+    player_messages_out: Sender<player::Message>,
+    player_events_in: Receiver<player::Event>,
+    analyzer_event_in: Receiver<analyzer::Event>,
+    analyzer_event_out: Sender<analyzer::Event>,
+    library_event_in: Receiver<watcher::Event>,
+    mount_event_in: Receiver<mounts::Event>,
 }
 
 pub struct App {
+    /// user settings
+    config: Config,
     //------------------------------------------------------------------//
     //                                UI                                //
     //------------------------------------------------------------------//
@@ -66,23 +201,222 @@ pub struct App {
     tracks: TrackList,
     /// current player position in number of packets.
     player_position: Arc<Mutex<Option<TimeMarker>>>,
+    /// smooths the player position for drawing
+    playhead: Playhead,
+    /// waveform lines of the live preview for the whole track, kept while the zoom doesn't
+    /// change and no samples are added
+    live_preview_bins: RenderCache<LivePreviewKey, Vec<PreviewSample>>,
+    /// waveform of the overview, kept while the pane doesn't change and no samples are added
+    preview_bins: RenderCache<(String, u16, usize), Vec<PreviewSample>>,
+    /// time at which the last frame was drawn
+    last_frame: Instant,
+    /// time it took to draw the last frame
+    frame_time: Duration,
+    /// whether the performance stats are shown
+    show_stats: bool,
+    /// load of the player and seconds of audio it decoded per second
+    player_stats: (f64, f64),
+    /// number of columns, that each waveform line covers. It grows, while drawing is too slow
+    /// for the frame rate
+    waveform_downscale: usize,
     /// zoom amount of live preview
     zoom_level: u32,
+    /// whether a beat grid at the BPM of the loaded track is drawn over the live preview
+    grid_check: bool,
+    /// areas of the live preview, the facet sidebar and the track table in the last frame, so
+    /// mouse events can be routed to the pane below the cursor
+    live_preview_area: Rect,
+    facets_area: Rect,
+    track_table_area: Rect,
+    /// effective format of the audio output
+    output_format: Option<String>,
+    /// short-term and integrated loudness of the output in LUFS
+    loudness: (Option<f64>, Option<f64>),
+    /// volume of the booth output
+    booth_volume: f32,
+    /// player for previewing library tracks, while the deck keeps playing
+    prelisten: Option<Prelisten>,
+    /// progress of the running library scan
+    scan: ScanProgress,
+    /// watchers, that keep the track list in sync with the library roots
+    library_watchers: Vec<RecommendedWatcher>,
+    /// removable drive, that the user is asked to scan
+    pending_mount: Option<PathBuf>,
+    /// file, that gets loaded, once the analyzer listed it
+    pending_load: Option<String>,
+    /// whether the next digit assigns the focused track to a quick-load slot instead of loading
+    /// the slot
+    quick_load_assign: bool,
+    /// time at which the player started opening the loaded track
+    loading: Option<Instant>,
+    /// time at which playback started waiting for slow storage
+    buffering: Option<Instant>,
+    /// time at which the cue key was last pressed or repeated, while it is held
+    cue_pressed: Option<Instant>,
+    /// time at which the end-of-track warning for the loaded track was received
+    end_of_track_warning: Option<Instant>,
+    /// active sleep timer preset in minutes and the point in time at which it stops playback
+    sleep_timer: Option<(u64, Instant)>,
+    /// whether playback stops at the end of the current track
+    stop_after_track: bool,
+    /// index of the playing audio stream within the loaded track's audio tracks
+    selected_stream: usize,
+    /// A-B repeat points of the loaded track
+    repeat: (Option<TimeMarker>, Option<TimeMarker>),
+    /// named positions within tracks
+    bookmarks: BookmarkStore,
+    /// parts of tracks, that their BPM is detected from instead of the configured window
+    bpm_windows: BpmWindowStore,
+    /// keys, that the user picked instead of the tagged ones
+    key_overrides: KeyOverrideStore,
+    /// how often and when tracks were played
+    play_stats: PlayStatsStore,
+    /// order of the track list
+    sort_order: SortOrder,
+    /// whether tracks, that were played within the last `fresh_days`, are hidden
+    only_fresh: bool,
+    /// genres, labels and years of the library, of which the selected ones narrow the track list
+    facets: Facets,
+    /// whether the facet sidebar is shown
+    show_facets: bool,
+    /// index of the focused entry in the facet sidebar
+    focused_facet: usize,
+    /// text, that listed tracks have to contain in their file name, title, artist or album
+    search: String,
+    /// question, that is waiting for an answer
+    prompt: Option<Prompt<PromptRequest>>,
+    /// index of the focused entry in the bookmark picker
+    focused_bookmark: usize,
+    /// history of edits, that can be undone
+    history: UndoStack,
+    /// session of the last run. Its track gets loaded, once the analyzer listed it
+    pending_session: Option<Session>,
+    /// track of the restored session and its playhead position, that is jumped to, once the
+    /// player loaded the track
+    pending_restore: Option<(String, TimeMarker)>,
+    /// journaled session of a run, that crashed. The user is asked, whether to restore it
+    crashed_session: Option<Session>,
+    /// time at which the journal was last written
+    journal_written: Instant,
+    /// terminal events, that are handled instead of the terminal's input, while the app is
+    /// simulated
+    simulated_input: Option<VecDeque<event::Event>>,
+    /// groups of tracks, that are copies of the same file
+    duplicates: Vec<Vec<Arc<Track>>>,
+    /// index of the focused track in the duplicate review, counted over all groups
+    focused_duplicate: usize,
+    /// tracks, that are planned to be played in this order
+    set_plan: SetPlan,
+    /// index of the focused track in the set planner
+    focused_plan_entry: usize,
+    /// track, that was picked automatically to follow the loaded track
+    picked_next: Option<Arc<Track>>,
+    /// file paths of picks, that were rejected for the loaded track
+    rejected_picks: Vec<String>,
+    /// index of the focused column in the column picker. Shown columns come first
+    focused_column: usize,
+    /// track, whose key is picked in the key picker
+    key_picker_track: Option<Arc<Track>>,
+    /// index of the focused entry in the key picker. The tagged key comes first
+    focused_key: usize,
+    //------------------------------------------------------------------//
+    //                             Sampler                              //
+    //------------------------------------------------------------------//
+    /// one-shot sample slots
+    sampler: Sampler,
+    /// sampler slot, that gain changes are applied to
+    selected_sampler_slot: usize,
 }
 
 impl Default for App {
     fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl App {
+    /// creates a new app with the given settings
+    pub fn new(config: Config) -> Self {
+        if let Err(err) = i18n::set_locale(&config.locale) {
+            warn!("failed to load the {} translations: {}", config.locale, err);
+        }
+        let session = Session::session_file()
+            .and_then(|path| Session::load(&path))
+            .unwrap_or_default();
+        let crashed_session = Session::journal_file().and_then(|path| Session::load(&path));
+        let active_event_scope = match crashed_session {
+            Some(_) => EventScope::RestorePrompt,
+            None => EventScope::FileList,
+        };
         Self {
+            config,
             player_position: Arc::new(Mutex::new(None)),
+            playhead: Playhead::default(),
+            live_preview_bins: RenderCache::default(),
+            preview_bins: RenderCache::default(),
+            last_frame: Instant::now(),
+            waveform_downscale: 1,
+            frame_time: Duration::ZERO,
+            show_stats: false,
+            player_stats: (0., 0.),
             latest_event: String::from(""),
             tracks: TrackList::default(),
-            active_event_scope: EventScope::FileList,
-            zoom_level: 50,
+            active_event_scope,
+            zoom_level: session.zoom_level.unwrap_or(50),
+            grid_check: false,
+            live_preview_area: Rect::default(),
+            facets_area: Rect::default(),
+            track_table_area: Rect::default(),
+            output_format: None,
+            loudness: (None, None),
+            booth_volume: 1.,
+            prelisten: None,
+            scan: ScanProgress::default(),
+            library_watchers: vec![],
+            pending_mount: None,
+            pending_load: None,
+            quick_load_assign: false,
+            loading: None,
+            buffering: None,
+            end_of_track_warning: None,
+            cue_pressed: None,
+            sleep_timer: None,
+            stop_after_track: false,
+            selected_stream: 0,
+            repeat: (None, None),
+            bookmarks: BookmarkStore::load(),
+            bpm_windows: BpmWindowStore::load(),
+            key_overrides: KeyOverrideStore::load(),
+            play_stats: PlayStatsStore::load(),
+            sort_order: SortOrder::Path,
+            only_fresh: false,
+            facets: Facets::default(),
+            show_facets: false,
+            focused_facet: 0,
+            search: String::new(),
+            prompt: None,
+            focused_bookmark: 0,
+            history: UndoStack::default(),
+            pending_session: Some(session)
+                .filter(|s| s.loaded_track.is_some() && crashed_session.is_none()),
+            pending_restore: None,
+            crashed_session,
+            journal_written: Instant::now(),
+            simulated_input: None,
+            duplicates: vec![],
+            focused_duplicate: 0,
+            set_plan: SetPlan::default(),
+            focused_plan_entry: 0,
+            picked_next: None,
+            rejected_picks: vec![],
+            focused_column: 0,
+            key_picker_track: None,
+            focused_key: 0,
+            sampler: Sampler::default(),
+            selected_sampler_slot: 0,
         }
     }
-}
 
-impl App {
     /// start the app
     pub async fn run(mut self) -> io::Result<()> {
         // init terminal
@@ -91,33 +425,146 @@ impl App {
         execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
+        let mut channels = self.start(Box::new(PulseSink::open));
+        // control the deck, while another window has focus
+        #[cfg(feature = "global-hotkeys")]
+        let global_hotkeys = GlobalHotkeys::register(&self.config);
+        loop {
+            let frame_interval = Duration::from_secs(1) / self.config.frame_rate;
+            if self.last_frame.elapsed() >= frame_interval {
+                self.last_frame = Instant::now();
+                terminal.draw(|f| self.render(f))?;
+                self.frame_time = self.last_frame.elapsed();
+                self.adapt_quality(self.frame_time, frame_interval);
+            } else {
+                std::thread::sleep(FRAME_WAIT);
+            }
+            self.tick(&mut channels).await;
+            #[cfg(feature = "global-hotkeys")]
+            for action in global_hotkeys.iter().flat_map(|hotkeys| hotkeys.poll()) {
+                match action {
+                    Action::TogglePlay => channels
+                        .player_messages_out
+                        .send(Message::TogglePlay)
+                        .unwrap(),
+                    Action::Cue => {
+                        self.cue_pressed = Some(Instant::now());
+                        channels.player_messages_out.send(Message::Cue).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    /// spawns the player, which plays on the outputs, that `open_sink` opens, and starts
+    /// scanning and watching the library. Returns the channels to the workers
+    pub(crate) fn start(&mut self, open_sink: OpenSink) -> Channels {
         // create message passing channels
-        let (player_events_out, mut player_events_in) = channel::<player::Event>();
+        let (player_events_out, player_events_in) = channel::<player::Event>();
         let (player_messages_out, player_messages_in) = channel::<player::Message>();
-        let (analyzer_event_out, mut analyzer_event_in) = channel::<analyzer::Event>();
+        let (analyzer_event_out, analyzer_event_in) = channel::<analyzer::Event>();
+        let (library_event_out, library_event_in) = channel::<watcher::Event>();
+        let (mount_event_out, mount_event_in) = channel::<mounts::Event>();
         // spawn player
-        let player_handle = Player::spawn(
+        Player::spawn_with_sink(
             Arc::clone(&self.player_position),
             player_messages_in,
             player_events_out,
+            self.config.clone(),
+            open_sink,
         );
-        // list tracks
-        let files = self
-            .scan_dir(Path::new("/home/data01/Music/test/"))
-            .unwrap();
-        // spawn analyzers
-        for file in files {
-            Analyzer::spawn(file, analyzer_event_out.clone());
+        self.prelisten = Some(Prelisten::spawn(&self.config));
+        let roots: Vec<LibraryRoot> = self
+            .config
+            .libraries
+            .iter()
+            .filter(|root| root.enabled)
+            .cloned()
+            .collect();
+        self.update_groups();
+        for root in roots.iter() {
+            self.scan_root(root, &root.path, &analyzer_event_out);
         }
-        loop {
-            terminal.draw(|f| self.render(f))?;
-            // only take key events every 250 milliseconds
-            self.update(
-                player_messages_out.clone(),
-                &mut player_events_in,
-                &mut analyzer_event_in,
-            )
-            .await;
+        if self.config.test_signals {
+            self.list_test_signals(&analyzer_event_out);
+        }
+        // keep the track list in sync with the library folders
+        self.library_watchers = roots
+            .iter()
+            .filter_map(|root| {
+                watcher::watch(&root.path, library_event_out.clone())
+                    .map_err(|err| warn!("failed to watch {}: {}", root.path.display(), err))
+                    .ok()
+            })
+            .collect();
+        // offer to scan removable drives, once they are plugged in
+        mounts::watch(mount_event_out);
+        Channels {
+            player_messages_out,
+            player_events_in,
+            analyzer_event_in,
+            analyzer_event_out,
+            library_event_in,
+            mount_event_in,
+        }
+    }
+
+    /// handles the pending input and worker events once, without drawing
+    pub(crate) async fn tick(&mut self, channels: &mut Channels) {
+        // only take key events every 250 milliseconds
+        self.update(
+            channels.player_messages_out.clone(),
+            &mut channels.player_events_in,
+            &mut channels.analyzer_event_in,
+            &channels.analyzer_event_out,
+            &mut channels.library_event_in,
+            &mut channels.mount_event_in,
+        )
+        .await;
+        if let Some(pressed) = self.cue_pressed {
+            if pressed.elapsed() >= CUE_RELEASE_TIMEOUT {
+                self.cue_pressed = None;
+                channels
+                    .player_messages_out
+                    .send(Message::CueRelease)
+                    .unwrap();
+            }
+        }
+        if self.journal_written.elapsed() >= JOURNAL_INTERVAL {
+            self.write_journal();
+        }
+    }
+
+    /// queues a terminal event, that is handled instead of the terminal's own input
+    pub(crate) fn simulate_input(&mut self, ev: event::Event) {
+        self.simulated_input
+            .get_or_insert_with(VecDeque::new)
+            .push_back(ev);
+    }
+
+    /// returns the next terminal event. Once input was simulated, the terminal is not read
+    fn next_input(&mut self) -> Option<event::Event> {
+        match &mut self.simulated_input {
+            Some(input) => input.pop_front(),
+            None => match event::poll(Duration::from_micros(1)) {
+                Ok(true) => Some(event::read().unwrap()),
+                _ => None,
+            },
+        }
+    }
+
+    /// returns the message about the latest event, that is shown in the status line
+    pub(crate) fn latest_event(&self) -> &str {
+        &self.latest_event
+    }
+
+    /// lowers the waveform detail, when drawing the last frame took longer than a frame, and
+    /// raises it again, once drawing is fast enough
+    fn adapt_quality(&mut self, draw_time: Duration, frame_interval: Duration) {
+        if draw_time > frame_interval && self.waveform_downscale < MAX_WAVEFORM_DOWNSCALE {
+            self.waveform_downscale *= 2;
+        } else if draw_time < frame_interval / 4 && self.waveform_downscale > 1 {
+            self.waveform_downscale /= 2;
         }
     }
 
@@ -127,29 +574,52 @@ impl App {
         player_messages_out: Sender<player::Message>,
         player_events_in: &mut Receiver<player::Event>,
         analyzer_event_in: &mut Receiver<analyzer::Event>,
+        analyzer_event_out: &Sender<analyzer::Event>,
+        library_event_in: &mut Receiver<watcher::Event>,
+        mount_event_in: &mut Receiver<mounts::Event>,
     ) -> () {
         //------------------------------------------------------------------//
         //                            Key Events                            //
         //------------------------------------------------------------------//
-        if let Ok(true) = event::poll(Duration::from_micros(1)) {
-            if let event::Event::Key(key) = event::read().unwrap() {
-                if let KeyModifiers::NONE = key.modifiers {
+        if let Some(ev) = self.next_input() {
+            if let event::Event::Mouse(mouse) = ev {
+                self.handle_mouse(mouse);
+            } else if let event::Event::Resize(..) = ev {
+                // waveforms are binned again for the new width, which may be drawn at full detail
+                self.waveform_downscale = 1;
+            } else if let event::Event::Key(key) = ev {
+                if self.active_event_scope == EventScope::Prompt {
+                    self.handle_prompt(key.code, analyzer_event_out);
+                } else if self.active_event_scope == EventScope::BookmarkPicker {
+                    self.handle_bookmark_picker(key.code, &player_messages_out);
+                } else if self.active_event_scope == EventScope::Help {
+                    // any key closes the help
+                    self.active_event_scope = EventScope::FileList;
+                } else if self.active_event_scope == EventScope::RestorePrompt {
+                    self.handle_restore_prompt(key.code, &player_messages_out);
+                } else if self.active_event_scope == EventScope::ColumnPicker {
+                    self.handle_column_picker(key.code);
+                } else if self.active_event_scope == EventScope::KeyPicker {
+                    self.handle_key_picker(key.code);
+                } else if self.active_event_scope == EventScope::QuickLoad {
+                    self.handle_quick_load(key.code, &player_messages_out, analyzer_event_out);
+                } else if self.active_event_scope == EventScope::MountPrompt {
+                    self.handle_mount_prompt(key.code, analyzer_event_out);
+                } else if self.active_event_scope == EventScope::DuplicateReview {
+                    self.handle_duplicate_review(key.code);
+                } else if self.active_event_scope == EventScope::SetPlanner {
+                    self.handle_set_planner(key.code, &player_messages_out);
+                } else if self.active_event_scope == EventScope::Facets {
+                    self.handle_facets(key.code);
+                } else if self.active_event_scope == EventScope::Search {
+                    self.handle_search(key.code);
+                } else if let KeyModifiers::NONE = key.modifiers {
                     // Events with no modifiers (local)
                     match key.code {
                         // zoom live preview in
-                        KeyCode::Char('+') => {
-                            let zl = self.zoom_level + 10;
-                            if zl <= 500 {
-                                self.zoom_level = zl;
-                            }
-                        }
+                        KeyCode::Char('+') => self.zoom(true),
                         // zoom live preview out
-                        KeyCode::Char('-') => {
-                            let zl = self.zoom_level - 10;
-                            if zl >= 50 {
-                                self.zoom_level = zl;
-                            }
-                        }
+                        KeyCode::Char('-') => self.zoom(false),
                         // go up a track
                         KeyCode::Char('j') => {
                             self.tracks.focus_next();
@@ -171,10 +641,202 @@ impl App {
                         // Toggle Play
                         KeyCode::Char(' ') => {
                             player_messages_out.send(Message::TogglePlay).unwrap();
-                            self.latest_event = String::from("TogglePlay");
+                            self.latest_event = tr("event.toggle_play");
+                        }
+                        // cycle sleep timer presets
+                        KeyCode::Char('z') => {
+                            let minutes = match self.sleep_timer {
+                                None => Some(SLEEP_TIMER_PRESETS[0]),
+                                Some((current, _)) => {
+                                    SLEEP_TIMER_PRESETS.iter().find(|m| **m > current).copied()
+                                }
+                            };
+                            let duration = minutes.map(|m| Duration::from_secs(m * 60));
+                            player_messages_out
+                                .send(Message::SetSleepTimer(
+                                    duration.map(|d| (d, SleepMode::FadeOut)),
+                                ))
+                                .unwrap();
+                            self.sleep_timer =
+                                minutes.zip(duration).map(|(m, d)| (m, Instant::now() + d));
+                            self.stop_after_track = false;
+                            self.latest_event = match minutes {
+                                Some(m) => i18n::tr_args("event.sleep_timer", &[&m]),
+                                None => tr("event.sleep_timer_off"),
+                            };
+                        }
+                        // set A-B repeat point A, then point B, then leave the repeat right away
+                        KeyCode::Char('r') => {
+                            let player_pos = (*self.player_position.lock().unwrap()).clone();
+                            self.repeat = match (self.repeat.clone(), player_pos) {
+                                ((None, _), Some(pos)) => {
+                                    self.latest_event = tr("event.repeat_a");
+                                    (Some(pos), None)
+                                }
+                                ((Some(a), None), Some(b))
+                                    if b.get_timestamp() > a.get_timestamp() =>
+                                {
+                                    player_messages_out
+                                        .send(Message::SetRepeat(Some((a.clone(), b.clone()))))
+                                        .unwrap();
+                                    self.latest_event = tr("event.repeat_ab");
+                                    (Some(a), Some(b))
+                                }
+                                ((Some(a), None), _) => {
+                                    self.latest_event = tr("event.repeat_b_before_a");
+                                    (Some(a), None)
+                                }
+                                _ => {
+                                    player_messages_out.send(Message::SetRepeat(None)).unwrap();
+                                    self.latest_event = tr("event.repeat_off");
+                                    (None, None)
+                                }
+                            };
+                        }
+                        // add bookmark at the playhead
+                        KeyCode::Char('b') => {
+                            let player_pos = (*self.player_position.lock().unwrap()).clone();
+                            if let (Some(track), Some(pos)) = (self.tracks.get_loaded(), player_pos)
+                            {
+                                let number = self.bookmarks.get(&track.file_path).len() + 1;
+                                let name = i18n::tr_args("marker.default_bookmark", &[&number]);
+                                self.latest_event = match self.bookmarks.add(
+                                    &track.file_path,
+                                    pos.get_timestamp(),
+                                    &name,
+                                ) {
+                                    Ok(()) => i18n::tr_args("event.bookmark_added", &[&name]),
+                                    Err(err) => i18n::tr_args("event.bookmarks_not_saved", &[&err]),
+                                };
+                                self.history.push(Edit::AddBookmark {
+                                    file_path: track.file_path.clone(),
+                                    ts: pos.get_timestamp(),
+                                    name,
+                                });
+                            }
+                        }
+                        // load a quick-load slot, which is chosen with the next digit
+                        KeyCode::Char('q') => {
+                            self.quick_load_assign = false;
+                            self.active_event_scope = EventScope::QuickLoad;
+                            self.latest_event = tr("event.quick_load");
+                        }
+                        // append the focused track to the planned set
+                        KeyCode::Char('a') => {
+                            if let Some(track) = self.tracks.get_focused() {
+                                self.latest_event =
+                                    i18n::tr_args("event.planned", &[&track.file_name]);
+                                self.set_plan.push(track);
+                            }
+                        }
+                        // open the set planner
+                        KeyCode::Char('s') => {
+                            if self.set_plan.tracks().is_empty() {
+                                self.latest_event = tr("event.nothing_planned");
+                            } else {
+                                // the plan might have shrunk, since the planner was last open
+                                self.focused_plan_entry = self
+                                    .focused_plan_entry
+                                    .min(self.set_plan.tracks().len() - 1);
+                                self.active_event_scope = EventScope::SetPlanner;
+                            }
+                        }
+                        // cycle the order of the track list
+                        KeyCode::Char('o') => {
+                            self.sort_order = match self.sort_order {
+                                SortOrder::Path => SortOrder::PlayCount,
+                                SortOrder::PlayCount => SortOrder::LastPlayed,
+                                SortOrder::LastPlayed => SortOrder::Path,
+                            };
+                            self.sort_tracks();
+                        }
+                        // only list tracks, that weren't played recently, or all of them
+                        KeyCode::Char('f') => {
+                            self.only_fresh = !self.only_fresh;
+                            self.filter_tracks();
+                            self.latest_event = if self.only_fresh {
+                                i18n::tr_args("event.only_fresh", &[&self.config.fresh_days])
+                            } else {
+                                tr("event.all_tracks")
+                            };
+                        }
+                        // double the BPM of the focused track
+                        KeyCode::Char('*') => self.scale_bpm(true),
+                        // halve the BPM of the focused track
+                        KeyCode::Char('/') => self.scale_bpm(false),
+                        // show the help overlay
+                        KeyCode::Char('?') => self.active_event_scope = EventScope::Help,
+                        // collapse or expand the library group of the focused track
+                        KeyCode::Tab => self.tracks.toggle_group(),
+                        // show or hide the performance stats
+                        KeyCode::F(12) => self.show_stats = !self.show_stats,
+                        // open bookmark picker
+                        KeyCode::Char('g') => {
+                            if let Some(track) = self.tracks.get_loaded() {
+                                if self.bookmarks.get(&track.file_path).is_empty() {
+                                    self.latest_event = tr("event.no_bookmarks");
+                                } else {
+                                    self.focused_bookmark = 0;
+                                    self.active_event_scope = EventScope::BookmarkPicker;
+                                }
+                            }
+                        }
+                        // skip to previous cue sheet track
+                        KeyCode::Char('[') => {
+                            self.skip_cue_sheet_track(false, &player_messages_out)
+                        }
+                        // skip to next cue sheet track
+                        KeyCode::Char(']') => self.skip_cue_sheet_track(true, &player_messages_out),
+                        // cycle audio streams of files with several audio tracks
+                        KeyCode::Char('t') => {
+                            if let Some(track) = self.tracks.get_loaded() {
+                                let num_streams = track.audio_track_ids.len();
+                                if num_streams > 1 {
+                                    self.selected_stream = (self.selected_stream + 1) % num_streams;
+                                    let track_id = track.audio_track_ids[self.selected_stream];
+                                    player_messages_out
+                                        .send(Message::SelectStream(track_id))
+                                        .unwrap();
+                                    self.latest_event = i18n::tr_args(
+                                        "event.audio_stream",
+                                        &[&(self.selected_stream + 1), &num_streams],
+                                    );
+                                }
+                            }
+                        }
+                        // press cue. Held keys repeat, so the release is noticed, once they stop
+                        KeyCode::Char('c') => {
+                            self.cue_pressed = Some(Instant::now());
+                            player_messages_out.send(Message::Cue).unwrap();
+                        }
+                        // pre-listen the focused track, cycling through its positions
+                        KeyCode::Char('p') => {
+                            if let (Some(prelisten), Some(track)) =
+                                (&mut self.prelisten, self.tracks.get_focused())
+                            {
+                                let position =
+                                    prelisten.cycle(&track.file_path, &track.codec_params);
+                                self.latest_event = match position {
+                                    Some(position) => i18n::tr_args(
+                                        "event.prelistening",
+                                        &[&track.file_name, &format!("{:.0}", position * 100.)],
+                                    ),
+                                    None if track.codec_params.n_frames.is_none() => i18n::tr_args(
+                                        "event.prelisten_unknown_length",
+                                        &[&track.file_name],
+                                    ),
+                                    None => tr("event.prelisten_stopped"),
+                                };
+                            }
+                        }
+                        // raise or lower the volume of the booth output
+                        KeyCode::Char(c @ ('<' | '>')) if self.config.booth_device.is_some() => {
+                            let step = if c == '>' { 0.1 } else { -0.1 };
+                            self.booth_volume = (self.booth_volume + step).clamp(0., 1.);
+                            player_messages_out
+                                .send(Message::SetBoothVolume(self.booth_volume))
+                                .unwrap();
                         }
-                        // press cue
-                        KeyCode::Char('c') => player_messages_out.send(Message::Cue).unwrap(),
                         // new cue marker
                         KeyCode::Char('m') => {
                             let player_pos = &(*self.player_position.lock().unwrap());
@@ -184,24 +846,24 @@ impl App {
                                     tm.get_timestamp(),
                                     track.codec_params.clone(),
                                 );
-                                track.add_mem_cue(cue_marker);
+                                track.add_mem_cue(cue_marker.clone());
+                                self.history.push(Edit::AddMemCue(track, cue_marker));
                             }
                         }
+                        // trigger sampler slot
+                        KeyCode::F(n) if n >= 1 && n as usize <= SAMPLER_SLOTS => {
+                            let slot = n as usize - 1;
+                            self.sampler.trigger(slot, &player_messages_out);
+                            self.selected_sampler_slot = slot;
+                        }
                         // Load Track
                         KeyCode::Enter => {
                             if self.active_event_scope != EventScope::FileList {
                                 ()
                             };
-                            let focused = self.tracks.load_focused();
-                            if let Some(track) = focused {
-                                player_messages_out
-                                    .send(Message::Load(track.file_path.clone()))
-                                    .unwrap();
-                                self.latest_event =
-                                    String::from(format!("Loaded {}", track.file_path));
-                            }
+                            self.load_focused(&player_messages_out);
                         }
-                        _ => self.latest_event = String::from("Unknown Command"),
+                        _ => self.latest_event = tr("event.unknown_command"),
                     }
                 } else {
                     // Events with modifier (global)
@@ -224,9 +886,398 @@ impl App {
                         KeyEvent {
                             code: KeyCode::Char('q'),
                             modifiers: KeyModifiers::ALT,
-                        } => std::process::exit(0),
+                        } => {
+                            self.save_session();
+                            std::process::exit(0)
+                        }
+                        // export the library meta data
+                        KeyEvent {
+                            code: KeyCode::Char('e'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            let path = &self.config.library_export;
+                            self.latest_event =
+                                match library_export::export(self.tracks.values(), path) {
+                                    Ok(n) => {
+                                        i18n::tr_args("event.exported", &[&n, &path.display()])
+                                    }
+                                    Err(err) => i18n::tr_args("event.export_failed", &[&err]),
+                                };
+                        }
+                        // bundle the analysis results, cues and bookmarks of the focused group
+                        KeyEvent {
+                            code: KeyCode::Char('b'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            let path = &self.config.analysis_bundle;
+                            self.latest_event = match analysis_bundle::export(
+                                &self.tracks.focused_group(),
+                                &self.bookmarks,
+                                &self.config,
+                                path,
+                            ) {
+                                Ok(n) => i18n::tr_args("event.bundled", &[&n, &path.display()]),
+                                Err(err) => i18n::tr_args("event.bundle_failed", &[&err]),
+                            };
+                        }
+                        // restore analysis results, cues and bookmarks from a bundle
+                        KeyEvent {
+                            code: KeyCode::Char('u'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            let path = &self.config.analysis_bundle;
+                            self.latest_event = match analysis_bundle::import(
+                                self.tracks.values(),
+                                &mut self.bookmarks,
+                                &self.config,
+                                path,
+                            ) {
+                                Ok(n) => i18n::tr_args("event.imported", &[&n, &path.display()]),
+                                Err(err) => i18n::tr_args("event.bundle_restore_failed", &[&err]),
+                            };
+                        }
+                        // assign the focused track to a quick-load slot, which is chosen with the
+                        // next digit
+                        KeyEvent {
+                            code: KeyCode::Char('Q'),
+                            modifiers: KeyModifiers::SHIFT,
+                        } => {
+                            self.quick_load_assign = true;
+                            self.active_event_scope = EventScope::QuickLoad;
+                            self.latest_event = tr("event.quick_load_assign");
+                        }
+                        // show the facet sidebar and focus it
+                        KeyEvent {
+                            code: KeyCode::Char('f'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            self.facets.update(self.tracks.values().iter());
+                            self.focused_facet = self
+                                .focused_facet
+                                .min(self.facets.entries().len().saturating_sub(1));
+                            self.show_facets = true;
+                            self.active_event_scope = EventScope::Facets;
+                        }
+                        // search the track list
+                        KeyEvent {
+                            code: KeyCode::Char('f'),
+                            modifiers: KeyModifiers::CONTROL,
+                        } => self.active_event_scope = EventScope::Search,
+                        // stop after the current track, instead of fading out on a timer
+                        KeyEvent {
+                            code: KeyCode::Char('Z'),
+                            modifiers: KeyModifiers::SHIFT,
+                        } => {
+                            self.stop_after_track = !self.stop_after_track;
+                            let timer = self
+                                .stop_after_track
+                                .then_some((Duration::ZERO, SleepMode::AfterTrack));
+                            player_messages_out
+                                .send(Message::SetSleepTimer(timer))
+                                .unwrap();
+                            self.sleep_timer = None;
+                            self.latest_event = match self.stop_after_track {
+                                true => tr("event.stop_after_track"),
+                                false => tr("event.stop_after_track_off"),
+                            };
+                        }
+                        // leave the A-B repeat, once point B is reached
+                        KeyEvent {
+                            code: KeyCode::Char('R'),
+                            modifiers: KeyModifiers::SHIFT,
+                        } => {
+                            if let (Some(_), Some(_)) = self.repeat {
+                                player_messages_out
+                                    .send(Message::ExitRepeat(RepeatExit::AtEnd))
+                                    .unwrap();
+                                self.latest_event = tr("event.repeat_leaving");
+                            }
+                        }
+                        // leave the A-B repeat and jump ahead by its length, as if it never
+                        // looped
+                        KeyEvent {
+                            code: KeyCode::Char('r'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            if let (Some(_), Some(_)) = self.repeat {
+                                player_messages_out
+                                    .send(Message::ExitRepeat(RepeatExit::Roll))
+                                    .unwrap();
+                                self.repeat = (None, None);
+                                self.latest_event = tr("event.repeat_rolled_out");
+                            }
+                        }
+                        // type the BPM of the focused track
+                        KeyEvent {
+                            code: KeyCode::Char('B'),
+                            modifiers: KeyModifiers::SHIFT,
+                        } => {
+                            if let Some(track) = self.tracks.get_focused() {
+                                let bpm = track.meta.read().unwrap().bpm;
+                                self.open_prompt(Prompt::number(
+                                    &tr("prompt.bpm.title"),
+                                    &i18n::tr_args("prompt.bpm", &[&track.file_name]),
+                                    &if bpm > 0 {
+                                        bpm.to_string()
+                                    } else {
+                                        String::new()
+                                    },
+                                    PromptRequest::SetBpm(track),
+                                ));
+                            }
+                        }
+                        // mark the focused track for editing its tags together with others
+                        KeyEvent {
+                            code: KeyCode::Char('M'),
+                            modifiers: KeyModifiers::SHIFT,
+                        } => {
+                            if let Some(track) = self.tracks.get_focused() {
+                                let id = match self.tracks.toggle_mark() {
+                                    true => "event.marked",
+                                    false => "event.unmarked",
+                                };
+                                self.latest_event = i18n::tr_args(id, &[&track.file_name]);
+                                self.tracks.focus_next();
+                            }
+                        }
+                        // edit the tags of the marked tracks, or of the focused one
+                        KeyEvent {
+                            code: KeyCode::Char('E'),
+                            modifiers: KeyModifiers::SHIFT,
+                        } => {
+                            let mut tracks = self.tracks.marked();
+                            if tracks.is_empty() {
+                                tracks.extend(self.tracks.get_focused());
+                            }
+                            tracks.retain(|track| track.unsupported.is_none());
+                            if !tracks.is_empty() {
+                                self.prompt_tag(tracks, 0, vec![]);
+                            }
+                        }
+                        // choose the part of the focused track, that its BPM is detected from
+                        KeyEvent {
+                            code: KeyCode::Char('w'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            if let Some(track) = self.tracks.get_focused() {
+                                let window = self
+                                    .bpm_windows
+                                    .get(&track.file_path)
+                                    .map_or(String::new(), |(start, end)| {
+                                        format!("{}-{}", start, end)
+                                    });
+                                self.open_prompt(Prompt::text(
+                                    &tr("prompt.bpm_window.title"),
+                                    &i18n::tr_args("prompt.bpm_window", &[&track.file_name]),
+                                    &window,
+                                    PromptRequest::SetBpmWindow(track),
+                                ));
+                            }
+                        }
+                        // mark the tracks, that were played during a recorded mix, as its chapters
+                        KeyEvent {
+                            code: KeyCode::Char('m'),
+                            modifiers: KeyModifiers::ALT,
+                        } => self.open_prompt(Prompt::text(
+                            &tr("prompt.chapters.title"),
+                            &tr("prompt.chapters"),
+                            "",
+                            PromptRequest::WriteChapters,
+                        )),
+                        // check the BPM of the loaded track against a beat grid
+                        KeyEvent {
+                            code: KeyCode::Char('g'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            self.grid_check = !self.grid_check;
+                            self.latest_event = tr(if self.grid_check {
+                                "event.grid_shown"
+                            } else {
+                                "event.grid_hidden"
+                            });
+                        }
+                        // replace the automatically picked track with the next best one
+                        KeyEvent {
+                            code: KeyCode::Char('n'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            if let Some(track) = self.picked_next.take() {
+                                if let Some(i) = self
+                                    .set_plan
+                                    .tracks()
+                                    .iter()
+                                    .position(|t| Arc::ptr_eq(t, &track))
+                                {
+                                    self.set_plan.remove(i);
+                                    self.focused_plan_entry = self
+                                        .focused_plan_entry
+                                        .min(self.set_plan.tracks().len().saturating_sub(1));
+                                }
+                                self.rejected_picks.push(track.file_path.clone());
+                                self.pick_next();
+                            }
+                        }
+                        // pick the key of the focused track
+                        KeyEvent {
+                            code: KeyCode::Char('k'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            if let Some(track) = self.tracks.get_focused() {
+                                let user_key = track.meta.read().unwrap().user_key.clone();
+                                self.focused_key = key_overrides::KEYS
+                                    .iter()
+                                    .position(|(key, _)| *key == user_key)
+                                    .map_or(0, |i| i + 1);
+                                self.key_picker_track = Some(track);
+                                self.active_event_scope = EventScope::KeyPicker;
+                            }
+                        }
+                        // choose the columns of the track table
+                        KeyEvent {
+                            code: KeyCode::Char('t'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            self.focused_column = 0;
+                            self.active_event_scope = EventScope::ColumnPicker;
+                        }
+                        // load the file, whose path or file:// URL is on the clipboard
+                        KeyEvent {
+                            code: KeyCode::Char('v'),
+                            modifiers: KeyModifiers::ALT,
+                        } => self.load_from_clipboard(&player_messages_out, analyzer_event_out),
+                        // restore the library meta data from an export
+                        KeyEvent {
+                            code: KeyCode::Char('i'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            let path = &self.config.library_export;
+                            let before: Vec<(Arc<Track>, TrackMeta)> = self
+                                .tracks
+                                .values()
+                                .iter()
+                                .map(|track| {
+                                    (Arc::clone(track), track.meta.read().unwrap().clone())
+                                })
+                                .collect();
+                            self.latest_event =
+                                match library_export::import(self.tracks.values(), path) {
+                                    Ok(n) => {
+                                        i18n::tr_args("event.imported", &[&n, &path.display()])
+                                    }
+                                    Err(err) => i18n::tr_args("event.import_failed", &[&err]),
+                                };
+                            let changes: Vec<(Arc<Track>, TrackMeta, TrackMeta)> = before
+                                .into_iter()
+                                .filter_map(|(track, before)| {
+                                    let after = track.meta.read().unwrap().clone();
+                                    (after != before).then_some((track, before, after))
+                                })
+                                .collect();
+                            if !changes.is_empty() {
+                                self.history.push(Edit::ChangeMeta(changes));
+                            }
+                        }
+                        // reload the config file and analyze all tracks again, if needed
+                        KeyEvent {
+                            code: KeyCode::Char('c'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            let config = Config::load();
+                            if config.analysis_differs(&self.config) {
+                                self.scan.found += self.tracks.values().len();
+                                for track in self.tracks.values() {
+                                    Analyzer::spawn(
+                                        track.file_path.clone(),
+                                        config.clone(),
+                                        self.bpm_windows.get(&track.file_path),
+                                        analyzer_event_out.clone(),
+                                    );
+                                }
+                                self.latest_event = tr("event.config_reloaded_reanalyzing");
+                            } else {
+                                self.latest_event = tr("event.config_reloaded");
+                            }
+                            self.config = config;
+                        }
+                        // undo the latest edit
+                        KeyEvent {
+                            code: KeyCode::Char('z'),
+                            modifiers: KeyModifiers::CONTROL,
+                        } => {
+                            self.latest_event = match self.history.undo() {
+                                Some(edit) => {
+                                    self.apply_edit(&edit, true);
+                                    i18n::tr_args("event.undone", &[&edit])
+                                }
+                                None => tr("event.nothing_to_undo"),
+                            };
+                        }
+                        // measure the output latency and compensate the playhead for it
+                        KeyEvent {
+                            code: KeyCode::Char('l'),
+                            modifiers: KeyModifiers::CONTROL,
+                        } => player_messages_out.send(Message::MeasureLatency).unwrap(),
+                        // redo the latest undone edit
+                        KeyEvent {
+                            code: KeyCode::Char('r'),
+                            modifiers: KeyModifiers::CONTROL,
+                        } => {
+                            self.latest_event = match self.history.redo() {
+                                Some(edit) => {
+                                    self.apply_edit(&edit, false);
+                                    i18n::tr_args("event.redone", &[&edit])
+                                }
+                                None => tr("event.nothing_to_redo"),
+                            };
+                        }
+                        // find duplicate files in the library
+                        KeyEvent {
+                            code: KeyCode::Char('d'),
+                            modifiers: KeyModifiers::ALT,
+                        } => {
+                            self.duplicates = duplicates::find_duplicates(self.tracks.values());
+                            if self.duplicates.is_empty() {
+                                self.latest_event = tr("event.no_duplicates");
+                            } else {
+                                self.focused_duplicate = 0;
+                                self.active_event_scope = EventScope::DuplicateReview;
+                            }
+                        }
+                        // load focused track into sampler slot
+                        KeyEvent {
+                            code: KeyCode::Char(c @ '1'..='9'),
+                            modifiers: KeyModifiers::ALT,
+                        } if c.to_digit(10).unwrap() as usize <= SAMPLER_SLOTS => {
+                            let slot = c.to_digit(10).unwrap() as usize - 1;
+                            if let Some(track) = self.tracks.get_focused() {
+                                self.latest_event =
+                                    match self.sampler.load(slot, track.file_path.clone()) {
+                                        Ok(_) => {
+                                            self.selected_sampler_slot = slot;
+                                            i18n::tr_args(
+                                                "event.sample_loaded",
+                                                &[&track.file_name, &(slot + 1)],
+                                            )
+                                        }
+                                        Err(err) => i18n::tr_args(
+                                            "event.sample_load_failed",
+                                            &[&track.file_name, &err],
+                                        ),
+                                    };
+                            }
+                        }
+                        // raise gain of selected sampler slot
+                        KeyEvent {
+                            code: KeyCode::Char('+'),
+                            modifiers: KeyModifiers::ALT,
+                        } => self.sampler.change_gain(self.selected_sampler_slot, 0.1),
+                        // lower gain of selected sampler slot
+                        KeyEvent {
+                            code: KeyCode::Char('-'),
+                            modifiers: KeyModifiers::ALT,
+                        } => self.sampler.change_gain(self.selected_sampler_slot, -0.1),
                         // unknown key command
-                        _ => self.latest_event = String::from("Unknown Command"),
+                        _ => self.latest_event = tr("event.unknown_command"),
                     }
                 };
             }
@@ -234,55 +1285,1488 @@ impl App {
         //------------------------------------------------------------------//
         //                          Player Events                           //
         //------------------------------------------------------------------//
-        // if let Ok(ev) = player_events_in.try_recv() {
-        //     match ev {
-        //         player::Event::PlayedPackages(num_packets) => {
-        //             self.player_position += num_packets;
-        //         }
-        //     }
-        // }
+        if let Some(reason) = self.prelisten.as_mut().and_then(|p| p.update()) {
+            self.latest_event = i18n::tr_args("event.prelisten_failed", &[&reason]);
+        }
+        if let Ok(ev) = player_events_in.try_recv() {
+            match ev {
+                player::Event::EndOfTrackWarning(remaining) => {
+                    self.end_of_track_warning = Some(Instant::now());
+                    self.latest_event =
+                        i18n::tr_args("event.end_of_track", &[&format_time(remaining)]);
+                    if self.config.auto_pick && self.picked_next.is_none() && self.nothing_planned()
+                    {
+                        self.pick_next();
+                    }
+                }
+                player::Event::SleepTimerExpired => {
+                    self.sleep_timer = None;
+                    self.stop_after_track = false;
+                    self.latest_event = tr("event.sleep_timer_expired");
+                }
+                player::Event::LoadStarted(_) => self.loading = Some(Instant::now()),
+                player::Event::Loaded(path) => {
+                    self.loading = None;
+                    self.buffering = None;
+                    self.latest_event = i18n::tr_args("event.loaded", &[&path]);
+                    // a jump sent before the track is loaded would be lost
+                    if let Some((_, marker)) = self
+                        .pending_restore
+                        .take()
+                        .filter(|(restored, _)| *restored == path)
+                    {
+                        player_messages_out.send(Message::JumpTo(marker)).unwrap();
+                    }
+                }
+                player::Event::LoadFailed(path, reason) => {
+                    self.loading = None;
+                    self.latest_event = i18n::tr_args("event.load_failed", &[&path, &reason]);
+                }
+                player::Event::OutputLost => {
+                    self.latest_event = tr("event.output_lost");
+                }
+                player::Event::OutputRestored => {
+                    self.latest_event = tr("event.output_restored");
+                }
+                player::Event::Buffering(buffering) => {
+                    self.buffering = Some(Instant::now()).filter(|_| buffering);
+                }
+                player::Event::Loudness(short_term, integrated) => {
+                    self.loudness = (short_term, integrated);
+                }
+                player::Event::Stats(load, throughput) => self.player_stats = (load, throughput),
+                player::Event::RepeatExited => {
+                    self.repeat = (None, None);
+                    self.latest_event = tr("event.repeat_off");
+                }
+                player::Event::Played => {
+                    if let Some(track) = self.tracks.get_loaded() {
+                        // the track started playing as long ago, as its position tells
+                        let position = (*self.player_position.lock().unwrap())
+                            .as_ref()
+                            .map_or(0., |pos| pos.get_time_in_seconds());
+                        let started = play_stats::now().saturating_sub(position as u64);
+                        if let Err(err) = self.play_stats.record(&track.file_path, started) {
+                            warn!("failed to save play statistics: {}", err);
+                        }
+                    }
+                }
+                player::Event::Latency(Some(latency)) => {
+                    let millis = (latency.as_secs_f64() * 1000.).round();
+                    self.config.latency = millis;
+                    self.latest_event = match Config::save_setting("latency", &millis.to_string()) {
+                        Ok(()) => i18n::tr_args("event.latency_saved", &[&millis]),
+                        Err(err) => i18n::tr_args("event.latency_not_saved", &[&err]),
+                    };
+                }
+                player::Event::Latency(None) => {
+                    self.latest_event = tr("event.latency_unknown");
+                }
+                player::Event::OutputFormat(spec) => {
+                    self.output_format = Some(format!(
+                        "f32 {:.1}kHz {}ch",
+                        spec.rate as f64 / 1000.,
+                        spec.channels.count()
+                    ));
+                }
+            }
+        }
         //------------------------------------------------------------------//
         //                         Analyzer Events                          //
         //------------------------------------------------------------------//
         if let Ok(ev) = analyzer_event_in.try_recv() {
             match ev {
                 analyzer::Event::DoneAnalyzing(track) => {
-                    self.latest_event = String::from(format!("Analyzed: {}", track));
+                    self.scan.analyzed += 1;
+                    self.latest_event = i18n::tr_args("event.analyzed", &[&track]);
+                }
+                analyzer::Event::NewTrack(track) => {
+                    match track.unsupported {
+                        Some(_) => self.scan.failed += 1,
+                        None => self.scan.probed += 1,
+                    }
+                    if let Some(key) = self.key_overrides.get(&track.file_path) {
+                        track.meta.write().unwrap().user_key = String::from(key);
+                    }
+                    self.tracks.insert(Arc::clone(&track));
+                    if !self.passes_filters(&track) {
+                        self.tracks.hide(&track.file_path);
+                    }
+                    self.restore_session(&track, &player_messages_out);
+                    if self.pending_load.as_deref() == Some(track.file_path.as_str()) {
+                        self.pending_load = None;
+                        if self.tracks.focus(&track.file_path).is_some() {
+                            self.load_focused(&player_messages_out);
+                        }
+                    }
                 }
-                analyzer::Event::NewTrack(track) => self.tracks.insert(track),
             }
         }
-    }
-
-    /// define how the app should look like
-    fn render<B: Backend>(&mut self, f: &mut Frame<B>) {
-        // TODO: refactor
-        let window = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(
-                [
-                    // split for the live preview
-                    Constraint::Percentage(10),
-                    // split for the waveform overview
-                    Constraint::Percentage(5),
-                    // split for the main body
-                    Constraint::Percentage(83),
-                    // split for the footer
-                    Constraint::Percentage(2),
-                ]
+        //------------------------------------------------------------------//
+        //                          Library Events                          //
+        //------------------------------------------------------------------//
+        if let Ok(ev) = library_event_in.try_recv() {
+            match ev {
+                // new and changed files get probed (again), which replaces their list entry
+                watcher::Event::Created(file_path) | watcher::Event::Modified(file_path)
+                    if self.config.scans(Path::new(&file_path)) =>
+                {
+                    self.scan.found += 1;
+                    let bpm_window = self.bpm_windows.get(&file_path);
+                    Analyzer::spawn(
+                        file_path,
+                        self.config.clone(),
+                        bpm_window,
+                        analyzer_event_out.clone(),
+                    );
+                }
+                // files, that the patterns exclude, stay out of the library
+                watcher::Event::Created(_) | watcher::Event::Modified(_) => {}
+                watcher::Event::Removed(file_path) => {
+                    self.tracks.remove(Path::new(&file_path));
+                    self.latest_event = i18n::tr_args("event.removed", &[&file_path]);
+                }
+            }
+        }
+        //------------------------------------------------------------------//
+        //                           Mount Events                           //
+        //------------------------------------------------------------------//
+        if let Ok(ev) = mount_event_in.try_recv() {
+            match ev {
+                mounts::Event::Mounted(path) => {
+                    self.set_available(&path, true);
+                    // roots on the drive, or a root containing it, are scanned right away
+                    let roots: Vec<LibraryRoot> = self
+                        .config
+                        .libraries
+                        .iter()
+                        .filter(|root| root.enabled && root.path.starts_with(&path))
+                        .cloned()
+                        .collect();
+                    if let Some(root) = self.config.root_of(&path).cloned() {
+                        self.scan_root(&root, &path, analyzer_event_out);
+                        self.latest_event = i18n::tr_args("event.scanning", &[&path.display()]);
+                    } else if !roots.is_empty() {
+                        for root in roots.iter() {
+                            self.scan_root(root, &root.path, analyzer_event_out);
+                        }
+                        self.latest_event = i18n::tr_args("event.scanning", &[&path.display()]);
+                    } else if self.active_event_scope == EventScope::FileList {
+                        self.pending_mount = Some(path);
+                        self.active_event_scope = EventScope::MountPrompt;
+                    }
+                }
+                mounts::Event::Unmounted(path) => {
+                    let count = self.set_available(&path, false);
+                    self.latest_event =
+                        i18n::tr_args("event.drive_removed", &[&path.display(), &count]);
+                    if self.pending_mount.as_ref() == Some(&path) {
+                        self.pending_mount = None;
+                        self.active_event_scope = EventScope::FileList;
+                    }
+                }
+            }
+        }
+        // start counting from zero for the next scan
+        if self.scan.is_done() {
+            self.scan = ScanProgress::default();
+        }
+    }
+
+    /// loads the focused track into the player. Files, that failed probing or whose drive was
+    /// removed, are refused.
+    fn load_focused(&mut self, player_messages_out: &Sender<player::Message>) {
+        if let Some(reason) = self
+            .tracks
+            .get_focused()
+            .and_then(|track| track.unsupported.clone())
+        {
+            self.latest_event = i18n::tr_args("event.cant_load", &[&reason]);
+            return;
+        }
+        if let Some(track) = self.tracks.get_focused().filter(|t| !t.is_available()) {
+            self.latest_event = i18n::tr_args("event.drive_missing", &[&track.file_name]);
+            return;
+        }
+        let focused = self.tracks.load_focused();
+        if let Some(track) = focused {
+            player_messages_out
+                .send(Message::Load(track.file_path.clone()))
+                .unwrap();
+            self.end_of_track_warning = None;
+            self.repeat = (None, None);
+            self.selected_stream = 0;
+            self.picked_next = None;
+            self.rejected_picks.clear();
+            self.pending_restore = None;
+            self.latest_event = i18n::tr_args("event.loaded", &[&track.file_path]);
+        }
+    }
+
+    /// returns true, if the set plan has no track after the loaded one
+    fn nothing_planned(&self) -> bool {
+        let loaded = match self.tracks.get_loaded() {
+            Some(loaded) => loaded,
+            None => return false,
+        };
+        let plan = self.set_plan.tracks();
+        match plan.iter().position(|t| t.file_path == loaded.file_path) {
+            Some(i) => i + 1 == plan.len(),
+            None => plan.is_empty(),
+        }
+    }
+
+    /// picks a track from the library, that mixes well into the loaded one, and appends it to
+    /// the set plan. Planned, rejected and recently played tracks are avoided
+    fn pick_next(&mut self) {
+        let loaded = match self.tracks.get_loaded() {
+            Some(loaded) => loaded,
+            None => return,
+        };
+        let plan = self.set_plan.tracks();
+        let candidates = self.tracks.values().iter().filter(|track| {
+            !self.rejected_picks.contains(&track.file_path)
+                && !plan.iter().any(|t| t.file_path == track.file_path)
+                && track.is_available()
+        });
+        let play_stats = &self.play_stats;
+        let fresh_days = self.config.fresh_days;
+        let picked = set_plan::pick_next(&loaded, candidates, |track| {
+            play_stats.played_within(&track.file_path, fresh_days)
+        });
+        self.latest_event = match &picked {
+            Some(track) => i18n::tr_args("event.picked_next", &[&track.file_name]),
+            None => tr("event.nothing_to_pick"),
+        };
+        if let Some(track) = &picked {
+            self.set_plan.push(Arc::clone(track));
+        }
+        self.picked_next = picked;
+    }
+
+    /// loads the file, that is named on the clipboard
+    fn load_from_clipboard(
+        &mut self,
+        player_messages_out: &Sender<player::Message>,
+        analyzer_event_out: &Sender<analyzer::Event>,
+    ) {
+        match clipboard::read()
+            .map_err(|err| i18n::tr_args("event.clipboard_failed", &[&err]))
+            .and_then(|text| {
+                clipboard::parse_track_path(&text)
+                    .map_err(|err| i18n::tr_args("event.cant_load", &[&err]))
+            }) {
+            Ok(path) => self.load_path(
+                path.to_string_lossy().into_owned(),
+                player_messages_out,
+                analyzer_event_out,
+            ),
+            Err(err) => self.latest_event = err,
+        }
+    }
+
+    /// assigns the focused track to a quick-load slot or loads the slot, once its digit was
+    /// pressed. Assignments are saved to the config file
+    fn handle_quick_load(
+        &mut self,
+        key: KeyCode,
+        player_messages_out: &Sender<player::Message>,
+        analyzer_event_out: &Sender<analyzer::Event>,
+    ) {
+        self.active_event_scope = EventScope::FileList;
+        let slot = match key {
+            KeyCode::Char(c @ '0'..='9') => c.to_digit(10).unwrap() as usize,
+            _ => {
+                self.latest_event = String::new();
+                return;
+            }
+        };
+        if self.quick_load_assign {
+            let track = match self.tracks.get_focused() {
+                Some(track) => track,
+                None => return,
+            };
+            self.config.quick_load[slot] = Some(PathBuf::from(&track.file_path));
+            self.latest_event =
+                match Config::save_setting(&format!("quick_load.{}", slot), &track.file_path) {
+                    Ok(()) => {
+                        i18n::tr_args("event.quick_load_assigned", &[&track.file_name, &slot])
+                    }
+                    Err(err) => i18n::tr_args("event.quick_load_not_saved", &[&slot, &err]),
+                };
+        } else {
+            match self.config.quick_load[slot].clone() {
+                Some(path) if path.is_file() => self.load_path(
+                    path.to_string_lossy().into_owned(),
+                    player_messages_out,
+                    analyzer_event_out,
+                ),
+                Some(path) => {
+                    self.latest_event = i18n::tr_args("event.not_a_file", &[&path.display()])
+                }
+                None => self.latest_event = i18n::tr_args("event.quick_load_empty", &[&slot]),
+            }
+        }
+    }
+
+    /// loads a file. Files outside of the library are listed first and loaded, once the
+    /// analyzer probed them
+    fn load_path(
+        &mut self,
+        path: String,
+        player_messages_out: &Sender<player::Message>,
+        analyzer_event_out: &Sender<analyzer::Event>,
+    ) {
+        if self.tracks.focus(&path).is_some() {
+            self.load_focused(player_messages_out);
+        } else {
+            self.scan.found += 1;
+            Analyzer::spawn(
+                path.clone(),
+                self.config.clone(),
+                self.bpm_windows.get(&path),
+                analyzer_event_out.clone(),
+            );
+            self.latest_event = i18n::tr_args("event.opening", &[&path]);
+            self.pending_load = Some(path);
+        }
+    }
+
+    /// jumps to the next or previous track of the loaded track's cue sheet
+    fn skip_cue_sheet_track(
+        &mut self,
+        forward: bool,
+        player_messages_out: &Sender<player::Message>,
+    ) {
+        let player_pos = (*self.player_position.lock().unwrap()).clone();
+        if let (Some(track), Some(pos)) = (self.tracks.get_loaded(), player_pos) {
+            if let Some(cue_sheet) = &track.cue_sheet {
+                let current = cue_sheet.track_at(pos.get_time_in_seconds());
+                let target = match (current, forward) {
+                    (None, true) => Some(0),
+                    (Some(i), true) => Some(i + 1),
+                    (Some(i), false) => i.checked_sub(1),
+                    (None, false) => None,
+                };
+                if let Some(cue_track) = target.and_then(|i| cue_sheet.tracks.get(i)) {
+                    let marker =
+                        TimeMarker::from_seconds(cue_track.start, track.codec_params.clone());
+                    player_messages_out.send(Message::JumpTo(marker)).unwrap();
+                    self.latest_event = format!(
+                        "{:02} {} - {}",
+                        cue_track.number, cue_track.performer, cue_track.title
+                    );
+                }
+            }
+        }
+    }
+
+    /// handles key events, while the bookmark picker is open
+    fn handle_bookmark_picker(
+        &mut self,
+        key: KeyCode,
+        player_messages_out: &Sender<player::Message>,
+    ) {
+        let track = match self.tracks.get_loaded() {
+            Some(track) => track,
+            None => {
+                self.active_event_scope = EventScope::FileList;
+                return;
+            }
+        };
+        let bookmarks = self.bookmarks.get(&track.file_path);
+        match key {
+            KeyCode::Char('j') if self.focused_bookmark + 1 < bookmarks.len() => {
+                self.focused_bookmark += 1;
+            }
+            KeyCode::Char('k') => {
+                self.focused_bookmark = self.focused_bookmark.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                if let Some(bookmark) = bookmarks.get(self.focused_bookmark) {
+                    let marker = TimeMarker::from_ts(bookmark.ts, track.codec_params.clone());
+                    player_messages_out.send(Message::JumpTo(marker)).unwrap();
+                    self.latest_event = i18n::tr_args("event.jumped_to", &[&bookmark.name]);
+                }
+                self.active_event_scope = EventScope::FileList;
+            }
+            KeyCode::Char('r') => {
+                if let Some(bookmark) = bookmarks.get(self.focused_bookmark) {
+                    let request = PromptRequest::RenameBookmark {
+                        ts: bookmark.ts,
+                        name: bookmark.name.clone(),
+                    };
+                    let prompt = Prompt::text(
+                        &tr("prompt.rename_bookmark.title"),
+                        &tr("prompt.rename_bookmark"),
+                        &bookmark.name,
+                        request,
+                    );
+                    self.open_prompt(prompt);
+                }
+            }
+            KeyCode::Esc => self.active_event_scope = EventScope::FileList,
+            _ => {}
+        }
+    }
+
+    /// zooms the live preview in or out
+    fn zoom(&mut self, zoom_in: bool) {
+        if zoom_in && self.zoom_level + 10 <= 500 {
+            self.zoom_level += 10;
+        } else if !zoom_in && self.zoom_level >= 60 {
+            self.zoom_level -= 10;
+        }
+    }
+
+    /// handles the mouse wheel. It scrolls the track table or the facet sidebar, or zooms the
+    /// live preview, depending on the pane below the cursor. While a popup is open, the panes
+    /// below it don't scroll
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let up = match mouse.kind {
+            MouseEventKind::ScrollUp => true,
+            MouseEventKind::ScrollDown => false,
+            _ => return,
+        };
+        if !matches!(
+            self.active_event_scope,
+            EventScope::FileList | EventScope::Facets | EventScope::Player
+        ) {
+            return;
+        }
+        let inside = |area: Rect| {
+            (area.left()..area.right()).contains(&mouse.column)
+                && (area.top()..area.bottom()).contains(&mouse.row)
+        };
+        if inside(self.live_preview_area) {
+            self.zoom(up);
+        } else if inside(self.facets_area) {
+            let num_entries = self.facets.entries().len();
+            self.focused_facet = if up {
+                self.focused_facet.saturating_sub(1)
+            } else {
+                (self.focused_facet + 1).min(num_entries.saturating_sub(1))
+            };
+        } else if inside(self.track_table_area) {
+            if up {
+                self.tracks.focus_previous();
+            } else {
+                self.tracks.focus_next();
+            }
+        }
+    }
+
+    /// asks the user a question. The answer is handled by `answer_prompt`
+    fn open_prompt(&mut self, prompt: Prompt<PromptRequest>) {
+        self.prompt = Some(prompt);
+        self.active_event_scope = EventScope::Prompt;
+    }
+
+    /// passes keys to the open prompt, until it is answered or cancelled
+    fn handle_prompt(&mut self, key: KeyCode, analyzer_event_out: &Sender<analyzer::Event>) {
+        let result = match self.prompt.as_mut() {
+            Some(prompt) => prompt.handle_key(key),
+            None => PromptResult::Cancelled,
+        };
+        if result == PromptResult::Pending {
+            return;
+        }
+        self.active_event_scope = EventScope::FileList;
+        if let Some(prompt) = self.prompt.take() {
+            self.answer_prompt(prompt.request, result, analyzer_event_out);
+        }
+    }
+
+    /// acts on the answer to a prompt and returns to the scope, that opened it
+    fn answer_prompt(
+        &mut self,
+        request: PromptRequest,
+        result: PromptResult,
+        analyzer_event_out: &Sender<analyzer::Event>,
+    ) {
+        match (request, result) {
+            (PromptRequest::DeleteDuplicate(track), result) => {
+                self.active_event_scope = EventScope::DuplicateReview;
+                if result == PromptResult::Confirmed {
+                    self.remove_duplicate(&track, true);
+                }
+            }
+            (PromptRequest::RenameBookmark { ts, name }, result) => {
+                self.active_event_scope = EventScope::BookmarkPicker;
+                let (track, new_name) = match (self.tracks.get_loaded(), result) {
+                    (Some(track), PromptResult::Text(new_name)) => (track, new_name),
+                    _ => return,
+                };
+                let res = self
+                    .bookmarks
+                    .remove(&track.file_path, ts, &name)
+                    .and_then(|_| self.bookmarks.add(&track.file_path, ts, &new_name));
+                self.latest_event = match res {
+                    Ok(()) => i18n::tr_args("event.bookmark_renamed", &[&name, &new_name]),
+                    Err(err) => i18n::tr_args("event.bookmarks_not_saved", &[&err]),
+                };
+            }
+            (PromptRequest::SetBpm(track), PromptResult::Number(bpm)) => {
+                self.set_bpm(&track, bpm.round() as u32)
+            }
+            (PromptRequest::SetBpmWindow(track), PromptResult::Text(text)) => {
+                let window = match text.as_str() {
+                    "auto" => None,
+                    text => match bpm_window::parse_window(text) {
+                        Ok(window) => Some(window),
+                        Err(_) => {
+                            self.latest_event = i18n::tr_args("event.invalid_bpm_window", &[&text]);
+                            return;
+                        }
+                    },
+                };
+                self.reanalyze_bpm_window(&track, window, analyzer_event_out);
+            }
+            (
+                PromptRequest::EditTags {
+                    tracks,
+                    field,
+                    mut changes,
+                },
+                PromptResult::Text(value),
+            ) => {
+                let tag_field = TagField::ALL[field];
+                if tag_field == TagField::Bpm
+                    && !value.is_empty()
+                    && !value.parse::<u32>().is_ok_and(|bpm| bpm > 0)
+                {
+                    // ask again
+                    self.latest_event = i18n::tr_args("event.invalid_bpm", &[&value]);
+                    self.prompt_tag(tracks, field, changes);
+                    return;
+                }
+                if value != shared_tag(&tracks, tag_field) {
+                    changes.push((tag_field, value));
+                }
+                self.prompt_tag(tracks, field + 1, changes);
+            }
+            (PromptRequest::WriteTags { tracks, changes }, PromptResult::Confirmed) => {
+                self.write_tags(&tracks, &changes)
+            }
+            (PromptRequest::WriteChapters, PromptResult::Text(path)) => {
+                let path = PathBuf::from(path);
+                self.latest_event = match self.write_chapters(&path) {
+                    Ok(0) => tr("event.no_plays_in_mix"),
+                    Ok(n) => i18n::tr_args("event.chapters_written", &[&n, &path.display()]),
+                    Err(err) => i18n::tr_args("event.chapters_not_written", &[&err]),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// embeds the tracks from the play log, that played while a mix was recorded, as chapters
+    /// into the recording. The recording is taken to have ended, when its file was last
+    /// modified, which is kept as it was. Returns the number of chapters
+    fn write_chapters(&self, path: &Path) -> io::Result<usize> {
+        let duration = chapters::duration(path)?;
+        let modified = fs::metadata(path)?.modified()?;
+        let end = modified
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let start = end.saturating_sub(duration.as_secs());
+        let tracks = self.tracks.values();
+        let chapters: Vec<Chapter> = self
+            .play_stats
+            .plays_between(start, end)?
+            .into_iter()
+            .map(|play| {
+                let track = tracks
+                    .iter()
+                    .find(|track| track.file_path == play.file_path);
+                let title = match track {
+                    Some(track) => {
+                        let meta = track.meta.read().unwrap();
+                        match (meta.artist.is_empty(), meta.title.is_empty()) {
+                            (false, false) => format!("{} - {}", meta.artist, meta.title),
+                            (true, false) => meta.title.clone(),
+                            _ => track.file_name.clone(),
+                        }
+                    }
+                    None => Path::new(&play.file_path)
+                        .file_name()
+                        .map_or(play.file_path.clone(), |name| {
+                            name.to_string_lossy().into_owned()
+                        }),
+                };
+                Chapter {
+                    start: Duration::from_secs(play.started - start),
+                    title,
+                }
+            })
+            .collect();
+        if !chapters.is_empty() {
+            chapters::write(path, &chapters)?;
+            fs::File::options()
+                .write(true)
+                .open(path)?
+                .set_modified(modified)?;
+        }
+        Ok(chapters.len())
+    }
+
+    /// asks for the tag at index `field` of `TagField::ALL` of the given tracks, or whether to
+    /// write the changes, once all tags were answered
+    fn prompt_tag(
+        &mut self,
+        tracks: Vec<Arc<Track>>,
+        field: usize,
+        changes: Vec<(TagField, String)>,
+    ) {
+        let tag_field = match TagField::ALL.get(field) {
+            Some(tag_field) => *tag_field,
+            None if changes.is_empty() => {
+                self.latest_event = tr("event.tags_unchanged");
+                return;
+            }
+            None => {
+                let names: Vec<String> =
+                    changes.iter().map(|(field, _)| tag_name(*field)).collect();
+                let mut message =
+                    i18n::tr_args("prompt.write_tags", &[&names.join(", "), &tracks.len()]);
+                if self.config.tag_backup {
+                    message = format!("{} {}", message, tr("prompt.write_tags_backup"));
+                }
+                self.open_prompt(Prompt::confirm(
+                    &tr("prompt.write_tags.title"),
+                    &message,
+                    PromptRequest::WriteTags { tracks, changes },
+                ));
+                return;
+            }
+        };
+        let title = i18n::tr_args(
+            "prompt.edit_tags.title",
+            &[&(field + 1), &TagField::ALL.len()],
+        );
+        let message = match &tracks[..] {
+            [track] => i18n::tr_args("prompt.edit_tag", &[&tag_name(tag_field), &track.file_name]),
+            _ => i18n::tr_args(
+                "prompt.edit_tag_batch",
+                &[&tag_name(tag_field), &tracks.len()],
+            ),
+        };
+        let value = shared_tag(&tracks, tag_field);
+        self.open_prompt(Prompt::optional_text(
+            &title,
+            &message,
+            &value,
+            PromptRequest::EditTags {
+                tracks,
+                field,
+                changes,
+            },
+        ));
+    }
+
+    /// writes changed tags to the files of the tracks and shows them in the library right away
+    fn write_tags(&mut self, tracks: &[Arc<Track>], changes: &[(TagField, String)]) {
+        let bpm = changes
+            .iter()
+            .find(|(field, _)| *field == TagField::Bpm)
+            .and_then(|(_, bpm)| bpm.parse::<u32>().ok());
+        let mut written = 0;
+        let mut failed = None;
+        for track in tracks {
+            // the analysis is cached for the file as it was, so it is stored again afterwards
+            let analysis = analysis_cache::load(&track.file_path, &self.config);
+            let path = Path::new(&track.file_path);
+            if let Err(err) = tags::write(path, changes, self.config.tag_backup) {
+                warn!("failed to write the tags of {}: {}", track.file_path, err);
+                failed = Some((track.file_name.clone(), err));
+                continue;
+            }
+            let mut meta = track.meta.write().unwrap();
+            for (field, value) in changes {
+                meta.set_tag(*field, value);
+            }
+            if let Some(mut analysis) = analysis {
+                analysis.bpm = bpm.unwrap_or(analysis.bpm);
+                if let Err(err) = analysis_cache::store(&track.file_path, &self.config, &analysis) {
+                    warn!(
+                        "failed to cache the analysis of {}: {}",
+                        track.file_path, err
+                    );
+                }
+            }
+            written += 1;
+        }
+        self.tracks.clear_marks();
+        self.filter_tracks();
+        self.latest_event = match failed {
+            None => i18n::tr_args("event.tags_written", &[&written]),
+            Some((file_name, err)) => {
+                i18n::tr_args("event.tags_not_written", &[&file_name, &err, &written])
+            }
+        };
+    }
+
+    /// stores the part of a track, that its BPM is detected from, and analyzes the track again.
+    /// `None` goes back to the configured window
+    fn reanalyze_bpm_window(
+        &mut self,
+        track: &Track,
+        window: Option<(f64, f64)>,
+        analyzer_event_out: &Sender<analyzer::Event>,
+    ) {
+        let res = self
+            .bpm_windows
+            .set(&track.file_path, window)
+            .and_then(|_| analysis_cache::remove(&track.file_path));
+        if let Err(err) = res {
+            self.latest_event = i18n::tr_args("event.bpm_window_not_saved", &[&err]);
+            return;
+        }
+        // the new analysis replaces the list entry
+        self.scan.found += 1;
+        Analyzer::spawn(
+            track.file_path.clone(),
+            self.config.clone(),
+            window,
+            analyzer_event_out.clone(),
+        );
+        self.latest_event = i18n::tr_args("event.reanalyzing", &[&track.file_name]);
+    }
+
+    /// loads the track of the last session and moves to its position, once the track got listed
+    fn restore_session(&mut self, track: &Track, player_messages_out: &Sender<player::Message>) {
+        let position = match &self.pending_session {
+            Some(session) if session.loaded_track.as_deref() == Some(track.file_path.as_str()) => {
+                session.position
+            }
+            _ => return,
+        };
+        self.pending_session = None;
+        if track.unsupported.is_some() || self.tracks.focus(&track.file_path).is_none() {
+            return;
+        }
+        self.load_focused(player_messages_out);
+        let marker = TimeMarker::from_seconds(position, track.codec_params.clone());
+        self.pending_restore = Some((track.file_path.clone(), marker));
+        self.latest_event = i18n::tr_args("event.session_restored", &[&track.file_name]);
+    }
+
+    /// asks, whether the journaled session of a crashed run should be restored
+    fn handle_restore_prompt(
+        &mut self,
+        key: KeyCode,
+        player_messages_out: &Sender<player::Message>,
+    ) {
+        match key {
+            KeyCode::Char('y') => {
+                self.pending_session = self.crashed_session.take();
+                let session = self.pending_session.clone().unwrap_or_default();
+                if let Some(zoom_level) = session.zoom_level {
+                    self.zoom_level = zoom_level;
+                }
+                // the track might already be listed
+                let listed = self
+                    .tracks
+                    .values()
+                    .iter()
+                    .find(|track| session.loaded_track.as_ref() == Some(&track.file_path))
+                    .cloned();
+                if let Some(track) = listed {
+                    self.restore_session(&track, player_messages_out);
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Esc => {
+                self.crashed_session = None;
+                self.latest_event = tr("event.session_discarded");
+            }
+            _ => return,
+        }
+        self.active_event_scope = EventScope::FileList;
+    }
+
+    /// handles the answer to the question, whether a removable drive should be scanned. The drive
+    /// is added as a library root until the app is closed
+    fn handle_mount_prompt(&mut self, key: KeyCode, analyzer_event_out: &Sender<analyzer::Event>) {
+        match (key, self.pending_mount.take()) {
+            (KeyCode::Char('y'), Some(path)) => {
+                let name = path
+                    .file_name()
+                    .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+                    .into_owned();
+                let root = LibraryRoot::new(&name, path.clone());
+                self.config.libraries.push(root.clone());
+                self.update_groups();
+                self.scan_root(&root, &path, analyzer_event_out);
+                self.latest_event = i18n::tr_args("event.scanning", &[&path.display()]);
+            }
+            (KeyCode::Char('n') | KeyCode::Esc, _) | (_, None) => {}
+            (_, pending) => {
+                self.pending_mount = pending;
+                return;
+            }
+        }
+        self.active_event_scope = EventScope::FileList;
+    }
+
+    /// scans a folder below a library root and analyzes the found tracks
+    fn scan_root(
+        &mut self,
+        root: &LibraryRoot,
+        dir: &Path,
+        analyzer_event_out: &Sender<analyzer::Event>,
+    ) {
+        let files = App::scan_dir(root, dir, &mut HashSet::new()).unwrap_or_else(|err| {
+            warn!("failed to scan {}: {}", dir.display(), err);
+            vec![]
+        });
+        self.scan.found += files.len();
+        for file in files {
+            let bpm_window = self.bpm_windows.get(&file);
+            Analyzer::spawn(
+                file,
+                self.config.clone(),
+                bpm_window,
+                analyzer_event_out.clone(),
+            );
+        }
+    }
+
+    /// generates the test signals and lists them like tracks
+    fn list_test_signals(&mut self, analyzer_event_out: &Sender<analyzer::Event>) {
+        let dir = match test_signal::signals_dir() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let signals = [
+            TestSignal::Sweep,
+            TestSignal::Clicks(self.config.test_signal_bpm),
+            TestSignal::PinkNoise,
+        ];
+        for signal in signals {
+            match signal.write(&dir) {
+                Ok(path) => {
+                    self.scan.found += 1;
+                    Analyzer::spawn(
+                        path.to_string_lossy().into_owned(),
+                        self.config.clone(),
+                        None,
+                        analyzer_event_out.clone(),
+                    );
+                }
+                Err(err) => warn!("failed to write {}: {}", signal.file_name(), err),
+            }
+        }
+    }
+
+    /// lists the tracks of each enabled library root in its own group, if there is more than one
+    fn update_groups(&mut self) {
+        let roots: Vec<_> = self
+            .config
+            .libraries
+            .iter()
+            .filter(|root| root.enabled)
+            .map(|root| (root.name.clone(), root.path.clone()))
+            .collect();
+        if roots.len() > 1 {
+            self.tracks.set_groups(roots);
+        }
+    }
+
+    /// marks the tracks below a folder as available or not. Returns the number of tracks
+    fn set_available(&self, path: &Path, available: bool) -> usize {
+        let mut count = 0;
+        for track in self.tracks.values() {
+            if Path::new(&track.file_path).starts_with(path) {
+                track.set_available(available);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// returns the state, that gets restored on the next start
+    fn current_session(&self) -> Session {
+        let pending = self
+            .pending_session
+            .as_ref()
+            .or(self.crashed_session.as_ref());
+        match (self.tracks.get_loaded(), pending) {
+            // keep the last session, if its track wasn't listed yet
+            (None, Some(pending)) => Session {
+                zoom_level: Some(self.zoom_level),
+                ..pending.clone()
+            },
+            (loaded, _) => Session {
+                loaded_track: loaded.map(|track| track.file_path.clone()),
+                position: (*self.player_position.lock().unwrap())
+                    .as_ref()
+                    .map(|pos| pos.get_time_in_seconds())
+                    .unwrap_or(0.),
+                zoom_level: Some(self.zoom_level),
+            },
+        }
+    }
+
+    /// persists the state on exit and removes the crash recovery journal
+    fn save_session(&self) {
+        if let Some(path) = Session::session_file() {
+            if let Err(err) = self.current_session().save(&path) {
+                warn!("failed to save session: {}", err);
+            }
+        }
+        if let Some(path) = Session::journal_file() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// writes the crash recovery journal
+    fn write_journal(&mut self) {
+        self.journal_written = Instant::now();
+        if let Some(path) = Session::journal_file() {
+            if let Err(err) = self.current_session().save(&path) {
+                warn!("failed to write journal: {}", err);
+            }
+        }
+    }
+
+    /// sorts the track list in the selected order
+    fn sort_tracks(&mut self) {
+        let play_stats = &self.play_stats;
+        match self.sort_order {
+            SortOrder::Path => self.tracks.sort_by_key(|track| track.file_path.clone()),
+            SortOrder::PlayCount => self.tracks.sort_by_key(|track| {
+                std::cmp::Reverse(play_stats.get(&track.file_path).map_or(0, |s| s.count))
+            }),
+            SortOrder::LastPlayed => self.tracks.sort_by_key(|track| {
+                play_stats
+                    .get(&track.file_path)
+                    .map_or(0, |s| s.last_played)
+            }),
+        }
+    }
+
+    /// hides the tracks, that don't pass the freshness filter, the selected facets or the search
+    fn filter_tracks(&mut self) {
+        let hidden = self
+            .tracks
+            .values()
+            .iter()
+            .filter(|track| !self.passes_filters(track))
+            .map(|track| track.file_path.clone())
+            .collect();
+        self.tracks.set_hidden(hidden);
+    }
+
+    /// returns true, if the track wasn't played within the last `fresh_days` while only fresh
+    /// tracks are listed, has one of the selected values of each facet and contains the search
+    /// text
+    fn passes_filters(&self, track: &Track) -> bool {
+        if self.only_fresh
+            && self
+                .play_stats
+                .played_within(&track.file_path, self.config.fresh_days)
+        {
+            return false;
+        }
+        let meta = track.meta.read().unwrap();
+        if !self.facets.matches(&meta) {
+            return false;
+        }
+        let search = self.search.to_lowercase();
+        [&track.file_name, &meta.title, &meta.artist, &meta.album]
+            .iter()
+            .any(|text| text.to_lowercase().contains(&search))
+    }
+
+    /// handles keys in the facet sidebar. Selecting entries narrows the track list down
+    fn handle_facets(&mut self, key: KeyCode) {
+        let num_entries = self.facets.entries().len();
+        match key {
+            KeyCode::Char('j') => {
+                self.focused_facet = (self.focused_facet + 1).min(num_entries.saturating_sub(1))
+            }
+            KeyCode::Char('k') => self.focused_facet = self.focused_facet.saturating_sub(1),
+            KeyCode::Char(' ') => {
+                self.facets.toggle(self.focused_facet);
+                self.filter_tracks();
+            }
+            // deselect all entries
+            KeyCode::Char('x') => {
+                self.facets.clear();
+                self.filter_tracks();
+            }
+            // focus the track list, while the sidebar keeps narrowing it down
+            KeyCode::Tab => self.active_event_scope = EventScope::FileList,
+            // close the sidebar, which lists all tracks again
+            KeyCode::Esc => {
+                self.facets.clear();
+                self.show_facets = false;
+                self.filter_tracks();
+                self.active_event_scope = EventScope::FileList;
+            }
+            _ => {}
+        }
+    }
+
+    /// handles keys, while the search text is typed. The track list is narrowed down as you type
+    fn handle_search(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char(c) => self.search.push(c),
+            KeyCode::Backspace => {
+                self.search.pop();
+            }
+            // keep the search and focus the track list
+            KeyCode::Enter => {
+                self.active_event_scope = EventScope::FileList;
+                return;
+            }
+            KeyCode::Esc => {
+                self.search.clear();
+                self.active_event_scope = EventScope::FileList;
+            }
+            _ => return,
+        }
+        self.filter_tracks();
+    }
+
+    /// doubles or halves the BPM of the focused track, when the detection picked the wrong
+    /// octave
+    fn scale_bpm(&mut self, double: bool) {
+        let track = match self.tracks.get_focused() {
+            Some(track) => track,
+            None => return,
+        };
+        let bpm = track.meta.read().unwrap().bpm;
+        let bpm = if double { bpm * 2 } else { bpm / 2 };
+        self.set_bpm(&track, bpm);
+    }
+
+    /// corrects the BPM of a track. The correction is kept in the analysis cache, so it survives
+    /// a restart
+    fn set_bpm(&mut self, track: &Arc<Track>, bpm: u32) {
+        let before = track.meta.read().unwrap().clone();
+        if bpm == 0 || bpm == before.bpm {
+            return;
+        }
+        track.change_bpm(bpm);
+        let after = track.meta.read().unwrap().clone();
+        self.history
+            .push(Edit::ChangeMeta(vec![(Arc::clone(track), before, after)]));
+        if let Some(mut analysis) = analysis_cache::load(&track.file_path, &self.config) {
+            analysis.bpm = bpm;
+            if let Err(err) = analysis_cache::store(&track.file_path, &self.config, &analysis) {
+                warn!("failed to cache the BPM of {}: {}", track.file_path, err);
+            }
+        }
+        self.latest_event = i18n::tr_args("event.bpm_set", &[&track.file_name, &bpm]);
+    }
+
+    /// reverts an edit, or applies it again
+    fn apply_edit(&mut self, edit: &Edit, undo: bool) {
+        match edit {
+            Edit::RemoveTrack(track) if undo => self.tracks.insert(Arc::clone(track)),
+            Edit::RemoveTrack(track) => self.tracks.remove(Path::new(&track.file_path)),
+            Edit::AddBookmark {
+                file_path,
+                ts,
+                name,
+            } => {
+                let res = if undo {
+                    self.bookmarks.remove(file_path, *ts, name)
+                } else {
+                    self.bookmarks.add(file_path, *ts, name)
+                };
+                if let Err(err) = res {
+                    warn!("failed to save bookmarks: {}", err);
+                }
+            }
+            Edit::AddMemCue(track, marker) if undo => track.remove_mem_cue(marker),
+            Edit::AddMemCue(track, marker) => track.add_mem_cue(marker.clone()),
+            Edit::ChangeMeta(changes) => {
+                for (track, before, after) in changes {
+                    let meta = if undo { before } else { after };
+                    *track.meta.write().unwrap() = meta.clone();
+                    if before.user_key != after.user_key {
+                        let key = Some(meta.user_key.as_str()).filter(|k| !k.is_empty());
+                        if let Err(err) = self.key_overrides.set(&track.file_path, key) {
+                            self.latest_event = i18n::tr_args("event.keys_not_saved", &[&err]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// handles keys in the duplicate review. The focused copy can be removed from the library or
+    /// deleted from disk
+    fn handle_duplicate_review(&mut self, key: KeyCode) {
+        let focused = self
+            .duplicates
+            .iter()
+            .flatten()
+            .nth(self.focused_duplicate)
+            .cloned();
+        match (key, focused) {
+            (KeyCode::Char('j'), _) => {
+                let count: usize = self.duplicates.iter().map(Vec::len).sum();
+                if self.focused_duplicate + 1 < count {
+                    self.focused_duplicate += 1;
+                }
+            }
+            (KeyCode::Char('k'), _) => {
+                self.focused_duplicate = self.focused_duplicate.saturating_sub(1);
+            }
+            (KeyCode::Char('x'), Some(track)) => self.remove_duplicate(&track, false),
+            // deleting can't be undone, so it has to be confirmed
+            (KeyCode::Char('d'), Some(track)) => {
+                let message = i18n::tr_args("prompt.delete", &[&track.file_path]);
+                self.open_prompt(Prompt::confirm(
+                    &tr("prompt.delete.title"),
+                    &message,
+                    PromptRequest::DeleteDuplicate(track),
+                ));
+            }
+            (KeyCode::Esc, _) => self.active_event_scope = EventScope::FileList,
+            _ => {}
+        }
+    }
+
+    /// removes a duplicate from the library and the review. If `delete` is set, its file is
+    /// deleted as well
+    fn remove_duplicate(&mut self, track: &Arc<Track>, delete: bool) {
+        if delete {
+            if let Err(err) = fs::remove_file(&track.file_path) {
+                self.latest_event = i18n::tr_args("event.delete_failed", &[&track.file_name, &err]);
+                return;
+            }
+            self.latest_event = i18n::tr_args("event.deleted", &[&track.file_path]);
+        } else {
+            self.latest_event = i18n::tr_args("event.removed_from_library", &[&track.file_path]);
+            self.history.push(Edit::RemoveTrack(Arc::clone(track)));
+        }
+        self.tracks.remove(Path::new(&track.file_path));
+        for group in self.duplicates.iter_mut() {
+            group.retain(|t| t != track);
+        }
+        self.duplicates.retain(|group| group.len() > 1);
+        let count: usize = self.duplicates.iter().map(Vec::len).sum();
+        self.focused_duplicate = self.focused_duplicate.min(count.saturating_sub(1));
+        if self.duplicates.is_empty() {
+            self.active_event_scope = EventScope::FileList;
+        }
+    }
+
+    /// returns the columns of the column picker: the shown ones in their order with their
+    /// settings, followed by the hidden ones
+    fn column_picker_entries(&self) -> Vec<(Column, Option<ColumnSetting>)> {
+        let shown = &self.config.library_columns;
+        shown
+            .iter()
+            .map(|setting| (setting.column, Some(*setting)))
+            .chain(
+                Column::ALL
+                    .into_iter()
+                    .filter(|column| shown.iter().all(|setting| setting.column != *column))
+                    .map(|column| (column, None)),
+            )
+            .collect()
+    }
+
+    /// handles keys in the key picker. Enter sets the focused key, the first entry goes back to
+    /// the tagged key
+    fn handle_key_picker(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('j') => {
+                self.focused_key = (self.focused_key + 1).min(key_overrides::KEYS.len())
+            }
+            KeyCode::Char('k') => self.focused_key = self.focused_key.saturating_sub(1),
+            KeyCode::Enter => {
+                self.active_event_scope = EventScope::FileList;
+                if let Some(track) = self.key_picker_track.take() {
+                    let key = match self.focused_key {
+                        0 => None,
+                        i => key_overrides::KEYS.get(i - 1).map(|(key, _)| *key),
+                    };
+                    self.set_key(&track, key);
+                }
+            }
+            KeyCode::Esc => {
+                self.active_event_scope = EventScope::FileList;
+                self.key_picker_track = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// sets the key, that a track is shown and mixed with, or goes back to its tagged key
+    fn set_key(&mut self, track: &Arc<Track>, key: Option<&str>) {
+        let before = track.meta.read().unwrap().clone();
+        if before.user_key == key.unwrap_or("") {
+            return;
+        }
+        if let Err(err) = self.key_overrides.set(&track.file_path, key) {
+            self.latest_event = i18n::tr_args("event.keys_not_saved", &[&err]);
+            return;
+        }
+        track.meta.write().unwrap().user_key = String::from(key.unwrap_or(""));
+        let after = track.meta.read().unwrap().clone();
+        self.history
+            .push(Edit::ChangeMeta(vec![(Arc::clone(track), before, after)]));
+        self.latest_event = match key {
+            Some(key) => i18n::tr_args("event.key_set", &[&track.file_name, &key]),
+            None => i18n::tr_args("event.key_reset", &[&track.file_name]),
+        };
+    }
+
+    /// handles keys in the column picker. Columns can be shown, hidden, moved and resized. The
+    /// columns are saved to the config file, once the picker is closed
+    fn handle_column_picker(&mut self, key: KeyCode) {
+        let entries = self.column_picker_entries();
+        let i = self.focused_column;
+        let columns = &mut self.config.library_columns;
+        match (key, entries[i]) {
+            (KeyCode::Char('j'), _) => self.focused_column = (i + 1).min(entries.len() - 1),
+            (KeyCode::Char('k'), _) => self.focused_column = i.saturating_sub(1),
+            // hide a shown column, but keep at least one
+            (KeyCode::Char(' '), (_, Some(_))) if columns.len() > 1 => {
+                columns.remove(i);
+            }
+            (KeyCode::Char(' '), (column, None)) => {
+                columns.push(ColumnSetting {
+                    column,
+                    width: None,
+                });
+                self.focused_column = columns.len() - 1;
+            }
+            // move a shown column right or left
+            (KeyCode::Char('J'), (_, Some(_))) if i + 1 < columns.len() => {
+                columns.swap(i, i + 1);
+                self.focused_column = i + 1;
+            }
+            (KeyCode::Char('K'), (_, Some(_))) if i > 0 => {
+                columns.swap(i, i - 1);
+                self.focused_column = i - 1;
+            }
+            // widen or narrow a shown column. Narrowing the narrowest width lets it share the
+            // space with the other columns without a width
+            (KeyCode::Char('+'), (_, Some(setting))) => {
+                columns[i].width = Some(setting.width.map_or(MIN_COLUMN_WIDTH, |w| w + 2))
+            }
+            (KeyCode::Char('-'), (_, Some(setting))) => {
+                columns[i].width = setting
+                    .width
+                    .filter(|w| *w > MIN_COLUMN_WIDTH)
+                    .map(|w| w - 2)
+            }
+            (KeyCode::Esc | KeyCode::Enter, _) => {
+                self.active_event_scope = EventScope::FileList;
+                let value: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+                if let Err(err) = Config::save_setting("library_columns", &value.join(", ")) {
+                    self.latest_event = i18n::tr_args("event.columns_not_saved", &[&err]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// handles keys in the set planner. The focused track can be moved, removed or loaded, and
+    /// the whole set exported as a playlist
+    fn handle_set_planner(&mut self, key: KeyCode, player_messages_out: &Sender<Message>) {
+        let num_tracks = self.set_plan.tracks().len();
+        match key {
+            KeyCode::Char('j') => {
+                self.focused_plan_entry =
+                    (self.focused_plan_entry + 1).min(num_tracks.saturating_sub(1))
+            }
+            KeyCode::Char('k') => {
+                self.focused_plan_entry = self.focused_plan_entry.saturating_sub(1)
+            }
+            // move the focused track down or up
+            KeyCode::Char(c @ ('J' | 'K')) => {
+                self.focused_plan_entry = self.set_plan.shift(self.focused_plan_entry, c == 'J')
+            }
+            KeyCode::Char('x') => {
+                if let Some(track) = self.set_plan.remove(self.focused_plan_entry) {
+                    self.latest_event = i18n::tr_args("event.unplanned", &[&track.file_name]);
+                }
+                if self.set_plan.tracks().is_empty() {
+                    self.active_event_scope = EventScope::FileList;
+                }
+                self.focused_plan_entry = self.focused_plan_entry.min(num_tracks.saturating_sub(2));
+            }
+            KeyCode::Char('e') => {
+                let path = &self.config.set_playlist;
+                self.latest_event = match self.set_plan.export_m3u(path) {
+                    Ok(n) => i18n::tr_args("event.exported", &[&n, &path.display()]),
+                    Err(err) => i18n::tr_args("event.export_failed", &[&err]),
+                };
+            }
+            KeyCode::Enter => {
+                let file_path = match self.set_plan.tracks().get(self.focused_plan_entry) {
+                    Some(track) => track.file_path.clone(),
+                    None => return,
+                };
+                if self.tracks.focus(&file_path).is_some() {
+                    self.load_focused(player_messages_out);
+                    self.active_event_scope = EventScope::FileList;
+                }
+            }
+            KeyCode::Esc => self.active_event_scope = EventScope::FileList,
+            _ => {}
+        }
+    }
+
+    /// define how the app should look like
+    pub(crate) fn render<B: Backend>(&mut self, f: &mut Frame<B>) {
+        let size = f.size();
+        if size.width < MIN_TERMINAL_SIZE.0 || size.height < MIN_TERMINAL_SIZE.1 {
+            self.live_preview_area = Rect::default();
+            self.facets_area = Rect::default();
+            self.track_table_area = Rect::default();
+            let hint = Paragraph::new(i18n::tr_args(
+                "window.too_small",
+                &[
+                    &size.width,
+                    &size.height,
+                    &MIN_TERMINAL_SIZE.0,
+                    &MIN_TERMINAL_SIZE.1,
+                ],
+            ))
+            .wrap(Wrap { trim: true })
+            .alignment(tui::layout::Alignment::Center);
+            f.render_widget(hint, size);
+            return;
+        }
+        // TODO: refactor
+        let window = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    // split for the live preview
+                    Constraint::Percentage(10),
+                    // split for the waveform overview
+                    Constraint::Percentage(5),
+                    // split for the main body
+                    Constraint::Percentage(83),
+                    // split for the footer
+                    Constraint::Percentage(2),
+                ]
                 .as_ref(),
             )
             .split(f.size());
+        let deck = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+            .split(window[0]);
+        self.live_preview_area = if self.config.accessible {
+            Rect::default()
+        } else {
+            deck[1]
+        };
         let player_position = (*self.player_position.lock().unwrap()).clone();
-        if let Some(track) = self.tracks.get_loaded() {
-            let live_preview = LivePreviewWidget::new(&track, &player_position, self.zoom_level);
-            let preview = PreviewWidget::new(&track, &player_position);
+        let player_position = self
+            .playhead
+            .update(&player_position, self.config.latency / 1000.);
+        if self.config.accessible {
+            // the deck and the overview share their space
+            let deck_area = Rect {
+                height: window[0].height + window[1].height,
+                ..window[0]
+            };
+            let lines = match self.tracks.get_loaded() {
+                Some(track) => self.accessible_status(&track, &player_position),
+                None => vec![tr("deck.no_track")],
+            };
+            f.render_widget(Paragraph::new(lines.join("\n")), deck_area);
+        } else if let Some(track) = self.tracks.get_loaded() {
+            let deck_header = self.deck_header(&track, &player_position);
+            // waveforms are only computed again, when their pane changed since the last frame
+            // the tint only changes with the track, so the cached bins stay valid
+            let color = self.waveform_color(&track);
+            let live_preview = LivePreviewWidget::new(&track, &player_position, self.zoom_level)
+                .downscale(self.waveform_downscale)
+                .color(color);
+            // the lines around the playhead are picked while drawing, so moving the playhead
+            // doesn't invalidate them
+            let live_preview_bins = self.live_preview_bins.get(
+                (
+                    track.file_path.clone(),
+                    self.zoom_level,
+                    self.waveform_downscale,
+                    track.preview_len(),
+                ),
+                || live_preview.bins(),
+            );
+            let mut live_preview = live_preview.cached_bins(&live_preview_bins);
+            if self.grid_check {
+                // the grid starts at the earliest memory cue, which usually marks the first beat
+                let anchor = track
+                    .mem_cues
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|cue| cue.get_time_in_seconds())
+                    .fold(None, |min: Option<f64>, t| {
+                        Some(min.map_or(t, |m| m.min(t)))
+                    })
+                    .unwrap_or(0.);
+                live_preview = live_preview.grid(anchor, track.meta.read().unwrap().bpm);
+            }
+            let preview = PreviewWidget::new(&track, &player_position)
+                .color(color)
+                .bookmarks(self.bookmarks.get(&track.file_path))
+                .repeat(self.repeat.0.as_ref(), self.repeat.1.as_ref());
+            let preview_bins = self.preview_bins.get(
+                (
+                    track.file_path.clone(),
+                    window[1].width,
+                    track.preview_len(),
+                ),
+                || preview.bins(window[1].width),
+            );
+            let preview = preview.cached_bins(&preview_bins);
 
             f.render_widget(preview, window[1]);
-            f.render_widget(live_preview, window[0]);
+            f.render_widget(deck_header, deck[0]);
+            f.render_widget(live_preview, deck[1]);
         }
 
-        let status_bar = Paragraph::new(self.latest_event.clone())
+        let mut status = self.latest_event.clone();
+        if let Some((_, deadline)) = self.sleep_timer {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let sleep = i18n::tr_args("status.sleep", &[&format_time(remaining.as_secs_f64())]);
+            status = format!("{}  [{}]", status, sleep);
+        }
+        if self.stop_after_track {
+            status = format!("{}  [{}]", status, tr("status.stop_after_track"));
+        }
+        if let Some(output_format) = &self.output_format {
+            let output = i18n::tr_args("status.output", &[output_format]);
+            status = format!("{}  [{}]", status, output);
+        }
+        if !self.scan.is_done() {
+            status = format!("{}  [{}]", status, self.scan);
+        }
+        if self.config.booth_device.is_some() {
+            let volume = format!("{:.0}", self.booth_volume * 100.);
+            status = format!(
+                "{}  [{}]",
+                status,
+                i18n::tr_args("status.booth", &[&volume])
+            );
+        }
+        if self.active_event_scope == EventScope::Search {
+            let search = format!("{}_", self.search);
+            status = format!(
+                "{}  [{}]",
+                status,
+                i18n::tr_args("status.search", &[&search])
+            );
+        } else if !self.search.is_empty() {
+            let search = i18n::tr_args("status.search", &[&self.search]);
+            status = format!("{}  [{}]", status, search);
+        }
+        let status_bar = Paragraph::new(status)
             .block(
                 Block::default()
                     // .title("Status")
@@ -291,39 +2775,589 @@ impl App {
             )
             .alignment(tui::layout::Alignment::Center);
         f.render_widget(status_bar, window[3]);
-        let track_table = TrackTableWidget::new(
-            &self.tracks,
-            self.active_event_scope == EventScope::FileList,
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(80), Constraint::Percentage(20)].as_ref())
+            .split(window[2]);
+        let library = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(if self.show_facets { 25 } else { 0 }),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(body[0]);
+        self.facets_area = if self.show_facets {
+            library[0]
+        } else {
+            Rect::default()
+        };
+        self.track_table_area = library[1];
+        if self.show_facets {
+            let items: Vec<ListItem> = self
+                .facets
+                .entries()
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let mark = if self.facets.is_selected(i) {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    };
+                    ListItem::new(format!(
+                        "{} {:<5} {} ({})",
+                        mark,
+                        entry.kind.title(),
+                        entry.value,
+                        entry.count
+                    ))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .title(tr("pane.facets"))
+                        .borders(Borders::ALL),
+                )
+                .highlight_style(Style::default().fg(Color::Black).bg(Color::DarkGray));
+            let mut state = ListState::default();
+            if self.active_event_scope == EventScope::Facets {
+                state.select(Some(self.focused_facet));
+            }
+            f.render_stateful_widget(list, library[0], &mut state);
+        }
+        if self.active_event_scope == EventScope::SetPlanner {
+            f.render_widget(self.set_planner(), library[1]);
+        } else {
+            let track_table = TrackTableWidget::new(
+                &self.tracks,
+                self.active_event_scope == EventScope::FileList,
+                &self.config.library_columns,
+            )
+            .play_stats(&self.play_stats);
+            f.render_widget(track_table, library[1]);
+        }
+        let side_panel = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(body[1]);
+        let sampler_slots = self.sampler.slots();
+        let sampler = SamplerWidget::new(&sampler_slots, self.selected_sampler_slot);
+        f.render_widget(sampler, side_panel[0]);
+        let loudness = LoudnessWidget::new(
+            self.loudness.0,
+            self.loudness.1,
+            self.config.loudness_target,
         );
-        f.render_widget(track_table, window[2]);
-        // let block = Block::default().title("popup").borders(Borders::ALL);
-        // let popup = PopupWidget::new(block, 10, 90);
-        // f.render_widget(popup, f.size());
+        f.render_widget(loudness, side_panel[1]);
+        if self.active_event_scope == EventScope::BookmarkPicker {
+            if let Some(track) = self.tracks.get_loaded() {
+                let items: Vec<ListItem> = self
+                    .bookmarks
+                    .get(&track.file_path)
+                    .iter()
+                    .map(|bookmark| {
+                        let marker = TimeMarker::from_ts(bookmark.ts, track.codec_params.clone());
+                        let time = format_time(marker.get_time_in_seconds());
+                        ListItem::new(format!("{}  {}", time, bookmark.name))
+                    })
+                    .collect();
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .title(tr("pane.bookmarks"))
+                            .borders(Borders::ALL),
+                    )
+                    .highlight_style(Style::default().fg(Color::Black).bg(Color::DarkGray));
+                let mut state = ListState::default();
+                state.select(Some(self.focused_bookmark));
+                f.render_stateful_widget(PopupWidget::new(list, 50, 50), f.size(), &mut state);
+            }
+        }
+        if self.active_event_scope == EventScope::Help {
+            let legend = [
+                (Color::Red, "marker.playhead"),
+                (Color::Green, "marker.mem_cue"),
+                (Color::Yellow, "marker.bookmark"),
+                (Color::Cyan, "marker.repeat"),
+                (Color::Blue, "marker.cue_track"),
+            ];
+            let lines: Vec<Spans> = legend
+                .iter()
+                .map(|(color, name)| {
+                    Spans::from(vec![
+                        Span::styled("| ", Style::default().fg(*color)),
+                        Span::raw(tr(name)),
+                    ])
+                })
+                .collect();
+            let help = Paragraph::new(lines).block(
+                Block::default()
+                    .title(tr("pane.markers"))
+                    .borders(Borders::ALL),
+            );
+            f.render_widget(PopupWidget::new(help, 30, 30), f.size());
+        }
+        if self.show_stats {
+            let preview_bytes: usize = self
+                .tracks
+                .values()
+                .iter()
+                .map(|track| track.preview_len() * std::mem::size_of::<PreviewSample>())
+                .sum();
+            let lines = vec![
+                Spans::from(i18n::tr_args(
+                    "stats.frame_time",
+                    &[&format!("{:.1}", self.frame_time.as_secs_f64() * 1000.)],
+                )),
+                Spans::from(i18n::tr_args("stats.detail", &[&self.waveform_downscale])),
+                Spans::from(i18n::tr_args(
+                    "stats.audio_load",
+                    &[&format!("{:.1}", self.player_stats.0 * 100.)],
+                )),
+                Spans::from(i18n::tr_args(
+                    "stats.decoding",
+                    &[&format!("{:.2}", self.player_stats.1)],
+                )),
+                Spans::from(i18n::tr_args(
+                    "stats.analyzing",
+                    &[&self
+                        .scan
+                        .found
+                        .saturating_sub(self.scan.analyzed + self.scan.failed)],
+                )),
+                Spans::from(i18n::tr_args(
+                    "stats.previews",
+                    &[&format!("{:.1}", preview_bytes as f64 / (1024. * 1024.))],
+                )),
+            ];
+            let stats = Paragraph::new(lines).block(
+                Block::default()
+                    .title(tr("pane.stats"))
+                    .borders(Borders::ALL),
+            );
+            f.render_widget(PopupWidget::new(stats, 30, 30), f.size());
+        }
+        if self.active_event_scope == EventScope::RestorePrompt {
+            let prompt =
+                Paragraph::new(format!("{} {}", tr("prompt.restore"), tr("prompt.yes_no"))).block(
+                    Block::default()
+                        .title(tr("prompt.restore.title"))
+                        .borders(Borders::ALL),
+                );
+            f.render_widget(PopupWidget::new(prompt, 40, 20), f.size());
+        }
+        if self.active_event_scope == EventScope::MountPrompt {
+            if let Some(path) = &self.pending_mount {
+                let prompt = Paragraph::new(format!(
+                    "{} {}",
+                    i18n::tr_args("prompt.mount", &[&path.display()]),
+                    tr("prompt.yes_no")
+                ))
+                .block(
+                    Block::default()
+                        .title(tr("prompt.mount.title"))
+                        .borders(Borders::ALL),
+                );
+                f.render_widget(PopupWidget::new(prompt, 40, 20), f.size());
+            }
+        }
+        if self.active_event_scope == EventScope::ColumnPicker {
+            let items: Vec<ListItem> = self
+                .column_picker_entries()
+                .into_iter()
+                .map(|(column, setting)| {
+                    let state = match setting.map(|s| s.width) {
+                        Some(Some(width)) => {
+                            format!("[x] {} ({})", track_table::column_title(column), width)
+                        }
+                        Some(None) => format!("[x] {}", track_table::column_title(column)),
+                        None => format!("[ ] {}", track_table::column_title(column)),
+                    };
+                    ListItem::new(state)
+                })
+                .collect();
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .title(tr("pane.columns"))
+                        .borders(Borders::ALL),
+                )
+                .highlight_style(Style::default().fg(Color::Black).bg(Color::DarkGray));
+            let mut state = ListState::default();
+            state.select(Some(self.focused_column));
+            f.render_stateful_widget(PopupWidget::new(list, 50, 50), f.size(), &mut state);
+        }
+        if let (EventScope::KeyPicker, Some(track)) =
+            (&self.active_event_scope, &self.key_picker_track)
+        {
+            let tagged = track.meta.read().unwrap().key.clone();
+            let tagged = if tagged.is_empty() {
+                tr("key_picker.untagged")
+            } else {
+                i18n::tr_args("key_picker.tagged", &[&tagged])
+            };
+            let items: Vec<ListItem> = std::iter::once(ListItem::new(tagged))
+                .chain(
+                    key_overrides::KEYS
+                        .iter()
+                        .map(|(key, name)| ListItem::new(format!("{:>3}  {}", key, name))),
+                )
+                .collect();
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .title(i18n::tr_args("pane.key_picker", &[&track.file_name]))
+                        .borders(Borders::ALL),
+                )
+                .highlight_style(Style::default().fg(Color::Black).bg(Color::DarkGray));
+            let mut state = ListState::default();
+            state.select(Some(self.focused_key));
+            f.render_stateful_widget(PopupWidget::new(list, 40, 60), f.size(), &mut state);
+        }
+        if let (EventScope::Prompt, Some(prompt)) = (&self.active_event_scope, &self.prompt) {
+            f.render_widget(PromptWidget::new(prompt), f.size());
+        }
+        if self.active_event_scope == EventScope::DuplicateReview {
+            let items: Vec<ListItem> = self
+                .duplicates
+                .iter()
+                .enumerate()
+                .flat_map(|(i, group)| {
+                    group.iter().map(move |track| {
+                        ListItem::new(format!("{:>3}  {}", i + 1, track.file_path))
+                    })
+                })
+                .collect();
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .title(tr("pane.duplicates"))
+                        .borders(Borders::ALL),
+                )
+                .highlight_style(Style::default().fg(Color::Black).bg(Color::DarkGray));
+            let mut state = ListState::default();
+            state.select(Some(self.focused_duplicate));
+            f.render_stateful_widget(PopupWidget::new(list, 80, 60), f.size(), &mut state);
+        }
+    }
+
+    /// returns the set planner, which lists the planned tracks with their start time within the
+    /// set and flags risky transitions
+    fn set_planner(&self) -> Table<'_> {
+        let start_times = self.set_plan.start_times();
+        let rows: Vec<Row> = self
+            .set_plan
+            .tracks()
+            .iter()
+            .enumerate()
+            .map(|(i, track)| {
+                let meta = track.meta.read().unwrap();
+                let transition = self.set_plan.transition(i);
+                let mut notes = vec![];
+                if let Some(change) = transition.as_ref().and_then(|t| t.tempo_change) {
+                    notes.push(i18n::tr_args(
+                        "set_plan.tempo_change",
+                        &[&format!("{:+.1}", change)],
+                    ));
+                }
+                match transition.as_ref().and_then(|t| t.keys_match) {
+                    Some(true) => notes.push(tr("set_plan.keys_match")),
+                    Some(false) => notes.push(tr("set_plan.key_clash")),
+                    None => {}
+                }
+                let style = if i == self.focused_plan_entry {
+                    Style::default().fg(Color::Black).bg(Color::DarkGray)
+                } else if transition.is_some_and(|t| t.is_risky()) {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default()
+                };
+                Row::new(vec![
+                    Cell::from(format!("{}", i + 1)),
+                    Cell::from(format_time(start_times[i])),
+                    Cell::from(track.file_name.clone()),
+                    Cell::from(format!("{}", meta.bpm)),
+                    Cell::from(meta.effective_key().to_string()),
+                    Cell::from(notes.join(", ")),
+                ])
+                .style(style)
+            })
+            .collect();
+        let total = format_time(start_times.last().copied().unwrap_or(0.));
+        Table::new(rows)
+            .header(
+                Row::new(
+                    [
+                        "set_plan.number",
+                        "set_plan.start",
+                        "set_plan.track",
+                        "set_plan.bpm",
+                        "set_plan.key",
+                        "set_plan.transition",
+                    ]
+                    .map(tr),
+                )
+                .style(Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
+                .bottom_margin(1),
+            )
+            .block(
+                Block::default()
+                    .title(i18n::tr_args("pane.set_plan", &[&total]))
+                    .borders(Borders::ALL),
+            )
+            .widths(&[
+                Constraint::Length(4),
+                Constraint::Length(8),
+                Constraint::Percentage(50),
+                Constraint::Length(5),
+                Constraint::Length(6),
+                Constraint::Percentage(30),
+            ])
+    }
+
+    /// returns the color of the waveforms of a track, which is tinted by its spectral balance,
+    /// if `waveform_tint` is set
+    fn waveform_color(&self, track: &Track) -> Color {
+        let balance = track
+            .meta
+            .read()
+            .unwrap()
+            .extra
+            .get(analyzer::BALANCE_KEY)
+            .and_then(|balance| balance.parse().ok());
+        match balance {
+            Some(balance) if self.config.waveform_tint => preview::balance_color(balance),
+            _ => Color::Gray,
+        }
+    }
+
+    /// returns the header line of the deck with the track name and the time display. The header
+    /// flashes red, while the end-of-track warning is active.
+    fn deck_header(
+        &self,
+        track: &Track,
+        player_position: &Option<TimeMarker>,
+    ) -> Paragraph<'static> {
+        if let Some(loading) = self.loading {
+            let frame = SPINNER[loading.elapsed().as_millis() as usize / 100 % SPINNER.len()];
+            return Paragraph::new(i18n::tr_args(
+                "deck.header_loading",
+                &[&track.file_name, &frame],
+            ));
+        }
+        if let Some(buffering) = self.buffering {
+            let frame = SPINNER[buffering.elapsed().as_millis() as usize / 100 % SPINNER.len()];
+            return Paragraph::new(i18n::tr_args(
+                "deck.header_buffering",
+                &[&track.file_name, &frame],
+            ))
+            .style(Style::default().fg(Color::Yellow));
+        }
+        let mut style = Style::default();
+        let mut time = match player_position {
+            // tracks of unknown length only show the elapsed time and never flash
+            Some(pos) => match pos.get_remaining_seconds() {
+                Some(remaining) => {
+                    if let Some(warning) = self.end_of_track_warning {
+                        // toggle the highlight every 500ms
+                        let flash = warning.elapsed().as_millis() / 500 % 2 == 0;
+                        if flash && remaining <= self.config.end_of_track_warning {
+                            style = style.fg(Color::White).bg(Color::Red);
+                        }
+                    }
+                    format!(
+                        "{} / -{}",
+                        format_time(pos.get_time_in_seconds()),
+                        format_time(remaining)
+                    )
+                }
+                None => format_time(pos.get_time_in_seconds()),
+            },
+            None => String::new(),
+        };
+        // name the current track of a mix
+        if let (Some(cue_sheet), Some(pos)) = (&track.cue_sheet, player_position) {
+            if let Some(cue_track) = cue_sheet
+                .track_at(pos.get_time_in_seconds())
+                .map(|i| &cue_sheet.tracks[i])
+            {
+                time = format!(
+                    "{}  {:02} {} - {}",
+                    time, cue_track.number, cue_track.performer, cue_track.title
+                );
+            }
+        }
+        Paragraph::new(format!("{}  {}", track.file_name, time)).style(style)
+    }
+
+    /// returns lines about the loaded track, its position, BPM and the next marker, that are
+    /// shown instead of the waveforms in accessible mode. Each line starts with a label, so
+    /// screen readers announce what it is about
+    fn accessible_status(
+        &self,
+        track: &Track,
+        player_position: &Option<TimeMarker>,
+    ) -> Vec<String> {
+        let meta = track.meta.read().unwrap().clone();
+        let name = match (meta.artist.is_empty(), meta.title.is_empty()) {
+            (false, false) => format!("{} - {}", meta.artist, meta.title),
+            (true, false) => meta.title.clone(),
+            _ => track.file_name.clone(),
+        };
+        let mut lines = vec![i18n::tr_args("deck.track", &[&name])];
+        let pos = match player_position {
+            _ if self.loading.is_some() => {
+                lines.push(tr("deck.loading"));
+                return lines;
+            }
+            Some(pos) => pos,
+            None => return lines,
+        };
+        let now = pos.get_time_in_seconds();
+        let mut position = match (pos.get_duration_in_seconds(), pos.get_remaining_seconds()) {
+            (Some(duration), Some(remaining)) => i18n::tr_args(
+                "deck.position",
+                &[
+                    &format_time(now),
+                    &format_time(duration),
+                    &format_time(remaining),
+                ],
+            ),
+            _ => i18n::tr_args("deck.position_unknown_length", &[&format_time(now)]),
+        };
+        if self.buffering.is_some() {
+            position = format!("{}, {}", position, tr("deck.buffering"));
+        }
+        lines.push(position);
+        let mut tempo = match meta.bpm {
+            0 => tr("deck.bpm_unknown"),
+            bpm => i18n::tr_args("deck.bpm", &[&bpm]),
+        };
+        if !meta.effective_key().is_empty() {
+            tempo = format!(
+                "{}, {}",
+                tempo,
+                i18n::tr_args("deck.key", &[&meta.effective_key()])
+            );
+        }
+        lines.push(tempo);
+        // the closest marker ahead of the playhead
+        let mut markers: Vec<(f64, String)> = track
+            .mem_cues
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|cue| (cue.get_time_in_seconds(), tr("marker.mem_cue")))
+            .collect();
+        for bookmark in self.bookmarks.get(&track.file_path) {
+            let marker = TimeMarker::from_ts(bookmark.ts, track.codec_params.clone());
+            markers.push((
+                marker.get_time_in_seconds(),
+                i18n::tr_args("marker.named_bookmark", &[&bookmark.name]),
+            ));
+        }
+        if let Some(cue_sheet) = &track.cue_sheet {
+            for cue_track in cue_sheet.tracks.iter() {
+                markers.push((
+                    cue_track.start,
+                    i18n::tr_args(
+                        "marker.cue_sheet_track",
+                        &[
+                            &format!("{:02}", cue_track.number),
+                            &cue_track.performer,
+                            &cue_track.title,
+                        ],
+                    ),
+                ));
+            }
+        }
+        if let Some(b) = &self.repeat.1 {
+            markers.push((b.get_time_in_seconds(), tr("marker.repeat_end")));
+        }
+        let next = markers
+            .into_iter()
+            .filter(|(time, _)| *time > now)
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        lines.push(match next {
+            Some((time, name)) => i18n::tr_args("deck.next", &[&name, &format_time(time - now)]),
+            None => match pos.get_remaining_seconds() {
+                Some(remaining) => i18n::tr_args("deck.next_end", &[&format_time(remaining)]),
+                None => tr("deck.next_none"),
+            },
+        });
+        lines
     }
 
-    /// scans a directory for tracks
-    /// Supported file types are .mp3 .flac .wav
-    fn scan_dir(&mut self, dir: &Path) -> io::Result<Vec<String>> {
+    /// scans a directory below a library root for tracks, that pass the root's patterns
+    /// Supported file types are .mp3 .flac .wav. Directories in `visited` are skipped, so
+    /// symlinks pointing back up the tree don't recurse forever. Entries, that can't be read or
+    /// whose path isn't valid UTF-8, are skipped, only an unreadable `dir` fails the scan
+    fn scan_dir(
+        root: &LibraryRoot,
+        dir: &Path,
+        visited: &mut HashSet<PathBuf>,
+    ) -> io::Result<Vec<String>> {
         let mut res = vec![];
         if dir.is_dir() {
+            if !visited.insert(fs::canonicalize(dir)?) {
+                return Ok(res);
+            }
             for entry in fs::read_dir(dir)? {
-                let entry = entry?;
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        warn!("failed to read an entry of {}: {}", dir.display(), err);
+                        continue;
+                    }
+                };
                 let path = entry.path();
+                if !root.follow_symlinks {
+                    match entry.file_type() {
+                        Ok(file_type) if !file_type.is_symlink() => {}
+                        _ => continue,
+                    }
+                }
                 if path.is_dir() {
-                    let mut sub_dirs = self.scan_dir(&path)?;
-                    res.append(&mut sub_dirs);
-                } else {
+                    match App::scan_dir(root, &path, visited) {
+                        Ok(mut sub_dirs) => res.append(&mut sub_dirs),
+                        Err(err) => warn!("failed to scan {}: {}", path.display(), err),
+                    }
+                } else if probe::has_supported_extension(&path) && root.scans(&path) {
                     //TODO: use path object for hashmap
-                    let extension = path.extension().unwrap().to_str().unwrap();
-                    let supported_extensions = ["mp3", "wav", "flac"];
-                    if supported_extensions.contains(&extension) {
-                        let file_path = entry.path().into_os_string().into_string().unwrap();
-                        // let file_name = entry.file_name().into_string().unwrap();
-                        res.push(file_path);
-                    };
+                    match path.into_os_string().into_string() {
+                        Ok(file_path) => res.push(file_path),
+                        Err(path) => warn!("skipped {:?}, its path is not UTF-8", path),
+                    }
                 }
             }
         };
         Ok(res)
     }
 }
+
+/// formats a number of seconds as m:ss
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// returns the translated name of a tag
+fn tag_name(field: TagField) -> String {
+    tr(&format!("tag.{}", field.name()))
+}
+
+/// returns the value of a tag, that all given tracks share, or an empty string, if it differs
+fn shared_tag(tracks: &[Arc<Track>], field: TagField) -> String {
+    let mut values = tracks
+        .iter()
+        .map(|track| track.meta.read().unwrap().tag(field));
+    let first = values.next().unwrap_or_default();
+    match values.all(|value| value == first) {
+        true => first,
+        false => String::new(),
+    }
+}
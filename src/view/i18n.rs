@@ -0,0 +1,375 @@
+use std::{fmt::Display, fs, io, path::PathBuf, sync::RwLock};
+
+use crate::core::config::Config;
+
+//------------------------------------------------------------------//
+//                               I18n                               //
+//------------------------------------------------------------------//
+
+/// Built-in English texts of the interface, keyed by id. `{}` is replaced by the arguments of
+/// `tr_args` in order
+const ENGLISH: &[(&str, &str)] = &[
+    (
+        "window.too_small",
+        "Terminal too small ({}x{}), flow needs at least {}x{}",
+    ),
+    // deck
+    ("deck.no_track", "No track loaded"),
+    ("deck.track", "Track: {}"),
+    ("deck.loading", "Position: loading"),
+    ("deck.position", "Position: {} of {}, {} remaining"),
+    (
+        "deck.position_unknown_length",
+        "Position: {}, length unknown",
+    ),
+    ("deck.buffering", "buffering"),
+    ("deck.bpm", "BPM: {}"),
+    ("deck.bpm_unknown", "BPM: unknown"),
+    ("deck.key", "key: {}"),
+    ("deck.next", "Next: {} in {}"),
+    ("deck.next_end", "Next: end of track in {}"),
+    ("deck.next_none", "Next: no marker ahead"),
+    ("deck.header_loading", "{}  loading {}"),
+    ("deck.header_buffering", "{}  buffering {}"),
+    // markers
+    ("marker.playhead", "playhead"),
+    ("marker.mem_cue", "memory cue"),
+    ("marker.bookmark", "bookmark"),
+    ("marker.named_bookmark", "bookmark {}"),
+    ("marker.default_bookmark", "Bookmark {}"),
+    ("marker.repeat", "A-B repeat"),
+    ("marker.repeat_end", "repeat end"),
+    ("marker.cue_track", "cue sheet track"),
+    ("marker.cue_sheet_track", "track {} {} - {}"),
+    // panes and popups
+    ("pane.files", "Files"),
+    ("pane.sampler", "Sampler"),
+    ("pane.loudness", "Loudness"),
+    ("pane.facets", "Facets"),
+    ("pane.bookmarks", "Bookmarks"),
+    ("pane.markers", "Overview Markers"),
+    ("pane.stats", "Stats"),
+    (
+        "pane.columns",
+        "Columns (space: show/hide, J/K: move, +/-: width)",
+    ),
+    ("pane.key_picker", "Key of {} (Enter: set, Esc: cancel)"),
+    (
+        "pane.duplicates",
+        "Duplicates (x: remove from library, d: delete file)",
+    ),
+    (
+        "pane.set_plan",
+        "Set Plan, {} (J/K: move, x: remove, e: export, Enter: load)",
+    ),
+    // prompts
+    ("prompt.yes_no", "(y/n)"),
+    ("prompt.restore.title", "Restore Session"),
+    (
+        "prompt.restore",
+        "The last session ended unexpectedly.\nRestore it?",
+    ),
+    ("prompt.mount.title", "Removable Drive"),
+    (
+        "prompt.mount",
+        "{} was plugged in.\nScan it until flow is closed?",
+    ),
+    ("prompt.delete.title", "Delete File"),
+    ("prompt.delete", "Delete {} from disk?"),
+    ("prompt.rename_bookmark.title", "Rename Bookmark"),
+    ("prompt.rename_bookmark", "New name:"),
+    ("prompt.bpm.title", "BPM"),
+    ("prompt.bpm", "BPM of {}"),
+    ("prompt.bpm_window.title", "BPM Window"),
+    (
+        "prompt.bpm_window",
+        "Seconds of {} to detect the BPM from, like 60-180.\nauto uses the configured window.",
+    ),
+    ("prompt.edit_tags.title", "Edit Tags ({}/{})"),
+    ("prompt.edit_tag", "{} of {}:"),
+    (
+        "prompt.edit_tag_batch",
+        "{} of {} tracks. Values, that differ, are shown empty and kept, unless one is typed:",
+    ),
+    ("prompt.write_tags.title", "Write Tags"),
+    ("prompt.write_tags", "Write {} to {} files?"),
+    (
+        "prompt.write_tags_backup",
+        "A copy of each file is kept as .bak.",
+    ),
+    ("prompt.chapters.title", "Write Chapters"),
+    (
+        "prompt.chapters",
+        "Path of the recorded mix (MP3 or M4A). The tracks, that played while it was recorded, become its chapters:",
+    ),
+    ("tag.artist", "Artist"),
+    ("tag.title", "Title"),
+    ("tag.album", "Album"),
+    ("tag.bpm", "BPM"),
+    ("tag.key", "Key"),
+    ("tag.comment", "Comment"),
+    // track table
+    ("column.file_name", "File Name"),
+    ("column.title", "Title"),
+    ("column.artist", "Artist"),
+    ("column.album", "Album"),
+    ("column.analysis", "Analysis"),
+    ("column.bpm", "BPM"),
+    ("column.key", "Key"),
+    ("column.format", "Format"),
+    ("column.plays", "Plays"),
+    ("column.last_played", "Last Played"),
+    ("track.unsupported", "Unsupported"),
+    ("track.unavailable", "Unavailable"),
+    ("track.never", "never"),
+    ("track.today", "today"),
+    ("track.days_ago", "{}d ago"),
+    // key picker
+    ("key_picker.tagged", "tagged key ({})"),
+    ("key_picker.untagged", "tagged key (none)"),
+    // facets
+    ("facet.genre", "Genre"),
+    ("facet.label", "Label"),
+    ("facet.year", "Year"),
+    // set planner
+    ("set_plan.number", "#"),
+    ("set_plan.start", "Start"),
+    ("set_plan.track", "Track"),
+    ("set_plan.bpm", "BPM"),
+    ("set_plan.key", "Key"),
+    ("set_plan.transition", "Transition"),
+    ("set_plan.keys_match", "keys match"),
+    ("set_plan.key_clash", "key clash"),
+    ("set_plan.tempo_change", "{}% tempo"),
+    // status line
+    ("status.sleep", "sleep in {}"),
+    ("status.stop_after_track", "stop after track"),
+    ("status.output", "out: {}"),
+    ("status.booth", "booth {}%"),
+    ("status.search", "search: {}"),
+    (
+        "scan.progress",
+        "scanning {}/{}: {} probed, {} analyzed, {} failed",
+    ),
+    // messages in the status line
+    (
+        "event.grid_shown",
+        "Showing a beat grid at the BPM of the loaded track",
+    ),
+    ("event.grid_hidden", "Hiding the beat grid"),
+    (
+        "event.invalid_bpm_window",
+        "`{}` is not a window like 60-180",
+    ),
+    ("event.toggle_play", "TogglePlay"),
+    ("event.sleep_timer", "Sleep timer: {} min"),
+    ("event.sleep_timer_off", "Sleep timer off"),
+    ("event.sleep_timer_expired", "Sleep timer stopped playback"),
+    ("event.stop_after_track", "Stopping after the current track"),
+    (
+        "event.stop_after_track_off",
+        "Playing on after the current track",
+    ),
+    ("event.repeat_a", "Repeat: A set"),
+    ("event.repeat_ab", "Repeat: A-B"),
+    ("event.repeat_b_before_a", "Repeat: B has to be after A"),
+    ("event.repeat_off", "Repeat: off"),
+    ("event.repeat_leaving", "Repeat: leaving at B"),
+    ("event.repeat_rolled_out", "Repeat: rolled out"),
+    ("event.bookmark_added", "Added {}"),
+    ("event.bookmarks_not_saved", "Failed to save bookmarks: {}"),
+    ("event.jumped_to", "Jumped to {}"),
+    ("event.bookmark_renamed", "Renamed {} to {}"),
+    ("event.no_bookmarks", "No bookmarks"),
+    ("event.quick_load", "Load quick-load slot 0-9"),
+    ("event.quick_load_assign", "Assign to quick-load slot 0-9"),
+    (
+        "event.quick_load_assigned",
+        "Assigned {} to quick-load slot {}",
+    ),
+    (
+        "event.quick_load_not_saved",
+        "Failed to save quick-load slot {}: {}",
+    ),
+    ("event.quick_load_empty", "Quick-load slot {} is empty"),
+    ("event.planned", "Planned {}"),
+    (
+        "event.nothing_planned",
+        "No planned tracks, add some with a",
+    ),
+    ("event.unplanned", "Removed {} from the set"),
+    (
+        "event.only_fresh",
+        "Hiding tracks played in the last {} days",
+    ),
+    ("event.all_tracks", "Listing all tracks"),
+    ("event.audio_stream", "Audio stream {}/{}"),
+    ("event.prelistening", "Pre-listening {} at {}%"),
+    ("event.prelisten_stopped", "Stopped pre-listening"),
+    ("event.prelisten_failed", "Can't pre-listen: {}"),
+    (
+        "event.prelisten_unknown_length",
+        "Can't pre-listen {}, its length is unknown",
+    ),
+    ("event.unknown_command", "Unknown Command"),
+    ("event.exported", "Exported {} tracks to {}"),
+    ("event.export_failed", "Export failed: {}"),
+    ("event.bundled", "Bundled {} tracks to {}"),
+    ("event.bundle_failed", "Bundling failed: {}"),
+    ("event.imported", "Restored {} tracks from {}"),
+    (
+        "event.bundle_restore_failed",
+        "Restoring the bundle failed: {}",
+    ),
+    ("event.import_failed", "Import failed: {}"),
+    (
+        "event.config_reloaded_reanalyzing",
+        "Reloaded config, analyzing again",
+    ),
+    ("event.config_reloaded", "Reloaded config"),
+    ("event.undone", "Undid {}"),
+    ("event.nothing_to_undo", "Nothing to undo"),
+    ("event.redone", "Redid {}"),
+    ("event.nothing_to_redo", "Nothing to redo"),
+    ("event.no_duplicates", "No duplicates found"),
+    ("event.sample_loaded", "Loaded {} into sampler slot {}"),
+    (
+        "event.sample_load_failed",
+        "Can't load {} into the sampler: {}",
+    ),
+    ("event.end_of_track", "Track ends in {}"),
+    ("event.loaded", "Loaded {}"),
+    ("event.load_failed", "Can't load {}: {}"),
+    ("event.output_lost", "Output device lost, playback paused"),
+    (
+        "event.output_restored",
+        "Output reopened on the default device",
+    ),
+    (
+        "event.latency_saved",
+        "Output latency is {} ms, saved to config",
+    ),
+    ("event.latency_not_saved", "Failed to save latency: {}"),
+    (
+        "event.latency_unknown",
+        "The output does not report its latency",
+    ),
+    ("event.analyzed", "Analyzed: {}"),
+    ("event.removed", "Removed: {}"),
+    ("event.scanning", "Scanning {}"),
+    (
+        "event.drive_removed",
+        "{} was removed, {} tracks are unavailable",
+    ),
+    ("event.cant_load", "Can't load: {}"),
+    (
+        "event.drive_missing",
+        "Can't load: the drive of {} was removed",
+    ),
+    ("event.not_a_file", "Can't load: {} is not a file"),
+    ("event.clipboard_failed", "Can't read the clipboard: {}"),
+    ("event.picked_next", "Next: {} (Alt+n: pick another)"),
+    ("event.nothing_to_pick", "No track left to pick"),
+    ("event.opening", "Opening {}"),
+    (
+        "event.bpm_window_not_saved",
+        "Failed to save the BPM window: {}",
+    ),
+    ("event.reanalyzing", "Analyzing {} again"),
+    ("event.session_restored", "Restored {}"),
+    ("event.session_discarded", "Discarded the previous session"),
+    ("event.bpm_set", "BPM of {} set to {}"),
+    ("event.invalid_bpm", "Not a BPM: {}"),
+    ("event.marked", "Marked {}"),
+    ("event.unmarked", "Unmarked {}"),
+    ("event.tags_unchanged", "No tags were changed"),
+    ("event.tags_written", "Wrote the tags of {} files"),
+    (
+        "event.tags_not_written",
+        "Failed to write the tags of {}: {} ({} files were written)",
+    ),
+    ("event.chapters_written", "Wrote {} chapters to {}"),
+    ("event.chapters_not_written", "Failed to write the chapters: {}"),
+    (
+        "event.no_plays_in_mix",
+        "No tracks were played, while the mix was recorded",
+    ),
+    ("event.keys_not_saved", "Failed to save the keys: {}"),
+    ("event.key_set", "Key of {} set to {}"),
+    ("event.key_reset", "Key of {} reset to the tagged key"),
+    ("event.delete_failed", "Can't delete {}: {}"),
+    ("event.deleted", "Deleted {}"),
+    ("event.removed_from_library", "Removed {} from the library"),
+    ("event.columns_not_saved", "Failed to save the columns: {}"),
+    // edits, that can be undone
+    ("edit.remove_track", "removing {}"),
+    ("edit.add_bookmark", "adding {}"),
+    ("edit.add_mem_cue", "adding a memory cue to {}"),
+    ("edit.change_meta", "changing meta data of {} tracks"),
+    // stats overlay
+    ("stats.frame_time", "frame time   {} ms"),
+    ("stats.detail", "detail       1/{}"),
+    ("stats.audio_load", "audio load   {} %"),
+    ("stats.decoding", "decoding     {}x real time"),
+    ("stats.analyzing", "analyzing    {} files"),
+    ("stats.previews", "previews     {} MiB"),
+];
+
+/// texts of the selected locale, that replace the English ones
+static TRANSLATIONS: RwLock<Vec<(String, String)>> = RwLock::new(Vec::new());
+
+/// returns the path of the translations of a locale
+fn locale_path(locale: &str) -> Option<PathBuf> {
+    Config::path()?
+        .parent()
+        .map(|dir| dir.join("locales").join(format!("{}.txt", locale)))
+}
+
+/// selects the locale of the interface. Its translations are read from
+/// `<config dir>/locales/<locale>.txt`, which has one `id = text` line per text. `\n` in a text
+/// is a line break. Texts, that are missing from it, are shown in English
+pub fn set_locale(locale: &str) -> io::Result<()> {
+    let mut translations = vec![];
+    if locale != "en" {
+        let path = locale_path(locale)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((id, text)) = line.split_once('=') {
+                translations.push((String::from(id.trim()), text.trim().replace("\\n", "\n")));
+            }
+        }
+    }
+    *TRANSLATIONS.write().unwrap() = translations;
+    Ok(())
+}
+
+/// returns the text with the given id in the selected locale. Unknown ids are returned as they
+/// are, so they stand out
+pub fn tr(id: &str) -> String {
+    if let Some((_, text)) = TRANSLATIONS.read().unwrap().iter().find(|(i, _)| i == id) {
+        return text.clone();
+    }
+    ENGLISH
+        .iter()
+        .find(|(i, _)| *i == id)
+        .map_or_else(|| String::from(id), |(_, text)| String::from(*text))
+}
+
+/// returns the text with the given id in the selected locale, with its placeholders replaced
+/// by the arguments in order
+pub fn tr_args(id: &str, args: &[&dyn Display]) -> String {
+    let text = tr(id);
+    let mut parts = text.split("{}");
+    let mut res = String::from(parts.next().unwrap_or(""));
+    for (i, part) in parts.enumerate() {
+        if let Some(arg) = args.get(i) {
+            res.push_str(&arg.to_string());
+        }
+        res.push_str(part);
+    }
+    res
+}
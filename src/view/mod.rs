@@ -1,3 +1,5 @@
 pub mod app;
+pub mod i18n;
 pub mod model;
+pub mod simulation;
 pub mod widgets;
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, fs, io, path::Path, sync::Arc};
+
+use log::warn;
+use symphonia::core::units::TimeStamp;
+
+use crate::core::{
+    analysis_cache::{self, push_bytes, CachedAnalysis, Reader},
+    analyzer::ANALYZER_VERSION,
+    config::Config,
+    player::TimeMarker,
+};
+
+use super::{bookmarks::BookmarkStore, track::Track};
+
+//------------------------------------------------------------------//
+//                          AnalysisBundle                          //
+//------------------------------------------------------------------//
+
+/// Identifies bundle files
+const MAGIC: &[u8; 4] = b"FLWB";
+
+/// Version of the layout of bundles. Bundles of other versions are not read
+const FORMAT_VERSION: u32 = 1;
+
+/// The prepared state of a single track within a bundle
+struct Entry {
+    /// encoded analysis results, empty if the track was not analyzed yet
+    analysis: Vec<u8>,
+    /// timestamps of memory cues
+    mem_cues: Vec<TimeStamp>,
+    /// timestamps and names of bookmarks
+    bookmarks: Vec<(TimeStamp, String)>,
+}
+
+/// writes the analysis results, memory cues and bookmarks of tracks to a bundle file. Tracks are
+/// identified by their path relative to their library root, so the bundle can be imported into
+/// a library at another location. Returns the number of tracks.
+///
+/// A bundle consists of a header with the analyzer version and settings, the entries and a
+/// FNV-1a checksum of everything before it. All numbers are little endian.
+pub fn export<'a, I>(
+    tracks: I,
+    bookmarks: &BookmarkStore,
+    config: &Config,
+    path: &Path,
+) -> io::Result<usize>
+where
+    I: IntoIterator<Item = &'a Arc<Track>>,
+{
+    let mut content = vec![];
+    content.extend(MAGIC);
+    content.extend(FORMAT_VERSION.to_le_bytes());
+    content.extend(ANALYZER_VERSION.to_le_bytes());
+    push_bytes(&mut content, &analysis_cache::settings(config));
+    let mut entries = vec![];
+    for track in tracks {
+        if track.unsupported.is_some() {
+            continue;
+        }
+        // only finished analyses are cached
+        let analysis = analysis_cache::load(&track.file_path, config)
+            .map(|analysis| analysis.to_bytes())
+            .unwrap_or_default();
+        let mem_cues = track
+            .mem_cues
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|cue| cue.get_timestamp())
+            .collect();
+        let bookmarks = bookmarks
+            .get(&track.file_path)
+            .iter()
+            .map(|bookmark| (bookmark.ts, bookmark.name.clone()))
+            .collect();
+        entries.push((
+            key(track, config),
+            Entry {
+                analysis,
+                mem_cues,
+                bookmarks,
+            },
+        ));
+    }
+    content.extend((entries.len() as u32).to_le_bytes());
+    for (key, entry) in entries.iter() {
+        push_bytes(&mut content, key.as_bytes());
+        push_bytes(&mut content, &entry.analysis);
+        content.extend((entry.mem_cues.len() as u32).to_le_bytes());
+        for ts in entry.mem_cues.iter() {
+            content.extend(ts.to_le_bytes());
+        }
+        content.extend((entry.bookmarks.len() as u32).to_le_bytes());
+        for (ts, name) in entry.bookmarks.iter() {
+            content.extend(ts.to_le_bytes());
+            push_bytes(&mut content, name.as_bytes());
+        }
+    }
+    content.extend(analysis_cache::checksum(&content).to_le_bytes());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)?;
+    Ok(entries.len())
+}
+
+/// restores the analysis results, memory cues and bookmarks of tracks from a bundle, that was
+/// written by `export`. Analysis results are only used, if they were made by the same analyzer
+/// version with the same settings, and get cached for the next start. Returns the number of
+/// restored tracks.
+pub fn import<'a, I>(
+    tracks: I,
+    bookmarks: &mut BookmarkStore,
+    config: &Config,
+    path: &Path,
+) -> io::Result<usize>
+where
+    I: IntoIterator<Item = &'a Arc<Track>>,
+{
+    let content = fs::read(path)?;
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed analysis bundle");
+    let (content, checksum) = match content.len().checked_sub(8) {
+        Some(len) if content.starts_with(MAGIC) => content.split_at(len),
+        _ => return Err(malformed()),
+    };
+    if checksum != analysis_cache::checksum(content).to_le_bytes() {
+        return Err(malformed());
+    }
+    let mut reader = Reader(&content[MAGIC.len()..]);
+    if reader.u32().ok_or_else(malformed)? != FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unsupported analysis bundle version",
+        ));
+    }
+    let analyzer_version = reader.u32().ok_or_else(malformed)?;
+    let settings = reader.bytes().ok_or_else(malformed)?;
+    let comparable =
+        analyzer_version == ANALYZER_VERSION && settings == analysis_cache::settings(config);
+    if !comparable {
+        warn!("analysis bundle was made with other settings, only importing cues and bookmarks");
+    }
+    let entries = parse_entries(&mut reader).ok_or_else(malformed)?;
+    let mut restored = 0;
+    for track in tracks {
+        let entry = match entries.get(&key(track, config)) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        if let Some(analysis) = CachedAnalysis::from_bytes(&entry.analysis).filter(|_| comparable) {
+            if let Err(err) = analysis_cache::store(&track.file_path, config, &analysis) {
+                warn!(
+                    "failed to cache the analysis of {}: {}",
+                    track.file_path, err
+                );
+            }
+            track.set_preview_samples(analysis.preview);
+            let mut meta = track.meta.write().unwrap();
+            meta.bpm = analysis.bpm;
+            meta.extra = analysis.extra;
+        }
+        for ts in entry.mem_cues.iter() {
+            let cue = TimeMarker::from_ts(*ts, track.codec_params.clone());
+            let exists = track.mem_cues.lock().unwrap().iter().any(|c| *c == cue);
+            if !exists {
+                track.add_mem_cue(cue);
+            }
+        }
+        for (ts, name) in entry.bookmarks.iter() {
+            let exists = bookmarks
+                .get(&track.file_path)
+                .iter()
+                .any(|bookmark| bookmark.ts == *ts && bookmark.name == *name);
+            if !exists {
+                bookmarks.add(&track.file_path, *ts, name)?;
+            }
+        }
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+/// parses the entries of a bundle, keyed by track
+fn parse_entries(reader: &mut Reader) -> Option<HashMap<String, Entry>> {
+    let mut entries = HashMap::new();
+    for _ in 0..reader.u32()? {
+        let key = reader.string()?;
+        let analysis = reader.bytes()?.to_vec();
+        let mut mem_cues = vec![];
+        for _ in 0..reader.u32()? {
+            mem_cues.push(reader.u64()?);
+        }
+        let mut bookmarks = vec![];
+        for _ in 0..reader.u32()? {
+            let ts = reader.u64()?;
+            bookmarks.push((ts, reader.string()?));
+        }
+        entries.insert(
+            key,
+            Entry {
+                analysis,
+                mem_cues,
+                bookmarks,
+            },
+        );
+    }
+    Some(entries)
+}
+
+/// returns the path of a track relative to its library root, or its file name, if it is outside
+/// of all roots
+fn key(track: &Track, config: &Config) -> String {
+    let path = Path::new(&track.file_path);
+    config
+        .root_of(path)
+        .and_then(|root| path.strip_prefix(&root.path).ok())
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .unwrap_or_else(|| track.file_name.clone())
+}
@@ -0,0 +1,97 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use symphonia::core::units::TimeStamp;
+
+use crate::core::config;
+
+//------------------------------------------------------------------//
+//                            Bookmarks                             //
+//------------------------------------------------------------------//
+
+/// A named position within a track
+#[derive(Clone, Debug)]
+pub struct Bookmark {
+    pub name: String,
+    pub ts: TimeStamp,
+}
+
+/// Holds the bookmarks of all tracks, keyed by file path. Bookmarks are persisted to
+/// `<data dir>/bookmarks` with one `timestamp<TAB>file path<TAB>name` line per bookmark.
+pub struct BookmarkStore {
+    path: Option<PathBuf>,
+    bookmarks: HashMap<String, Vec<Bookmark>>,
+}
+
+impl BookmarkStore {
+    /// loads the persisted bookmarks
+    pub fn load() -> Self {
+        let path = config::data_dir().map(|dir| dir.join("bookmarks"));
+        let mut bookmarks: HashMap<String, Vec<Bookmark>> = HashMap::new();
+        if let Some(content) = path.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+            for line in content.lines() {
+                let mut fields = line.splitn(3, '\t');
+                if let (Some(ts), Some(file_path), Some(name)) =
+                    (fields.next(), fields.next(), fields.next())
+                {
+                    if let Ok(ts) = ts.parse() {
+                        bookmarks
+                            .entry(String::from(file_path))
+                            .or_default()
+                            .push(Bookmark {
+                                name: String::from(name),
+                                ts,
+                            });
+                    }
+                }
+            }
+        }
+        Self { path, bookmarks }
+    }
+
+    /// returns the bookmarks of a track, sorted by position
+    pub fn get(&self, file_path: &str) -> &[Bookmark] {
+        self.bookmarks
+            .get(file_path)
+            .map(|bookmarks| bookmarks.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// adds a bookmark to a track and persists all bookmarks
+    pub fn add(&mut self, file_path: &str, ts: TimeStamp, name: &str) -> io::Result<()> {
+        let name = name.replace(['\t', '\n'], " ");
+        let bookmarks = self.bookmarks.entry(String::from(file_path)).or_default();
+        bookmarks.push(Bookmark { name, ts });
+        bookmarks.sort_by_key(|bookmark| bookmark.ts);
+        self.save()
+    }
+
+    /// removes a bookmark from a track and persists all bookmarks
+    pub fn remove(&mut self, file_path: &str, ts: TimeStamp, name: &str) -> io::Result<()> {
+        if let Some(bookmarks) = self.bookmarks.get_mut(file_path) {
+            if let Some(index) = bookmarks.iter().position(|b| b.ts == ts && b.name == name) {
+                bookmarks.remove(index);
+            }
+        }
+        self.save()
+    }
+
+    /// writes all bookmarks to the bookmarks file
+    fn save(&self) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut content = String::new();
+            for (file_path, bookmarks) in &self.bookmarks {
+                for bookmark in bookmarks {
+                    content.push_str(&format!(
+                        "{}\t{}\t{}\n",
+                        bookmark.ts, file_path, bookmark.name
+                    ));
+                }
+            }
+            fs::write(path, content)?;
+        }
+        Ok(())
+    }
+}
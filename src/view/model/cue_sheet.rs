@@ -0,0 +1,147 @@
+use std::{fs, path::Path};
+
+//------------------------------------------------------------------//
+//                             CueSheet                             //
+//------------------------------------------------------------------//
+
+/// A track within a cue sheet
+#[derive(Clone, Debug)]
+pub struct CueSheetTrack {
+    pub number: u32,
+    pub title: String,
+    pub performer: String,
+    /// start of the track within the file in seconds
+    pub start: f64,
+}
+
+/// The track listing of a single-file mix, parsed from a .cue file
+#[derive(Clone, Debug, Default)]
+pub struct CueSheet {
+    pub tracks: Vec<CueSheetTrack>,
+}
+
+impl CueSheet {
+    /// looks for a cue sheet next to an audio file, which has the file's name with a .cue
+    /// extension
+    pub fn for_file(file_path: &str) -> Option<Self> {
+        let content = fs::read_to_string(Path::new(file_path).with_extension("cue")).ok()?;
+        let cue_sheet = CueSheet::parse(&content);
+        if cue_sheet.tracks.is_empty() {
+            None
+        } else {
+            Some(cue_sheet)
+        }
+    }
+
+    /// parses the tracks of a cue sheet. Unknown commands are ignored
+    pub fn parse(content: &str) -> Self {
+        let mut tracks: Vec<CueSheetTrack> = vec![];
+        for line in content.lines() {
+            let line = line.trim();
+            let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+            match (command, tracks.last_mut()) {
+                ("TRACK", _) => {
+                    let number = args
+                        .split_whitespace()
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or(tracks.len() as u32 + 1);
+                    tracks.push(CueSheetTrack {
+                        number,
+                        title: String::new(),
+                        performer: String::new(),
+                        start: 0.,
+                    });
+                }
+                ("TITLE", Some(track)) => track.title = unquote(args),
+                ("PERFORMER", Some(track)) => track.performer = unquote(args),
+                ("INDEX", Some(track)) => {
+                    let mut args = args.split_whitespace();
+                    // index 01 marks the start of the track, index 00 the pregap
+                    if let (Some("01"), Some(time)) = (args.next(), args.next()) {
+                        if let Some(start) = parse_time(time) {
+                            track.start = start;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self { tracks }
+    }
+
+    /// returns the index of the track, that is playing at the given position
+    pub fn track_at(&self, seconds: f64) -> Option<usize> {
+        self.tracks.iter().rposition(|track| track.start <= seconds)
+    }
+}
+
+/// removes surrounding quotes from a cue sheet value
+fn unquote(value: &str) -> String {
+    String::from(value.trim().trim_matches('"'))
+}
+
+/// parses a cue sheet time in mm:ss:ff format, with 75 frames per second
+fn parse_time(time: &str) -> Option<f64> {
+    let parts: Vec<u32> = time
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [minutes, seconds, frames] => {
+            Some(minutes as f64 * 60. + seconds as f64 + frames as f64 / 75.)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_minutes_seconds_and_frames() {
+        assert_eq!(parse_time("01:02:15"), Some(62.2));
+        assert_eq!(parse_time("75:00:00"), Some(4500.));
+        assert_eq!(parse_time("01:02"), None);
+        assert_eq!(parse_time("01:xx:00"), None);
+    }
+
+    #[test]
+    fn parses_tracks_with_their_start_and_skips_the_pregap() {
+        let cue_sheet = CueSheet::parse(
+            r#"PERFORMER "Some DJ"
+TITLE "Some Mix"
+FILE "mix.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "Intro"
+    PERFORMER "First Artist"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Second Track"
+    PERFORMER "Second Artist"
+    INDEX 00 03:58:00
+    INDEX 01 04:00:30
+  TRACK AUDIO
+    REM unknown commands are ignored
+    INDEX 01 09:30:00
+"#,
+        );
+        let tracks: Vec<_> = cue_sheet
+            .tracks
+            .iter()
+            .map(|t| (t.number, t.title.as_str(), t.performer.as_str(), t.start))
+            .collect();
+        assert_eq!(
+            tracks,
+            [
+                (1, "Intro", "First Artist", 0.),
+                (2, "Second Track", "Second Artist", 240.4),
+                (3, "", "", 570.),
+            ]
+        );
+        assert_eq!(cue_sheet.track_at(239.), Some(0));
+        assert_eq!(cue_sheet.track_at(240.4), Some(1));
+        assert_eq!(cue_sheet.track_at(3600.), Some(2));
+    }
+}
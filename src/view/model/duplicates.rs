@@ -0,0 +1,69 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs::{self, File},
+    hash::Hasher,
+    io::{self, Read},
+    sync::Arc,
+};
+
+use super::track::Track;
+
+//------------------------------------------------------------------//
+//                            Duplicates                            //
+//------------------------------------------------------------------//
+
+/// groups tracks, that are copies of the same file. Candidates need to have the same file size
+/// and duration, and are confirmed by hashing their content. Every group has at least two tracks.
+pub fn find_duplicates<'a, I>(tracks: I) -> Vec<Vec<Arc<Track>>>
+where
+    I: IntoIterator<Item = &'a Arc<Track>>,
+{
+    let mut candidates: HashMap<(u64, Option<u64>), Vec<Arc<Track>>> = HashMap::new();
+    for track in tracks {
+        if track.unsupported.is_some() {
+            continue;
+        }
+        if let Ok(metadata) = fs::metadata(&track.file_path) {
+            let key = (metadata.len(), duration(track));
+            candidates.entry(key).or_default().push(Arc::clone(track));
+        }
+    }
+    let mut groups = vec![];
+    for candidates in candidates.into_values().filter(|c| c.len() > 1) {
+        let mut by_hash: HashMap<u64, Vec<Arc<Track>>> = HashMap::new();
+        for track in candidates {
+            if let Ok(hash) = hash_file(&track.file_path) {
+                by_hash.entry(hash).or_default().push(track);
+            }
+        }
+        groups.extend(by_hash.into_values().filter(|group| group.len() > 1));
+    }
+    // keep the order stable between scans
+    for group in groups.iter_mut() {
+        group.sort();
+    }
+    groups.sort();
+    groups
+}
+
+/// returns the duration of a track in whole seconds
+fn duration(track: &Track) -> Option<u64> {
+    let n_frames = track.codec_params.n_frames?;
+    let sample_rate = track.codec_params.sample_rate?;
+    Some(n_frames / sample_rate as u64)
+}
+
+/// hashes the content of a file
+fn hash_file(file_path: &str) -> io::Result<u64> {
+    let mut file = File::open(file_path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
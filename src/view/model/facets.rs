@@ -0,0 +1,126 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+use crate::view::i18n::tr;
+
+use super::track::{Track, TrackMeta};
+
+//------------------------------------------------------------------//
+//                              Facets                              //
+//------------------------------------------------------------------//
+
+/// Tags, that the library can be narrowed down by
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum FacetKind {
+    Genre,
+    Label,
+    Year,
+}
+
+impl FacetKind {
+    pub fn title(&self) -> String {
+        tr(match self {
+            FacetKind::Genre => "facet.genre",
+            FacetKind::Label => "facet.label",
+            FacetKind::Year => "facet.year",
+        })
+    }
+
+    /// returns the value of this tag of a track, or None, if it isn't tagged
+    fn value_of(&self, meta: &TrackMeta) -> Option<String> {
+        let value = match self {
+            FacetKind::Genre => meta.genre.trim().to_string(),
+            FacetKind::Label => meta.label.trim().to_string(),
+            FacetKind::Year if meta.year > 0 => meta.year.to_string(),
+            FacetKind::Year => String::new(),
+        };
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+/// A tag value, that is present in the library
+pub struct FacetEntry {
+    pub kind: FacetKind,
+    pub value: String,
+    /// number of tracks with this value
+    pub count: usize,
+}
+
+/// The genres, labels and years of the library with their track counts. Selected values narrow
+/// the library down to tracks, that have any of the selected values of each kind
+#[derive(Default)]
+pub struct Facets {
+    entries: Vec<FacetEntry>,
+    selected: HashSet<(FacetKind, String)>,
+}
+
+impl Facets {
+    /// counts the values of the given tracks. Selected values stay selected, even if no track
+    /// has them anymore
+    pub fn update<'a, I>(&mut self, tracks: I)
+    where
+        I: IntoIterator<Item = &'a Arc<Track>>,
+    {
+        let mut counts: BTreeMap<(FacetKind, String), usize> = BTreeMap::new();
+        for track in tracks {
+            let meta = track.meta.read().unwrap();
+            for kind in [FacetKind::Genre, FacetKind::Label, FacetKind::Year] {
+                if let Some(value) = kind.value_of(&meta) {
+                    *counts.entry((kind, value)).or_insert(0) += 1;
+                }
+            }
+        }
+        for selected in self.selected.iter() {
+            counts.entry(selected.clone()).or_insert(0);
+        }
+        self.entries = counts
+            .into_iter()
+            .map(|((kind, value), count)| FacetEntry { kind, value, count })
+            .collect();
+    }
+
+    pub fn entries(&self) -> &[FacetEntry] {
+        &self.entries
+    }
+
+    pub fn is_selected(&self, i: usize) -> bool {
+        self.entries
+            .get(i)
+            .is_some_and(|e| self.selected.contains(&(e.kind, e.value.clone())))
+    }
+
+    /// selects or deselects the entry at `i`
+    pub fn toggle(&mut self, i: usize) {
+        if let Some(entry) = self.entries.get(i) {
+            let key = (entry.kind, entry.value.clone());
+            if !self.selected.remove(&key) {
+                self.selected.insert(key);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.selected.clear();
+    }
+
+    /// returns true, if any value is selected
+    pub fn is_active(&self) -> bool {
+        !self.selected.is_empty()
+    }
+
+    /// returns true, if the track has one of the selected values of each kind, that has any
+    pub fn matches(&self, meta: &TrackMeta) -> bool {
+        [FacetKind::Genre, FacetKind::Label, FacetKind::Year]
+            .iter()
+            .all(|kind| {
+                let mut selected = self.selected.iter().filter(|(k, _)| k == kind).peekable();
+                if selected.peek().is_none() {
+                    return true;
+                }
+                let value = kind.value_of(meta);
+                selected.any(|(_, v)| Some(v) == value.as_ref())
+            })
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use crate::core::config;
+
+use super::set_plan::camelot;
+
+//------------------------------------------------------------------//
+//                           KeyOverrides                           //
+//------------------------------------------------------------------//
+
+/// The keys of the Camelot wheel with their musical names, in the order of the key picker
+pub const KEYS: [(&str, &str); 24] = [
+    ("1A", "A♭m"),
+    ("1B", "B"),
+    ("2A", "E♭m"),
+    ("2B", "F♯"),
+    ("3A", "B♭m"),
+    ("3B", "D♭"),
+    ("4A", "Fm"),
+    ("4B", "A♭"),
+    ("5A", "Cm"),
+    ("5B", "E♭"),
+    ("6A", "Gm"),
+    ("6B", "B♭"),
+    ("7A", "Dm"),
+    ("7B", "F"),
+    ("8A", "Am"),
+    ("8B", "C"),
+    ("9A", "Em"),
+    ("9B", "G"),
+    ("10A", "Bm"),
+    ("10B", "D"),
+    ("11A", "F♯m"),
+    ("11B", "A"),
+    ("12A", "D♭m"),
+    ("12B", "E"),
+];
+
+/// Holds the keys, that the user set for tracks instead of their tagged keys, keyed by file
+/// path. They are persisted to `<data dir>/keys` with one `key<TAB>file path` line per track.
+pub struct KeyOverrideStore {
+    path: Option<PathBuf>,
+    keys: HashMap<String, String>,
+}
+
+impl KeyOverrideStore {
+    /// loads the persisted keys. Lines with invalid keys are skipped
+    pub fn load() -> Self {
+        let path = config::data_dir().map(|dir| dir.join("keys"));
+        let mut keys = HashMap::new();
+        if let Some(content) = path.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+            for line in content.lines() {
+                if let Some((key, file_path)) = line.split_once('\t') {
+                    if camelot(key).is_some() {
+                        keys.insert(String::from(file_path), String::from(key));
+                    }
+                }
+            }
+        }
+        Self { path, keys }
+    }
+
+    /// returns the key, that the user set for a track
+    pub fn get(&self, file_path: &str) -> Option<&str> {
+        self.keys.get(file_path).map(String::as_str)
+    }
+
+    /// sets the key of a track, or goes back to its tagged key, and persists all keys. Keys,
+    /// that are not on the Camelot wheel, are rejected
+    pub fn set(&mut self, file_path: &str, key: Option<&str>) -> io::Result<()> {
+        match key {
+            Some(key) if camelot(key).is_none() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("`{}` is not a key", key),
+                ))
+            }
+            Some(key) => self.keys.insert(String::from(file_path), String::from(key)),
+            None => self.keys.remove(file_path),
+        };
+        self.save()
+    }
+
+    /// writes all keys to the keys file
+    fn save(&self) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut content = String::new();
+            for (file_path, key) in &self.keys {
+                content.push_str(&format!("{}\t{}\n", key, file_path));
+            }
+            fs::write(path, content)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use std::{collections::HashMap, fs, io, iter::Peekable, path::Path, str::Chars, sync::Arc};
+
+use super::track::Track;
+
+//------------------------------------------------------------------//
+//                          LibraryExport                           //
+//------------------------------------------------------------------//
+
+/// columns of an exported library, in the order they are written
+const FIELDS: [&str; 5] = ["file_path", "artist", "title", "album", "bpm"];
+
+/// a single exported track, keyed by field name
+type Entry = HashMap<String, String>;
+
+/// writes the meta data of all tracks to a file. Files ending in `.json` are written as a JSON
+/// array of objects, everything else as CSV with a header line. Returns the number of tracks.
+pub fn export<'a, I>(tracks: I, path: &Path) -> io::Result<usize>
+where
+    I: IntoIterator<Item = &'a Arc<Track>>,
+{
+    let rows: Vec<[String; 5]> = tracks
+        .into_iter()
+        .filter(|track| track.unsupported.is_none())
+        .map(|track| {
+            let meta = track.meta.read().unwrap();
+            [
+                track.file_path.clone(),
+                meta.artist.clone(),
+                meta.title.clone(),
+                meta.album.clone(),
+                meta.bpm.to_string(),
+            ]
+        })
+        .collect();
+    let content = if is_json(path) {
+        let objects: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                let fields: Vec<String> = FIELDS
+                    .iter()
+                    .zip(row)
+                    .map(|(key, value)| match *key {
+                        "bpm" => format!("\"{}\": {}", key, value),
+                        _ => format!("\"{}\": {}", key, json_string(value)),
+                    })
+                    .collect();
+                format!("  {{{}}}", fields.join(", "))
+            })
+            .collect();
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    } else {
+        let mut content = FIELDS.join(",");
+        content.push('\n');
+        for row in rows.iter() {
+            let fields: Vec<String> = row.iter().map(|value| csv_field(value)).collect();
+            content.push_str(&fields.join(","));
+            content.push('\n');
+        }
+        content
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)?;
+    Ok(rows.len())
+}
+
+/// restores the meta data of tracks from a file, that was written by `export`. Tracks are
+/// matched by their file path and empty values are skipped. Returns the number of restored tracks.
+pub fn import<'a, I>(tracks: I, path: &Path) -> io::Result<usize>
+where
+    I: IntoIterator<Item = &'a Arc<Track>>,
+{
+    let content = fs::read_to_string(path)?;
+    let entries = if is_json(path) {
+        parse_json(&content)
+    } else {
+        parse_csv(&content)
+    }
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed library export"))?;
+    let entries: HashMap<&str, &Entry> = entries
+        .iter()
+        .filter_map(|entry| Some((entry.get("file_path")?.as_str(), entry)))
+        .collect();
+    let mut restored = 0;
+    for track in tracks {
+        if let Some(entry) = entries.get(track.file_path.as_str()) {
+            let mut meta = track.meta.write().unwrap();
+            let value = |key: &str| entry.get(key).filter(|v| !v.is_empty()).cloned();
+            if let Some(artist) = value("artist") {
+                meta.artist = artist;
+            }
+            if let Some(title) = value("title") {
+                meta.title = title;
+            }
+            if let Some(album) = value("album") {
+                meta.album = album;
+            }
+            if let Some(bpm) = value("bpm").and_then(|bpm| bpm.parse().ok()) {
+                meta.bpm = bpm;
+            }
+            restored += 1;
+        }
+    }
+    Ok(restored)
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+//------------------------------------------------------------------//
+//                               CSV                                //
+//------------------------------------------------------------------//
+
+/// quotes a CSV field, if it contains separators, quotes or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+/// parses CSV content, whose first line names the columns
+fn parse_csv(content: &str) -> Option<Vec<Entry>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    let mut records = records.into_iter();
+    let header = records.next()?;
+    Some(
+        records
+            .filter(|record| record.len() == header.len())
+            .map(|record| header.iter().cloned().zip(record).collect())
+            .collect(),
+    )
+}
+
+//------------------------------------------------------------------//
+//                               JSON                               //
+//------------------------------------------------------------------//
+
+/// encodes a string as a JSON string literal
+fn json_string(value: &str) -> String {
+    let mut res = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// parses a JSON array of flat objects with string or number values
+fn parse_json(content: &str) -> Option<Vec<Entry>> {
+    let mut chars = content.chars().peekable();
+    let mut entries = vec![];
+    expect(&mut chars, '[')?;
+    if skip_whitespace(&mut chars) == Some(']') {
+        return Some(entries);
+    }
+    loop {
+        expect(&mut chars, '{')?;
+        let mut entry = Entry::new();
+        if skip_whitespace(&mut chars) == Some('}') {
+            chars.next();
+        } else {
+            loop {
+                expect(&mut chars, '"')?;
+                let key = parse_json_string(&mut chars)?;
+                expect(&mut chars, ':')?;
+                let value = match skip_whitespace(&mut chars)? {
+                    '"' => {
+                        chars.next();
+                        parse_json_string(&mut chars)?
+                    }
+                    _ => {
+                        let mut number = String::new();
+                        while let Some(c) =
+                            chars.next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+                        {
+                            number.push(c);
+                        }
+                        number
+                    }
+                };
+                entry.insert(key, value);
+                match skip_whitespace(&mut chars)? {
+                    ',' => chars.next(),
+                    '}' => {
+                        chars.next();
+                        break;
+                    }
+                    _ => return None,
+                };
+            }
+        }
+        entries.push(entry);
+        match skip_whitespace(&mut chars)? {
+            ',' => chars.next(),
+            ']' => return Some(entries),
+            _ => return None,
+        };
+    }
+}
+
+/// parses the rest of a JSON string, after its opening quote
+fn parse_json_string(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut res = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(res),
+            '\\' => match chars.next()? {
+                'n' => res.push('\n'),
+                'r' => res.push('\r'),
+                't' => res.push('\t'),
+                'b' => res.push('\u{8}'),
+                'f' => res.push('\u{c}'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    res.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => res.push(c),
+            },
+            c => res.push(c),
+        }
+    }
+}
+
+/// skips whitespace and returns the next character without consuming it
+fn skip_whitespace(chars: &mut Peekable<Chars>) -> Option<char> {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    chars.peek().copied()
+}
+
+/// consumes the next non-whitespace character, if it is the expected one
+fn expect(chars: &mut Peekable<Chars>, expected: char) -> Option<()> {
+    skip_whitespace(chars);
+    chars.next_if_eq(&expected).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config;
+    use symphonia::core::codecs::CodecParameters;
+
+    fn track(file_path: &str, artist: &str, title: &str, bpm: u32) -> Arc<Track> {
+        let track = Track::new(String::from(file_path), CodecParameters::new());
+        {
+            let mut meta = track.meta.write().unwrap();
+            meta.artist = String::from(artist);
+            meta.title = String::from(title);
+            meta.album = String::from("Live, \"Unplugged\"\r\nDisc 2");
+            meta.bpm = bpm;
+        }
+        Arc::new(track)
+    }
+
+    fn round_trip(file_name: &str) {
+        let tracks = [
+            track("/music/a \"quoted\".mp3", "Artist, The", "Say \"Hi\"", 124),
+            track("/music/b.flac", "Back\\slash\ttab", "Two\nLines", 0),
+            track("/music/c.ogg", "", "", 98),
+        ];
+        let path = config::use_test_data_dir()
+            .join("library_export")
+            .join(file_name);
+        assert_eq!(export(tracks.iter(), &path).unwrap(), 3);
+        let imported: Vec<Arc<Track>> = tracks
+            .iter()
+            .map(|track| track.file_path.as_str())
+            .map(|file_path| Arc::new(Track::new(String::from(file_path), CodecParameters::new())))
+            .collect();
+        assert_eq!(import(imported.iter(), &path).unwrap(), 3);
+        for (track, imported) in tracks.iter().zip(imported.iter()) {
+            assert_eq!(*track.meta.read().unwrap(), *imported.meta.read().unwrap());
+        }
+    }
+
+    #[test]
+    fn restores_quoted_fields_line_breaks_and_quotes_from_csv() {
+        round_trip("library.csv");
+    }
+
+    #[test]
+    fn restores_escaped_strings_from_json() {
+        round_trip("library.json");
+    }
+}
@@ -1 +1,15 @@
+pub mod analysis_bundle;
+pub mod bookmarks;
+pub mod cue_sheet;
+pub mod duplicates;
+pub mod facets;
+pub mod key_overrides;
+pub mod library_export;
+pub mod play_stats;
+pub mod playhead;
+pub mod render_cache;
+pub mod scan_progress;
+pub mod session;
+pub mod set_plan;
 pub mod track;
+pub mod undo;
@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::core::config;
+
+//------------------------------------------------------------------//
+//                            PlayStats                             //
+//------------------------------------------------------------------//
+
+/// How often and when a track was played
+#[derive(Clone, Copy, Debug)]
+pub struct PlayStats {
+    pub count: u32,
+    /// seconds since the unix epoch
+    pub last_played: u64,
+}
+
+/// A single play of a track
+#[derive(Clone, Debug, PartialEq)]
+pub struct Play {
+    /// seconds since the unix epoch, at which the track started playing
+    pub started: u64,
+    pub file_path: String,
+}
+
+/// Holds the play statistics of all tracks, keyed by file path. They are persisted to
+/// `<data dir>/play_stats` with one `count<TAB>last played<TAB>file path` line per track.
+/// Every play is also appended to `<data dir>/play_log` as a `started<TAB>file path` line.
+pub struct PlayStatsStore {
+    path: Option<PathBuf>,
+    log_path: Option<PathBuf>,
+    stats: HashMap<String, PlayStats>,
+}
+
+impl PlayStatsStore {
+    /// loads the persisted statistics
+    pub fn load() -> Self {
+        let path = config::data_dir().map(|dir| dir.join("play_stats"));
+        let log_path = config::data_dir().map(|dir| dir.join("play_log"));
+        let mut stats = HashMap::new();
+        if let Some(content) = path.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+            for line in content.lines() {
+                let mut fields = line.splitn(3, '\t');
+                if let (Some(count), Some(last_played), Some(file_path)) =
+                    (fields.next(), fields.next(), fields.next())
+                {
+                    if let (Ok(count), Ok(last_played)) = (count.parse(), last_played.parse()) {
+                        stats.insert(String::from(file_path), PlayStats { count, last_played });
+                    }
+                }
+            }
+        }
+        Self {
+            path,
+            log_path,
+            stats,
+        }
+    }
+
+    /// returns the statistics of a track, if it was ever played
+    pub fn get(&self, file_path: &str) -> Option<PlayStats> {
+        self.stats.get(file_path).copied()
+    }
+
+    /// counts a play of a track, that started playing at `started`, logs it and persists all
+    /// statistics
+    pub fn record(&mut self, file_path: &str, started: u64) -> io::Result<()> {
+        let stats = self
+            .stats
+            .entry(String::from(file_path))
+            .or_insert(PlayStats {
+                count: 0,
+                last_played: 0,
+            });
+        stats.count += 1;
+        stats.last_played = now();
+        if let Some(path) = &self.log_path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut log = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(log, "{}\t{}", started, file_path)?;
+        }
+        self.save()
+    }
+
+    /// returns the logged plays, that were playing between `start` and `end`
+    pub fn plays_between(&self, start: u64, end: u64) -> io::Result<Vec<Play>> {
+        let content = match &self.log_path {
+            Some(path) if path.exists() => fs::read_to_string(path)?,
+            _ => String::new(),
+        };
+        Ok(plays_between(parse_log(&content), start, end))
+    }
+
+    /// returns true, if the track was played within the given number of days
+    pub fn played_within(&self, file_path: &str, days: u32) -> bool {
+        let since = now().saturating_sub(days as u64 * 24 * 60 * 60);
+        self.stats
+            .get(file_path)
+            .is_some_and(|stats| stats.last_played >= since)
+    }
+
+    /// writes all statistics to the statistics file
+    fn save(&self) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut content = String::new();
+            for (file_path, stats) in &self.stats {
+                content.push_str(&format!(
+                    "{}\t{}\t{}\n",
+                    stats.count, stats.last_played, file_path
+                ));
+            }
+            fs::write(path, content)?;
+        }
+        Ok(())
+    }
+}
+
+/// parses the lines of the play log. Malformed lines are skipped
+fn parse_log(content: &str) -> Vec<Play> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (started, file_path) = line.split_once('\t')?;
+            Some(Play {
+                started: started.parse().ok()?,
+                file_path: String::from(file_path),
+            })
+        })
+        .collect()
+}
+
+/// returns the plays, that started between `start` and `end`, in the order they started. The
+/// track, that was already playing at `start`, comes first and is moved to `start`
+fn plays_between(mut plays: Vec<Play>, start: u64, end: u64) -> Vec<Play> {
+    plays.sort_by_key(|play| play.started);
+    let first = plays.iter().rposition(|play| play.started <= start);
+    let mut res: Vec<Play> = first
+        .map(|i| Play {
+            started: start,
+            file_path: plays[i].file_path.clone(),
+        })
+        .into_iter()
+        .collect();
+    res.extend(
+        plays
+            .into_iter()
+            .filter(|play| play.started > start && play.started < end),
+    );
+    res
+}
+
+/// returns the current time in seconds since the unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(started: u64, file_path: &str) -> Play {
+        Play {
+            started,
+            file_path: String::from(file_path),
+        }
+    }
+
+    #[test]
+    fn parses_the_play_log_and_skips_malformed_lines() {
+        let plays =
+            parse_log("100\tsets/intro.flac\nbroken\nxx\tsets/outro.flac\n200\tsets/a\tb.mp3\n");
+        assert_eq!(
+            plays,
+            [play(100, "sets/intro.flac"), play(200, "sets/a\tb.mp3")]
+        );
+    }
+
+    #[test]
+    fn starts_the_plays_of_a_recording_with_the_track_playing_at_its_start() {
+        let plays = vec![
+            play(400, "d.flac"),
+            play(100, "a.flac"),
+            play(200, "b.flac"),
+            play(300, "c.flac"),
+            play(50, "z.flac"),
+        ];
+        assert_eq!(
+            plays_between(plays.clone(), 150, 400),
+            [
+                play(150, "a.flac"),
+                play(200, "b.flac"),
+                play(300, "c.flac")
+            ]
+        );
+        // nothing was playing yet, when the recording started
+        assert_eq!(
+            plays_between(plays, 0, 120),
+            [play(50, "z.flac"), play(100, "a.flac")]
+        );
+    }
+}
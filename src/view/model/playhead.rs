@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use crate::core::player::TimeMarker;
+
+//------------------------------------------------------------------//
+//                             Playhead                             //
+//------------------------------------------------------------------//
+
+/// Longest step in seconds between two positions, that still counts as continuous playback.
+/// Larger steps are seeks or jumps, which are shown right away
+const MAX_STEP: f64 = 0.5;
+
+/// Interpolates the playhead between the position updates of the player, which only arrive once
+/// per decoded packet. Playback always runs at the original speed, so the position advances with
+/// wall-clock time. The drawn position lags behind by the output latency, so it matches what is
+/// audible.
+#[derive(Default)]
+pub struct Playhead {
+    /// last position reported by the player and the time at which it was first seen
+    observed: Option<(TimeMarker, Instant)>,
+    /// seconds, that the last update advanced the position by
+    step: f64,
+    /// wall-clock time between the last two updates
+    interval: Duration,
+}
+
+impl Playhead {
+    /// takes the current position of the player and returns the position to draw, compensated
+    /// by the given latency in seconds
+    pub fn update(&mut self, position: &Option<TimeMarker>, latency: f64) -> Option<TimeMarker> {
+        let position = self.interpolate(position)?;
+        if latency > 0. {
+            Some(position.advanced_by(-latency))
+        } else {
+            Some(position)
+        }
+    }
+
+    /// returns the position of the player, advanced by the time since its last update
+    fn interpolate(&mut self, position: &Option<TimeMarker>) -> Option<TimeMarker> {
+        let position = match position {
+            Some(position) => position,
+            None => {
+                *self = Self::default();
+                return None;
+            }
+        };
+        let now = Instant::now();
+        match &self.observed {
+            Some((observed, seen)) if observed == position => {
+                let elapsed = now.duration_since(*seen);
+                // without an update for a while, playback is paused
+                if self.step > 0. && elapsed < self.interval * 2 {
+                    return Some(position.advanced_by(elapsed.as_secs_f64().min(self.step)));
+                }
+            }
+            Some((observed, seen)) => {
+                let step = position.get_time_in_seconds() - observed.get_time_in_seconds();
+                if step > 0. && step < MAX_STEP {
+                    self.step = step;
+                    self.interval = now.duration_since(*seen);
+                } else {
+                    self.step = 0.;
+                }
+                self.observed = Some((position.clone(), now));
+            }
+            None => self.observed = Some((position.clone(), now)),
+        }
+        Some(position.clone())
+    }
+}
@@ -0,0 +1,32 @@
+use std::rc::Rc;
+
+//------------------------------------------------------------------//
+//                           RenderCache                            //
+//------------------------------------------------------------------//
+
+/// Keeps the content of a pane between frames, so it is only computed again, once the inputs,
+/// that it was computed from, change
+pub struct RenderCache<K, V> {
+    entry: Option<(K, Rc<V>)>,
+}
+
+impl<K: PartialEq, V> RenderCache<K, V> {
+    /// returns the content for the given inputs. It is only computed, if the inputs differ from
+    /// those of the last call
+    pub fn get(&mut self, key: K, compute: impl FnOnce() -> V) -> Rc<V> {
+        match &self.entry {
+            Some((cached, value)) if *cached == key => Rc::clone(value),
+            _ => {
+                let value = Rc::new(compute());
+                self.entry = Some((key, Rc::clone(&value)));
+                value
+            }
+        }
+    }
+}
+
+impl<K, V> Default for RenderCache<K, V> {
+    fn default() -> Self {
+        Self { entry: None }
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt;
+
+use crate::view::i18n::tr_args;
+
+//------------------------------------------------------------------//
+//                           ScanProgress                           //
+//------------------------------------------------------------------//
+
+/// Counts of the files, that were handed to the analyzer since the last scan finished
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScanProgress {
+    /// files, that are being scanned
+    pub found: usize,
+    /// files, that could be opened
+    pub probed: usize,
+    /// files, whose analysis finished or was restored from the cache
+    pub analyzed: usize,
+    /// files, that could not be opened
+    pub failed: usize,
+}
+
+impl ScanProgress {
+    /// returns whether every found file was either analyzed or failed
+    pub fn is_done(&self) -> bool {
+        self.analyzed + self.failed >= self.found
+    }
+}
+
+impl fmt::Display for ScanProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = tr_args(
+            "scan.progress",
+            &[
+                &(self.analyzed + self.failed),
+                &self.found,
+                &self.probed,
+                &self.analyzed,
+                &self.failed,
+            ],
+        );
+        f.write_str(&text)
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+
+use crate::core::config;
+
+//------------------------------------------------------------------//
+//                             Session                              //
+//------------------------------------------------------------------//
+
+/// The part of the app state, that is restored on the next start. It is kept in
+/// `<data dir>/session` as `key = value` lines. While the app runs, the same state is journaled
+/// to `<data dir>/journal`, which is removed again on a clean exit.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Session {
+    /// file path of the loaded track
+    pub loaded_track: Option<String>,
+    /// playhead position within the loaded track in seconds
+    pub position: f64,
+    /// zoom level of the live preview
+    pub zoom_level: Option<u32>,
+}
+
+impl Session {
+    /// returns the path of the session file, that is written on exit
+    pub fn session_file() -> Option<PathBuf> {
+        config::data_dir().map(|dir| dir.join("session"))
+    }
+
+    /// returns the path of the journal. Finding it on startup means, that the last run crashed
+    pub fn journal_file() -> Option<PathBuf> {
+        config::data_dir().map(|dir| dir.join("journal"))
+    }
+
+    /// loads a session from a file, if it exists
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let mut session = Session::default();
+        for line in content.lines() {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            match key {
+                "loaded_track" => session.loaded_track = Some(String::from(value)),
+                "position" => session.position = value.parse().unwrap_or(0.),
+                "zoom_level" => session.zoom_level = value.parse().ok(),
+                _ => warn!("unknown session entry `{}`", key),
+            }
+        }
+        Some(session)
+    }
+
+    /// writes the session to a file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = String::new();
+        if let Some(loaded_track) = &self.loaded_track {
+            content.push_str(&format!("loaded_track = {}\n", loaded_track));
+            content.push_str(&format!("position = {}\n", self.position));
+        }
+        if let Some(zoom_level) = self.zoom_level {
+            content.push_str(&format!("zoom_level = {}\n", zoom_level));
+        }
+        fs::write(path, content)
+    }
+}
@@ -0,0 +1,275 @@
+use std::{cmp::Ordering, fs, io, path::Path, sync::Arc};
+
+use super::track::{Track, TrackMeta};
+
+//------------------------------------------------------------------//
+//                             SetPlan                              //
+//------------------------------------------------------------------//
+
+/// largest tempo change in percent, that is still considered a smooth transition
+const MAX_TEMPO_CHANGE: f64 = 6.;
+
+/// An ordered list of tracks, that is prepared ahead of a set
+#[derive(Default)]
+pub struct SetPlan {
+    tracks: Vec<Arc<Track>>,
+}
+
+/// How well two consecutive tracks of a set mix
+pub struct Transition {
+    /// change of the tempo in percent, if both tempos are known. Half and double tempos count as
+    /// the same tempo
+    pub tempo_change: Option<f64>,
+    /// whether the keys are neighbours on the Camelot wheel, if both keys are known
+    pub keys_match: Option<bool>,
+}
+
+impl Transition {
+    /// returns true, if the tempo changes too much or the keys clash
+    pub fn is_risky(&self) -> bool {
+        self.tempo_change
+            .is_some_and(|change| change.abs() > MAX_TEMPO_CHANGE)
+            || self.keys_match == Some(false)
+    }
+}
+
+impl SetPlan {
+    pub fn tracks(&self) -> &[Arc<Track>] {
+        &self.tracks
+    }
+
+    pub fn push(&mut self, track: Arc<Track>) {
+        self.tracks.push(track);
+    }
+
+    pub fn remove(&mut self, i: usize) -> Option<Arc<Track>> {
+        (i < self.tracks.len()).then(|| self.tracks.remove(i))
+    }
+
+    /// moves the track at `i` one position up or down. Returns its new position
+    pub fn shift(&mut self, i: usize, down: bool) -> usize {
+        let j = if down { i + 1 } else { i.wrapping_sub(1) };
+        if i < self.tracks.len() && j < self.tracks.len() {
+            self.tracks.swap(i, j);
+            j
+        } else {
+            i
+        }
+    }
+
+    /// returns the time in seconds, at which each track starts, if the set is played from start
+    /// to end, followed by the length of the whole set
+    pub fn start_times(&self) -> Vec<f64> {
+        let mut times = vec![0.];
+        let mut time = 0.;
+        for track in self.tracks.iter() {
+            time += duration(track);
+            times.push(time);
+        }
+        times
+    }
+
+    /// returns the transition into the track at `i` from the track before it
+    pub fn transition(&self, i: usize) -> Option<Transition> {
+        let from = self
+            .tracks
+            .get(i.checked_sub(1)?)?
+            .meta
+            .read()
+            .unwrap()
+            .clone();
+        let to = self.tracks.get(i)?.meta.read().unwrap().clone();
+        Some(transition(&from, &to))
+    }
+
+    /// writes the set as an extended M3U playlist. Returns the number of tracks
+    pub fn export_m3u(&self, path: &Path) -> io::Result<usize> {
+        let mut content = String::from("#EXTM3U\n");
+        for track in self.tracks.iter() {
+            let meta = track.meta.read().unwrap();
+            let name = match (meta.artist.is_empty(), meta.title.is_empty()) {
+                (false, false) => format!("{} - {}", meta.artist, meta.title),
+                (true, false) => meta.title.clone(),
+                _ => track.file_name.clone(),
+            };
+            content.push_str(&format!(
+                "#EXTINF:{:.0},{}\n{}\n",
+                duration(track),
+                name,
+                track.file_path
+            ));
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, content)?;
+        Ok(self.tracks.len())
+    }
+}
+
+/// returns how well a track mixes into another one
+pub fn transition(from: &TrackMeta, to: &TrackMeta) -> Transition {
+    let tempo_change = (from.bpm > 0 && to.bpm > 0).then(|| {
+        let mut ratio = to.bpm as f64 / from.bpm as f64;
+        while ratio < 0.75 {
+            ratio *= 2.;
+        }
+        while ratio >= 1.5 {
+            ratio /= 2.;
+        }
+        (ratio - 1.) * 100.
+    });
+    let keys_match = camelot(from.effective_key())
+        .zip(camelot(to.effective_key()))
+        .map(|((n, minor), (m, other_minor))| {
+            let distance = (n as i32 - m as i32).rem_euclid(12);
+            if minor == other_minor {
+                distance <= 1 || distance == 11
+            } else {
+                distance == 0
+            }
+        });
+    Transition {
+        tempo_change,
+        keys_match,
+    }
+}
+
+/// picks the track to follow `from`, when nothing is planned after it. Candidates with
+/// matching keys come first, then the ones with the smallest tempo change. Tracks, that
+/// `played_recently` is true for, are only picked, if nothing else is left
+pub fn pick_next<'a, I>(
+    from: &Track,
+    candidates: I,
+    played_recently: impl Fn(&Track) -> bool,
+) -> Option<Arc<Track>>
+where
+    I: IntoIterator<Item = &'a Arc<Track>>,
+{
+    let from_meta = from.meta.read().unwrap().clone();
+    candidates
+        .into_iter()
+        .filter(|track| track.unsupported.is_none() && track.file_path != from.file_path)
+        .map(|track| {
+            let transition = transition(&from_meta, &track.meta.read().unwrap());
+            let key_rank = match transition.keys_match {
+                Some(true) => 0,
+                None => 1,
+                Some(false) => 2,
+            };
+            // unknown tempos are ranked after all known ones
+            let tempo_change = transition.tempo_change.map_or(f64::MAX, f64::abs);
+            (played_recently(track), key_rank, tempo_change, track)
+        })
+        .min_by(|a, b| {
+            (a.0, a.1)
+                .cmp(&(b.0, b.1))
+                .then(a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal))
+        })
+        .map(|(_, _, _, track)| Arc::clone(track))
+}
+
+/// returns the length of a track in seconds, or 0, if it is unknown
+fn duration(track: &Track) -> f64 {
+    match (track.codec_params.n_frames, track.codec_params.sample_rate) {
+        (Some(n_frames), Some(sample_rate)) => n_frames as f64 / sample_rate as f64,
+        _ => 0.,
+    }
+}
+
+/// returns the position of a key on the Camelot wheel and whether it is minor. Accepts Camelot
+/// notation like `8A` as well as key names like `Am`, `F#`, `Eb minor` or `Dbmaj`
+pub fn camelot(key: &str) -> Option<(u8, bool)> {
+    let key = key.trim();
+    // Camelot notation
+    if let Some(number) = key.strip_suffix(['A', 'B', 'a', 'b']) {
+        if let Ok(number @ 1..=12) = number.parse::<u8>() {
+            return Some((number, key.ends_with(['A', 'a'])));
+        }
+    }
+    let mut chars = key.chars();
+    let pitch_class: i32 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (pitch_class, rest) = match rest.chars().next() {
+        Some(c @ ('#' | '♯')) => (pitch_class + 1, &rest[c.len_utf8()..]),
+        Some(c @ ('b' | '♭')) => (pitch_class - 1, &rest[c.len_utf8()..]),
+        _ => (pitch_class, rest),
+    };
+    let minor = match rest.trim().to_ascii_lowercase().as_str() {
+        "" | "maj" | "major" => false,
+        "m" | "min" | "minor" => true,
+        _ => return None,
+    };
+    // minor keys share their position with the major key three semitones up
+    let major = if minor { pitch_class + 3 } else { pitch_class };
+    // every fifth up is one step clockwise, C major is at 8
+    let number = (major * 7 + 7).rem_euclid(12) + 1;
+    Some((number as u8, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(bpm: u32, key: &str) -> TrackMeta {
+        TrackMeta {
+            bpm,
+            key: String::from(key),
+            ..TrackMeta::default()
+        }
+    }
+
+    #[test]
+    fn reads_camelot_notation_and_key_names() {
+        assert_eq!(camelot("8A"), Some((8, true)));
+        assert_eq!(camelot("12b"), Some((12, false)));
+        assert_eq!(camelot("13A"), None);
+        assert_eq!(camelot("Am"), Some((8, true)));
+        assert_eq!(camelot("C"), Some((8, false)));
+        assert_eq!(camelot("F#m"), Some((11, true)));
+        assert_eq!(camelot("Eb minor"), Some((2, true)));
+        assert_eq!(camelot("Dbmaj"), Some((3, false)));
+        assert_eq!(camelot("Cb"), Some((1, false)));
+        assert_eq!(camelot("H"), None);
+        assert_eq!(camelot("Am7"), None);
+    }
+
+    #[test]
+    fn matches_neighbouring_keys_on_the_wheel() {
+        let keys_match = |from: &str, to: &str| transition(&meta(0, from), &meta(0, to)).keys_match;
+        assert_eq!(keys_match("8A", "8A"), Some(true));
+        assert_eq!(keys_match("8A", "9A"), Some(true));
+        assert_eq!(keys_match("12A", "1A"), Some(true));
+        assert_eq!(keys_match("8A", "8B"), Some(true));
+        assert_eq!(keys_match("8A", "10A"), Some(false));
+        assert_eq!(keys_match("8A", "9B"), Some(false));
+        assert_eq!(keys_match("8A", ""), None);
+    }
+
+    #[test]
+    fn counts_half_and_double_tempos_as_the_same_tempo() {
+        let tempo_change =
+            |from: u32, to: u32| transition(&meta(from, ""), &meta(to, "")).tempo_change;
+        assert!((tempo_change(120, 126).unwrap() - 5.).abs() < 1e-9);
+        assert!((tempo_change(128, 64).unwrap()).abs() < 1e-9);
+        assert!((tempo_change(120, 250).unwrap() - 25. / 6.).abs() < 1e-9);
+        assert_eq!(tempo_change(0, 120), None);
+    }
+
+    #[test]
+    fn flags_large_tempo_changes_and_key_clashes_as_risky() {
+        assert!(!transition(&meta(120, "8A"), &meta(126, "9A")).is_risky());
+        assert!(transition(&meta(120, "8A"), &meta(130, "8A")).is_risky());
+        assert!(transition(&meta(120, "8A"), &meta(120, "3A")).is_risky());
+        assert!(!transition(&meta(0, ""), &meta(120, "3A")).is_risky());
+    }
+}
@@ -1,6 +1,8 @@
 use bounded_vec_deque::BoundedVecDeque;
+use std::collections::BTreeMap;
 use std::hash::Hash;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, RwLock};
 use symphonia::core::formats::Track as SymphoniaTrack;
 use symphonia::core::meta::{Metadata, StandardTagKey, Tag, Value};
@@ -11,8 +13,12 @@ use symphonia::core::codecs::CodecParameters;
 use crate::core::{
     analyzer::{PreviewSample, PREVIEW_SAMPLE_RATE},
     player::TimeMarker,
+    probe::FormatInfo,
+    tags::TagField,
 };
 
+use super::cue_sheet::CueSheet;
+
 //------------------------------------------------------------------//
 //                              Track                               //
 //------------------------------------------------------------------//
@@ -29,23 +35,59 @@ pub struct Track {
     pub codec_params: CodecParameters,
     /// downsampled version of decoded frames for preview
     preview_buffer: RwLock<Vec<PreviewSample>>,
+    /// number of preview samples per second, that the preview buffer was analyzed with
+    pub preview_sample_rate: u32,
     /// list of memory cue markers
     pub mem_cues: Mutex<BoundedVecDeque<TimeMarker>>,
+    /// track listing, if the file is a mix with an accompanying .cue file
+    pub cue_sheet: Option<CueSheet>,
+    /// ids of the audio tracks in the file's container, starting with the default one
+    pub audio_track_ids: Vec<u32>,
+    /// container and codec of the file
+    pub format: Option<FormatInfo>,
+    /// reason, why the file can't be played
+    pub unsupported: Option<String>,
+    /// whether the drive, that the file lives on, is mounted
+    available: AtomicBool,
 }
 
 impl Track {
     pub fn new(file_path: String, codec_params: CodecParameters) -> Self {
         let file_name = String::from(Path::new(&file_path).file_name().unwrap().to_str().unwrap());
+        let cue_sheet = CueSheet::for_file(&file_path);
         Self {
             meta: RwLock::new(TrackMeta::default()),
             preview_buffer: RwLock::new(vec![]),
+            preview_sample_rate: PREVIEW_SAMPLE_RATE,
             file_path,
             file_name,
             mem_cues: Mutex::new(BoundedVecDeque::new(10)),
             codec_params,
+            cue_sheet,
+            audio_track_ids: vec![],
+            format: None,
+            unsupported: None,
+            available: AtomicBool::new(true),
         }
     }
 
+    /// creates a track for a file, that can't be played
+    pub fn unsupported(file_path: String, reason: String) -> Self {
+        let mut track = Track::new(file_path, CodecParameters::new());
+        track.unsupported = Some(reason);
+        track
+    }
+
+    /// returns false, if the drive, that the file lives on, was removed
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::Relaxed)
+    }
+
+    /// marks the file as reachable or not, when its drive is mounted or removed
+    pub fn set_available(&self, available: bool) {
+        self.available.store(available, Ordering::Relaxed);
+    }
+
     pub fn change_bpm(&self, bpm: u32) {
         let mut meta = self.meta.write().unwrap();
         meta.bpm = bpm;
@@ -56,6 +98,20 @@ impl Track {
         self.mem_cues.lock().unwrap().push_back(tm);
     }
 
+    /// remove the latest memory cue at the given position
+    pub fn remove_mem_cue(&self, tm: &TimeMarker) {
+        let mut mem_cues = self.mem_cues.lock().unwrap();
+        let index = mem_cues
+            .iter()
+            .enumerate()
+            .filter(|(_, cue)| *cue == tm)
+            .map(|(i, _)| i)
+            .last();
+        if let Some(index) = index {
+            mem_cues.remove(index);
+        }
+    }
+
     /// append preview samples to preview buffer
     pub fn append_preview_samples(&self, preview_samples: &mut Vec<PreviewSample>) {
         // Hack: this sets the frames per packet
@@ -65,6 +121,21 @@ impl Track {
         self.preview_buffer.write().unwrap().append(preview_samples);
     }
 
+    /// replaces the preview buffer, e.g. with imported analysis results
+    pub fn set_preview_samples(&self, preview_samples: Vec<PreviewSample>) {
+        *self.preview_buffer.write().unwrap() = preview_samples;
+    }
+
+    /// returns the number of preview samples analyzed so far
+    pub fn preview_len(&self) -> usize {
+        self.preview_buffer.read().unwrap().len()
+    }
+
+    /// returns a copy of all preview samples analyzed so far
+    pub fn preview_samples(&self) -> Vec<PreviewSample> {
+        self.preview_buffer.read().unwrap().clone()
+    }
+
     /// returns the analysis progress for this track.
     /// The result is a number between 0 and 100 (%).
     pub fn progress(&self) -> Option<u8> {
@@ -75,82 +146,53 @@ impl Track {
             (self.codec_params.n_frames, self.codec_params.sample_rate)
         {
             if preview_buffer.len() > 0 {
-                res = (preview_buffer.len() * (sample_rate / PREVIEW_SAMPLE_RATE) as usize) as f64
+                res = (preview_buffer.len() * (sample_rate / self.preview_sample_rate) as usize)
+                    as f64
                     / (n_frames as f64)
             }
         }
         Some((res * 100.).ceil() as u8)
     }
 
-    /// returns the preview samples for a given player position and target screen size
-    /// the playhead position shifts the player position by [-target_size/2, target_size/2] relative in the buffer
-    pub fn live_preview(
-        &self,
-        target_size: usize,
-        target_sample_rate: u32,
-        playhead_position: &TimeMarker,
-    ) -> Vec<PreviewSample> {
-        let conversion_factor = PREVIEW_SAMPLE_RATE as f32 / target_sample_rate as f32;
-        let mut unscaled = vec![];
-        let preview_buffer = self.preview_buffer.read().unwrap();
-        // let buffer_len_in_millis = (preview_buffer.len() / PREVIEW_SAMPLE_RATE as usize) * 1000;
-        let mut curr_time_in_seconds = playhead_position.get_time_in_seconds();
-        let player_pos = (curr_time_in_seconds * PREVIEW_SAMPLE_RATE as f64) as usize;
-        let player_pos = player_pos as f32 / conversion_factor;
-        // check if enough sampes exist for target resolution
-        let diff = player_pos as isize - (target_size / 2) as isize;
-        if diff >= 0 {
-            // if yes return buffer content
-            let l = (player_pos as f32 - (target_size as f32 / 2.0)) as usize;
-            let l = (l as f32 * conversion_factor).ceil() as usize;
-            let r = (player_pos as f32 + (target_size as f32 / 2.0)) as usize;
-            let r = (r as f32 * conversion_factor).ceil() as usize;
-            let r = std::cmp::min(r, preview_buffer.len());
-            if l < r {
-                unscaled = preview_buffer[l..r].to_owned();
-            }
-        } else {
-            let diff = diff.abs() as usize;
-            let mut padding: Vec<PreviewSample> =
-                vec![0.0 as f32; diff * conversion_factor.floor() as usize]
-                    .iter()
-                    .map(|s| PreviewSample {
-                        mids: *s,
-                        lows: *s,
-                        highs: *s,
-                    })
-                    .collect();
-            if preview_buffer.len() > 0 {
-                padding.extend(
-                    preview_buffer[0..(target_size - diff) * conversion_factor.floor() as usize]
-                        .to_vec(),
-                );
-            };
-            unscaled = padding.to_owned()
-        }
-        let scaled = unscaled
-            .into_iter()
-            .chunks(conversion_factor.floor() as usize)
-            .into_iter()
+    /// returns the number of preview samples, that one line of the live preview covers at the
+    /// given resolution in lines per second
+    pub fn samples_per_line(&self, target_sample_rate: u32) -> usize {
+        (self.preview_sample_rate / target_sample_rate.max(1)).max(1) as usize
+    }
+
+    /// returns the lines of the live preview for the whole track at the given resolution in
+    /// lines per second. They don't depend on the playhead, which only selects the lines, that
+    /// are drawn
+    pub fn live_preview_bins(&self, target_sample_rate: u32) -> Vec<PreviewSample> {
+        let samples_per_line = self.samples_per_line(target_sample_rate);
+        self.preview_buffer
+            .read()
+            .unwrap()
+            .chunks(samples_per_line)
             .map(|chunk| {
-                let sum: PreviewSample = chunk.into_iter().sum::<PreviewSample>();
-                let conversion_rate = conversion_factor.floor();
-                let lows = sum.lows / conversion_rate;
-                let mids = sum.mids / conversion_rate;
-                let highs = sum.highs / conversion_rate;
-                PreviewSample { lows, mids, highs }
+                let sum: PreviewSample = chunk.iter().cloned().sum::<PreviewSample>();
+                let n = samples_per_line as f32;
+                PreviewSample {
+                    lows: sum.lows / n,
+                    mids: sum.mids / n,
+                    highs: sum.highs / n,
+                }
             })
-            .collect();
-        scaled
+            .collect()
     }
 
     /// computes a downsampled version of the full track that fits in a buffer of target_size
     pub fn preview(&self, target_size: usize) -> Vec<PreviewSample> {
         let preview_buffer = self.preview_buffer.read().unwrap().clone();
         let conversion_rate =
-            PREVIEW_SAMPLE_RATE as f64 / self.codec_params.sample_rate.unwrap() as f64;
-        let chunks =
-            (self.codec_params.n_frames.unwrap() as f64 * conversion_rate) / target_size as f64;
+            self.preview_sample_rate as f64 / self.codec_params.sample_rate.unwrap() as f64;
+        // short tracks in wide terminals have fewer preview samples than lines
+        // without a known length, the preview covers the part, that was analyzed so far
+        let preview_len = match self.codec_params.n_frames {
+            Some(n_frames) => n_frames as f64 * conversion_rate,
+            None => preview_buffer.len() as f64,
+        };
+        let chunks = (preview_len / target_size as f64).max(1.);
         // let preview_buffer =
         //     Analyzer::downsample_to_preview(&preview_buffer, num_channles, target_size);
         let preview_buffer = preview_buffer
@@ -194,25 +236,81 @@ impl Hash for Track {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TrackMeta {
     pub artist: String,
     pub title: String,
     pub album: String,
+    pub genre: String,
+    /// record label
+    pub label: String,
+    /// year of release, 0 if it is unknown
+    pub year: u32,
     pub bpm: u32,
+    /// musical key as tagged, e.g. `Am` or `8A`. Empty, if it is unknown
+    pub key: String,
+    /// key, that the user picked instead of the tagged one. Empty, if it wasn't overridden
+    pub user_key: String,
+    pub comment: String,
+    /// results of analyzer plugins, keyed by `<plugin>.<key>`
+    pub extra: BTreeMap<String, String>,
 }
 impl Default for TrackMeta {
     fn default() -> Self {
         Self {
             bpm: 0,
+            key: String::from(""),
+            user_key: String::from(""),
+            comment: String::from(""),
             artist: String::from(""),
             title: String::from(""),
             album: String::from(""),
+            genre: String::from(""),
+            label: String::from(""),
+            year: 0,
+            extra: BTreeMap::new(),
         }
     }
 }
 
 impl TrackMeta {
+    /// returns the key, that the track is shown and mixed with. The user's key takes precedence
+    /// over the tagged one
+    pub fn effective_key(&self) -> &str {
+        if self.user_key.is_empty() {
+            &self.key
+        } else {
+            &self.user_key
+        }
+    }
+
+    /// returns the value of a tag, that can be written back to the file. The BPM is empty, if it
+    /// is unknown
+    pub fn tag(&self, field: TagField) -> String {
+        match field {
+            TagField::Artist => self.artist.clone(),
+            TagField::Title => self.title.clone(),
+            TagField::Album => self.album.clone(),
+            TagField::Bpm if self.bpm == 0 => String::new(),
+            TagField::Bpm => self.bpm.to_string(),
+            TagField::Key => self.key.clone(),
+            TagField::Comment => self.comment.clone(),
+        }
+    }
+
+    /// takes over a tag, that was written to the file
+    pub fn set_tag(&mut self, field: TagField, value: &str) {
+        let value = String::from(value.trim());
+        match field {
+            TagField::Artist => self.artist = value,
+            TagField::Title => self.title = value,
+            TagField::Album => self.album = value,
+            TagField::Bpm => self.bpm = value.parse().unwrap_or(0),
+            TagField::Key => self.key = value,
+            TagField::Comment => self.comment = value,
+        }
+    }
+
     pub fn parse_from(&mut self, tags: Vec<Tag>) {
         for tag in tags {
             // println!("{}", tag);
@@ -228,12 +326,51 @@ impl TrackMeta {
                             self.artist = artist;
                         }
                     }
+                    StandardTagKey::Album => {
+                        if let Value::String(album) = tag.value {
+                            self.album = album;
+                        }
+                    }
+                    StandardTagKey::Genre => {
+                        if let Value::String(genre) = tag.value {
+                            self.genre = genre;
+                        }
+                    }
+                    StandardTagKey::Label => {
+                        if let Value::String(label) = tag.value {
+                            self.label = label;
+                        }
+                    }
+                    // the original release date takes precedence over reissues
+                    StandardTagKey::Date | StandardTagKey::ReleaseDate if self.year != 0 => {}
+                    StandardTagKey::Date
+                    | StandardTagKey::ReleaseDate
+                    | StandardTagKey::OriginalDate => {
+                        let year = match &tag.value {
+                            Value::String(date) => date.get(..4).and_then(|y| y.parse().ok()),
+                            Value::UnsignedInt(year) => Some(*year as u32),
+                            _ => None,
+                        };
+                        if let Some(year) = year {
+                            self.year = year;
+                        }
+                    }
+                    StandardTagKey::Comment => {
+                        if let Value::String(comment) = tag.value {
+                            self.comment = comment;
+                        }
+                    }
                     StandardTagKey::Bpm => {
                         if let Value::UnsignedInt(bpm) = tag.value {
                             self.bpm = bpm as u32;
                         }
                     }
-                    _ => break,
+                    _ => {}
+                }
+            } else if ["TKEY", "INITIALKEY", "KEY"].contains(&tag.key.to_uppercase().as_str()) {
+                // there is no standard key for the musical key
+                if let Value::String(key) = tag.value {
+                    self.key = key;
                 }
             }
         }
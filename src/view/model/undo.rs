@@ -0,0 +1,76 @@
+use std::{fmt, sync::Arc};
+
+use symphonia::core::units::TimeStamp;
+
+use crate::core::player::TimeMarker;
+use crate::view::i18n::tr_args;
+
+use super::track::{Track, TrackMeta};
+
+//------------------------------------------------------------------//
+//                            UndoStack                             //
+//------------------------------------------------------------------//
+
+/// Maximum number of edits, that can be undone
+const UNDO_LIMIT: usize = 100;
+
+/// An edit of the library or a track, that can be reverted
+#[derive(Clone, Debug)]
+pub enum Edit {
+    /// a track was removed from the library
+    RemoveTrack(Arc<Track>),
+    /// a bookmark was added to a track
+    AddBookmark {
+        file_path: String,
+        ts: TimeStamp,
+        name: String,
+    },
+    /// a memory cue was added to a track
+    AddMemCue(Arc<Track>, TimeMarker),
+    /// meta data of tracks was replaced. Holds the meta data before and after the change
+    ChangeMeta(Vec<(Arc<Track>, TrackMeta, TrackMeta)>),
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Edit::RemoveTrack(track) => tr_args("edit.remove_track", &[&track.file_name]),
+            Edit::AddBookmark { name, .. } => tr_args("edit.add_bookmark", &[name]),
+            Edit::AddMemCue(track, _) => tr_args("edit.add_mem_cue", &[&track.file_name]),
+            Edit::ChangeMeta(changes) => tr_args("edit.change_meta", &[&changes.len()]),
+        };
+        f.write_str(&text)
+    }
+}
+
+/// Keeps the history of edits. Undone edits can be redone, until a new edit is made.
+#[derive(Default)]
+pub struct UndoStack {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl UndoStack {
+    /// records a new edit
+    pub fn push(&mut self, edit: Edit) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(edit);
+        self.redo.clear();
+    }
+
+    /// returns the latest edit, that should be reverted
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop()?;
+        self.redo.push(edit.clone());
+        Some(edit)
+    }
+
+    /// returns the latest undone edit, that should be applied again
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.undo.push(edit.clone());
+        Some(edit)
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    io,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use tui::{backend::TestBackend, Terminal};
+
+use crate::core::{config::Config, sink::NullSink};
+
+use super::app::{App, Channels};
+
+//------------------------------------------------------------------//
+//                            Simulation                            //
+//------------------------------------------------------------------//
+
+/// Time to wait between two ticks, while waiting for a condition, so the workers can make
+/// progress
+const TICK_WAIT: Duration = Duration::from_millis(5);
+
+/// Runs the app without a terminal and an audio device, so it can be tested end to end. The
+/// app draws into an in-memory terminal and plays on a NullSink, that never blocks. Input is
+/// only handled and frames are only drawn, when `tick` is called
+pub struct Simulation {
+    app: App,
+    terminal: Terminal<TestBackend>,
+    channels: Channels,
+    sink: NullSink,
+    /// messages, that the status line showed, in order
+    events: Vec<String>,
+}
+
+impl Simulation {
+    /// starts the app with the given settings on a terminal of the given size
+    pub fn new(config: Config, width: u16, height: u16) -> io::Result<Self> {
+        let mut app = App::new(config);
+        let sink = NullSink::default();
+        let channels = app.start(sink.opener());
+        Ok(Self {
+            app,
+            terminal: Terminal::new(TestBackend::new(width, height))?,
+            channels,
+            sink,
+            events: vec![],
+        })
+    }
+
+    /// handles the pending input and worker events once and draws a frame
+    pub async fn tick(&mut self) -> io::Result<()> {
+        self.app.tick(&mut self.channels).await;
+        let app = &mut self.app;
+        self.terminal.draw(|f| app.render(f))?;
+        let latest_event = self.app.latest_event();
+        let shown = self.events.last().map(String::as_str);
+        if !latest_event.is_empty() && shown != Some(latest_event) {
+            self.events.push(String::from(latest_event));
+        }
+        Ok(())
+    }
+
+    /// ticks, until `accept` returns true, and returns false, if that doesn't happen in time
+    pub async fn tick_until(
+        &mut self,
+        timeout: Duration,
+        accept: impl Fn(&Self) -> bool,
+    ) -> io::Result<bool> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            self.tick().await?;
+            if accept(self) {
+                return Ok(true);
+            }
+            sleep(TICK_WAIT);
+        }
+        Ok(false)
+    }
+
+    /// presses a key, which is handled on the next tick
+    pub fn press(&mut self, code: KeyCode) {
+        self.app
+            .simulate_input(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+    }
+
+    /// returns the lines of the last drawn frame
+    pub fn screen(&self) -> Vec<String> {
+        let buffer = self.terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .map(|line| line.iter().map(|cell| cell.symbol.as_str()).collect())
+            .collect()
+    }
+
+    /// returns whether a line of the last drawn frame contains the text
+    pub fn shows(&self, text: &str) -> bool {
+        self.screen().iter().any(|line| line.contains(text))
+    }
+
+    /// returns the messages, that the status line showed, in order
+    pub fn events(&self) -> &[String] {
+        &self.events
+    }
+
+    /// returns the sink, that the player writes to
+    pub fn sink(&self) -> &NullSink {
+        &self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        config::{self, LibraryRoot},
+        test_signal::TestSignal,
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    #[tokio::test]
+    async fn loads_seeks_and_plays_a_test_tone() {
+        // keep the session, caches and stores of the test away from the user's
+        let dir = config::use_test_data_dir().join("simulation");
+        let library = dir.join("library");
+        TestSignal::Sweep.write(&library).unwrap();
+        let config = Config {
+            libraries: vec![LibraryRoot::new("library", library)],
+            ..Config::default()
+        };
+        let mut sim = Simulation::new(config, 120, 40).unwrap();
+
+        // the tone is listed, once it was analyzed
+        assert!(sim
+            .tick_until(TIMEOUT, |sim| sim.shows("sweep.wav"))
+            .await
+            .unwrap());
+        sim.press(KeyCode::Enter);
+        assert!(sim
+            .tick_until(TIMEOUT, |sim| sim.shows("sweep.wav  0:00 / -1:00"))
+            .await
+            .unwrap());
+
+        // skip forward by 20 seconds
+        sim.press(KeyCode::Char('l'));
+        assert!(sim
+            .tick_until(TIMEOUT, |sim| sim.shows("sweep.wav  0:20 / -0:40"))
+            .await
+            .unwrap());
+        assert_eq!(sim.sink().written(), 0);
+
+        // the null sink takes the rest of the track right away, which are stereo 32 bit float
+        // frames from 20 seconds to the end
+        sim.press(KeyCode::Char(' '));
+        assert!(sim
+            .tick_until(TIMEOUT, |sim| sim.sink().written() / 8 == 40 * 44100)
+            .await
+            .unwrap());
+        assert!(sim
+            .tick_until(TIMEOUT, |sim| sim.shows("/ -0:00"))
+            .await
+            .unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let events = sim.events();
+        let loaded = events.iter().position(|ev| ev.starts_with("Loaded "));
+        let played = events.iter().position(|ev| ev == "TogglePlay");
+        let warned = events
+            .iter()
+            .position(|ev| ev.starts_with("Track ends in "));
+        assert!(loaded < played && played < warned, "{:?}", events);
+        assert!(loaded.is_some());
+    }
+}
@@ -7,13 +7,21 @@ use tui::widgets::{
     Block, Widget,
 };
 
-use crate::core::player::TimeMarker;
+use crate::core::{analyzer::PreviewSample, player::TimeMarker};
 use crate::view::model::track::Track;
 
 pub struct LivePreviewWidget<'a> {
     track: &'a Track,
     player_pos: &'a Option<TimeMarker>,
     zoom_level: u32,
+    /// number of canvas columns, that each line of the waveform covers
+    downscale: usize,
+    /// waveform lines of the whole track, if they were computed before
+    bins: Option<&'a [PreviewSample]>,
+    /// color of the mids
+    color: Color,
+    /// position of the first beat and length of a beat in seconds, if a beat grid is drawn
+    grid: Option<(f64, f64)>,
 }
 
 pub enum WaveFormLayer {
@@ -28,6 +36,81 @@ impl<'a> LivePreviewWidget<'a> {
             player_pos,
             track,
             zoom_level,
+            downscale: 1,
+            bins: None,
+            color: Color::Gray,
+            grid: None,
+        }
+    }
+
+    /// draws the mids in the given color instead of gray
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// draws a line on every beat at a constant tempo, starting at `anchor` seconds. Transients,
+    /// that drift away from the lines, show that the tempo of the track varies
+    pub fn grid(mut self, anchor: f64, bpm: u32) -> Self {
+        if bpm > 0 {
+            self.grid = Some((anchor, 60. / bpm as f64));
+        }
+        self
+    }
+
+    /// draws waveform lines, that were computed by `bins` for an earlier frame
+    pub fn cached_bins(mut self, bins: &'a [PreviewSample]) -> Self {
+        self.bins = Some(bins);
+        self
+    }
+
+    /// returns the number of waveform lines per second
+    fn lines_per_second(&self) -> u32 {
+        (self.zoom_level / self.downscale as u32).max(1)
+    }
+
+    /// computes the waveform lines of the whole track at the zoom level. They stay valid, while
+    /// the playhead moves, because only the lines around it are drawn
+    pub fn bins(&self) -> Vec<PreviewSample> {
+        self.track.live_preview_bins(self.lines_per_second())
+    }
+
+    /// draws the waveform with fewer lines, which is faster on slow terminals
+    pub fn downscale(mut self, downscale: usize) -> Self {
+        self.downscale = downscale.max(1);
+        self
+    }
+
+    /// returns the number of seconds, that a canvas unit covers
+    fn seconds_per_unit(&self) -> f64 {
+        let samples_per_line = self.track.samples_per_line(self.lines_per_second()) as f64;
+        samples_per_line / self.track.preview_sample_rate as f64 / self.downscale as f64
+    }
+
+    /// draws the beat grid around the playhead
+    fn draw_grid(&self, ctx: &mut Context, x_max: usize, y_max: usize) {
+        let (pos, (anchor, beat_len)) = match (self.player_pos, self.grid) {
+            (Some(pos), Some(grid)) => (pos.get_time_in_seconds(), grid),
+            _ => return,
+        };
+        let seconds_per_unit = self.seconds_per_unit();
+        let half_width = x_max as f64 * seconds_per_unit;
+        let first = ((pos - half_width - anchor) / beat_len).ceil() as i64;
+        let last = ((pos + half_width - anchor) / beat_len).floor() as i64;
+        for beat in first..=last {
+            let x = (anchor + beat as f64 * beat_len - pos) / seconds_per_unit;
+            ctx.draw(&Line {
+                x1: x,
+                x2: x,
+                y1: -(y_max as f64),
+                y2: y_max as f64,
+                // every bar stands out
+                color: if beat.rem_euclid(4) == 0 {
+                    Color::Yellow
+                } else {
+                    Color::DarkGray
+                },
+            });
         }
     }
 
@@ -35,18 +118,25 @@ impl<'a> LivePreviewWidget<'a> {
         &self,
         ctx: &mut Context,
         layer: WaveFormLayer,
+        bins: &[PreviewSample],
         target_size: usize,
         y_max: usize,
     ) {
-        if let Some(player_pos) = self.player_pos {
-            for (i, sample) in self
-                .track
-                .live_preview(target_size, self.zoom_level, player_pos)
-                .iter()
-                .take(target_size)
-                .enumerate()
-            {
-                let x = (-((target_size / 2) as i32) + i as i32) as f64;
+        if let Some(pos) = self.player_pos {
+            let lines = (target_size / self.downscale) as isize;
+            // line under the playhead, which is drawn in the center
+            let samples_per_line = self.track.samples_per_line(self.lines_per_second()) as f64;
+            let center = (pos.get_time_in_seconds() * self.track.preview_sample_rate as f64
+                / samples_per_line) as isize;
+            for i in 0..lines {
+                let sample = match usize::try_from(center - lines / 2 + i)
+                    .ok()
+                    .and_then(|line| bins.get(line))
+                {
+                    Some(sample) => sample,
+                    None => continue,
+                };
+                let x = ((i - lines / 2) * self.downscale as isize) as f64;
                 let y = match layer {
                     WaveFormLayer::Lows => sample.lows,
                     WaveFormLayer::Mids => sample.mids,
@@ -55,7 +145,7 @@ impl<'a> LivePreviewWidget<'a> {
                 let y = (y * (y_max as f32)) as f64;
                 let color = match layer {
                     WaveFormLayer::Lows => Color::LightRed,
-                    WaveFormLayer::Mids => Color::Gray,
+                    WaveFormLayer::Mids => self.color,
                     WaveFormLayer::Highs => Color::White,
                 };
                 ctx.draw(&Line {
@@ -78,6 +168,14 @@ impl<'a> Widget for LivePreviewWidget<'a> {
         let y_max = area.height as usize;
         let playhead_offset_from_center = 0;
         let target_size = x_max * 2;
+        let computed;
+        let bins = match self.bins {
+            Some(bins) => bins,
+            None => {
+                computed = self.bins();
+                &computed
+            }
+        };
         // println!("x:({},{}), y:({}{})", x_min, x_max, y_min, y_max);
         // println!("preview_buf_len: {}", preview_buf.len());
         let canvas = Canvas::default()
@@ -85,6 +183,8 @@ impl<'a> Widget for LivePreviewWidget<'a> {
             .x_bounds([-(x_max as f64), x_max as f64])
             .y_bounds([-(y_max as f64), y_max as f64])
             .paint(|ctx| {
+                // the waveform is drawn over the grid, so transients can be compared with it
+                self.draw_grid(ctx, x_max, y_max);
                 // playhead
                 ctx.draw(&Line {
                     x1: -(playhead_offset_from_center as f64),
@@ -93,9 +193,9 @@ impl<'a> Widget for LivePreviewWidget<'a> {
                     y2: y_max as f64,
                     color: Color::Red,
                 });
-                self.draw_waveform(ctx, WaveFormLayer::Lows, target_size, y_max);
-                self.draw_waveform(ctx, WaveFormLayer::Mids, target_size, y_max);
-                // self.draw_waveform(ctx, WaveFormLayer::Highs, target_size, y_max);
+                self.draw_waveform(ctx, WaveFormLayer::Lows, bins, target_size, y_max);
+                self.draw_waveform(ctx, WaveFormLayer::Mids, bins, target_size, y_max);
+                // self.draw_waveform(ctx, WaveFormLayer::Highs, bins, target_size, y_max);
             });
         canvas.render(area, buf);
     }
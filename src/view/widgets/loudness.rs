@@ -0,0 +1,79 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Borders, Widget},
+};
+
+use crate::view::i18n::tr;
+
+/// Lowest loudness in LUFS, that is shown on the meter
+const FLOOR: f64 = -40.;
+/// Highest loudness in LUFS, that is shown on the meter
+const CEILING: f64 = 0.;
+
+/// A Widget for showing the short-term loudness as a bar, with the integrated loudness and a
+/// line at the target loudness
+pub struct LoudnessWidget {
+    short_term: Option<f64>,
+    integrated: Option<f64>,
+    target: f64,
+}
+
+impl LoudnessWidget {
+    pub fn new(short_term: Option<f64>, integrated: Option<f64>, target: f64) -> Self {
+        Self {
+            short_term,
+            integrated,
+            target,
+        }
+    }
+}
+
+impl Widget for LoudnessWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .title(tr("pane.loudness"))
+            .borders(Borders::TOP);
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if inner.height < 2 || inner.width == 0 {
+            return;
+        }
+        // maps a loudness to a column of the bar
+        let column = |lufs: f64| {
+            let ratio = ((lufs - FLOOR) / (CEILING - FLOOR)).clamp(0., 1.);
+            inner.left() + (ratio * (inner.width - 1) as f64).round() as u16
+        };
+        let format = |lufs: Option<f64>| match lufs {
+            Some(lufs) => format!("{:.1}", lufs),
+            None => String::from("-"),
+        };
+        let text = format!(
+            "S {}  I {}  LUFS",
+            format(self.short_term),
+            format(self.integrated)
+        );
+        buf.set_stringn(
+            inner.left(),
+            inner.top(),
+            text,
+            inner.width as usize,
+            Style::default().fg(Color::White),
+        );
+        let y = inner.top() + 1;
+        if let Some(short_term) = self.short_term.filter(|lufs| *lufs > FLOOR) {
+            let color = if short_term > self.target {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            for x in inner.left()..=column(short_term) {
+                buf.get_mut(x, y).set_char('█').set_fg(color);
+            }
+        }
+        buf.get_mut(column(self.target), y)
+            .set_char('|')
+            .set_fg(Color::Yellow);
+    }
+}
@@ -1,4 +1,7 @@
 pub mod live_preview;
+pub mod loudness;
 pub mod popup;
 pub mod preview;
+pub mod prompt;
+pub mod sampler;
 pub mod track_table;
@@ -1,7 +1,7 @@
 use tui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
-    widgets::{Clear, Widget},
+    widgets::{Clear, StatefulWidget, Widget},
 };
 
 pub struct PopupWidget<T: Widget> {
@@ -54,3 +54,15 @@ impl<T: Widget> Widget for PopupWidget<T> {
         self.widget.render(popup_area, buf);
     }
 }
+
+impl<T: Widget + StatefulWidget> StatefulWidget for PopupWidget<T> {
+    type State = T::State;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let popup_area = self.centered_rect(self.percent_x, self.percent_y, area);
+        // clear background of target area
+        Clear.render(popup_area, buf);
+        // draw the stateful child widget on popup area
+        StatefulWidget::render(self.widget, popup_area, buf, state);
+    }
+}
@@ -6,11 +6,20 @@ use tui::{
     },
 };
 
-use crate::{core::player::TimeMarker, view::model::track::Track};
+use crate::{
+    core::{analyzer::PreviewSample, player::TimeMarker},
+    view::model::{bookmarks::Bookmark, track::Track},
+};
 
+/// An overview of the whole track, that doubles as a minimap of the markers in the track
 pub struct PreviewWidget<'a> {
     track: &'a Track,
     player_position: &'a Option<TimeMarker>,
+    bookmarks: &'a [Bookmark],
+    repeat: (Option<&'a TimeMarker>, Option<&'a TimeMarker>),
+    /// waveform of the whole track, if it was computed before
+    bins: Option<&'a [PreviewSample]>,
+    color: Color,
 }
 
 impl<'a> PreviewWidget<'a> {
@@ -18,26 +27,69 @@ impl<'a> PreviewWidget<'a> {
         Self {
             track,
             player_position,
+            bookmarks: &[],
+            repeat: (None, None),
+            bins: None,
+            color: Color::Gray,
         }
     }
+
+    /// draws the waveform in the given color instead of gray
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// draws a waveform, that was computed by `bins` for an earlier frame
+    pub fn cached_bins(mut self, bins: &'a [PreviewSample]) -> Self {
+        self.bins = Some(bins);
+        self
+    }
+
+    /// computes the waveform of the whole track, that fits into an area of the given width
+    pub fn bins(&self, width: u16) -> Vec<PreviewSample> {
+        self.track.preview(width as usize * 2)
+    }
+
+    /// shows the bookmarks of the track
+    pub fn bookmarks(mut self, bookmarks: &'a [Bookmark]) -> Self {
+        self.bookmarks = bookmarks;
+        self
+    }
+
+    /// shows the A-B repeat points as brackets
+    pub fn repeat(mut self, a: Option<&'a TimeMarker>, b: Option<&'a TimeMarker>) -> Self {
+        self.repeat = (a, b);
+        self
+    }
 }
 
 impl<'a> Widget for PreviewWidget<'a> {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
         let x_max = area.width as usize;
         let y_max = area.height as usize;
-        let preview_buffer = &self.track.preview(x_max * 2);
+        let computed;
+        let preview_buffer = match self.bins {
+            Some(bins) => bins,
+            None => {
+                computed = self.bins(area.width);
+                &computed
+            }
+        };
+        // maps the progress within the track to the x axis
+        let to_x = |progress: f64| (progress * x_max as f64 * 2.0).floor() - x_max as f64;
+        let y_max = y_max as f64;
 
         let canvas = Canvas::default()
             .block(Block::default())
             .x_bounds([-(x_max as f64), x_max as f64])
-            .y_bounds([-(y_max as f64), y_max as f64])
+            .y_bounds([-y_max, y_max])
             .paint(|ctx| {
                 //
                 for (i, sample) in preview_buffer.iter().take((x_max * 2) as usize).enumerate() {
                     //
                     let x = (-(x_max as i16) + i as i16) as f64;
-                    let y = (sample.lows * (y_max as f32)) as f64;
+                    let y = sample.lows as f64 * y_max;
                     // let y = 1. * 20.;
                     // // clip the signal if too hight
                     // let y = if y > (y_max as f64) { y_max as f64 } else { y };
@@ -46,36 +98,101 @@ impl<'a> Widget for PreviewWidget<'a> {
                         x2: x,
                         y1: y,
                         y2: -y,
-                        color: Color::Gray,
+                        color: self.color,
                     });
                 }
                 ctx.layer();
 
-                if let Some(player_position) = self.player_position {
-                    let relative_pos = player_position.get_progress();
-                    let x = relative_pos * x_max as f64 * 2.0;
-                    let x = x.floor() as isize - x_max as isize;
+                // sections of a mix are marked by ticks at the top
+                let duration =
+                    TimeMarker::new(self.track.codec_params.clone()).get_duration_in_seconds();
+                if let (Some(cue_sheet), Some(duration)) = (&self.track.cue_sheet, duration) {
+                    for cue_track in cue_sheet.tracks.iter() {
+                        let x = to_x(cue_track.start / duration);
+                        ctx.draw(&Line {
+                            x1: x,
+                            x2: x,
+                            y1: y_max,
+                            y2: y_max / 2.,
+                            color: Color::Blue,
+                        });
+                    }
+                }
+                // markers of tracks with unknown length have no place on the overview
+                let bookmarks = self.bookmarks.iter().filter_map(|bookmark| {
+                    TimeMarker::from_ts(bookmark.ts, self.track.codec_params.clone()).get_progress()
+                });
+                for progress in bookmarks {
+                    let x = to_x(progress);
                     ctx.draw(&Line {
-                        x1: x as f64,
-                        x2: x as f64,
-                        y1: y_max as f64,
-                        y2: -(y_max as f64),
-                        color: Color::Red,
-                    })
+                        x1: x,
+                        x2: x,
+                        y1: y_max,
+                        y2: -y_max,
+                        color: Color::Yellow,
+                    });
+                }
+                // the A-B repeat is drawn as a pair of brackets
+                for (marker, direction) in [(self.repeat.0, 1.), (self.repeat.1, -1.)] {
+                    if let Some(progress) = marker.and_then(|marker| marker.get_progress()) {
+                        let x = to_x(progress);
+                        ctx.draw(&Line {
+                            x1: x,
+                            x2: x,
+                            y1: y_max,
+                            y2: -y_max,
+                            color: Color::Cyan,
+                        });
+                        for y in [y_max, -y_max] {
+                            ctx.draw(&Line {
+                                x1: x,
+                                x2: x + direction * 2.,
+                                y1: y,
+                                y2: y,
+                                color: Color::Cyan,
+                            });
+                        }
+                    }
                 }
-                for marker in &(*self.track.mem_cues.lock().unwrap()) {
-                    let relative_pos = marker.get_progress();
-                    let x = relative_pos * x_max as f64 * 2.0;
-                    let x = x.floor() as isize - x_max as isize;
+                let mem_cues = self.track.mem_cues.lock().unwrap();
+                for progress in mem_cues.iter().filter_map(|marker| marker.get_progress()) {
+                    let x = to_x(progress);
                     ctx.draw(&Line {
-                        x1: x as f64,
-                        x2: x as f64,
-                        y1: y_max as f64,
-                        y2: -(y_max as f64),
+                        x1: x,
+                        x2: x,
+                        y1: y_max,
+                        y2: -y_max,
                         color: Color::Green,
                     });
                 }
+                if let Some(progress) = self
+                    .player_position
+                    .as_ref()
+                    .and_then(|pos| pos.get_progress())
+                {
+                    let x = to_x(progress);
+                    ctx.draw(&Line {
+                        x1: x,
+                        x2: x,
+                        y1: y_max,
+                        y2: -y_max,
+                        color: Color::Red,
+                    })
+                }
             });
         canvas.render(area, buf);
     }
 }
+
+/// returns the color of a waveform with the given spectral balance. Gray is neutral, bass-heavy
+/// tracks get warmer and bright tracks cooler
+pub fn balance_color(balance: f32) -> Color {
+    let balance = balance.clamp(-1., 1.);
+    let (r, g, b) = if balance < 0. {
+        (255., 140., 60.)
+    } else {
+        (90., 170., 255.)
+    };
+    let mix = |neutral: f32, tint: f32| (neutral + (tint - neutral) * balance.abs()) as u8;
+    Color::Rgb(mix(170., r), mix(170., g), mix(170., b))
+}
@@ -0,0 +1,152 @@
+use crossterm::event::KeyCode;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+};
+
+use super::popup::PopupWidget;
+use crate::view::i18n::tr;
+
+//------------------------------------------------------------------//
+//                              Prompt                              //
+//------------------------------------------------------------------//
+
+/// What a prompt asks the user for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PromptInput {
+    /// yes or no
+    Confirm,
+    /// any non-empty text
+    Text,
+    /// any text, also an empty one
+    OptionalText,
+    /// a positive number
+    Number,
+}
+
+/// State of a prompt after a key was handled
+#[derive(Clone, Debug, PartialEq)]
+pub enum PromptResult {
+    /// the user isn't done yet
+    Pending,
+    Cancelled,
+    Confirmed,
+    Text(String),
+    Number(f64),
+}
+
+/// A modal question, that is answered with the keyboard while the app keeps running. `T` is the
+/// request of whoever opened the prompt, so it knows what to do with the answer
+pub struct Prompt<T> {
+    title: String,
+    message: String,
+    input: PromptInput,
+    /// typed text
+    text: String,
+    pub request: T,
+}
+
+impl<T> Prompt<T> {
+    /// creates a yes/no question
+    pub fn confirm(title: &str, message: &str, request: T) -> Self {
+        Self::new(title, message, PromptInput::Confirm, "", request)
+    }
+
+    /// creates a prompt for text, that starts out with `initial`
+    pub fn text(title: &str, message: &str, initial: &str, request: T) -> Self {
+        Self::new(title, message, PromptInput::Text, initial, request)
+    }
+
+    /// creates a prompt for text, that may also be left empty
+    pub fn optional_text(title: &str, message: &str, initial: &str, request: T) -> Self {
+        Self::new(title, message, PromptInput::OptionalText, initial, request)
+    }
+
+    /// creates a prompt for a positive number, that starts out with `initial`
+    pub fn number(title: &str, message: &str, initial: &str, request: T) -> Self {
+        Self::new(title, message, PromptInput::Number, initial, request)
+    }
+
+    fn new(title: &str, message: &str, input: PromptInput, initial: &str, request: T) -> Self {
+        Self {
+            title: String::from(title),
+            message: String::from(message),
+            input,
+            text: String::from(initial),
+            request,
+        }
+    }
+
+    /// edits the answer. Enter submits it, Esc cancels the prompt. Invalid answers can't be
+    /// submitted
+    pub fn handle_key(&mut self, key: KeyCode) -> PromptResult {
+        match (self.input, key) {
+            (_, KeyCode::Esc) => PromptResult::Cancelled,
+            (PromptInput::Confirm, KeyCode::Char('y') | KeyCode::Enter) => PromptResult::Confirmed,
+            (PromptInput::Confirm, KeyCode::Char('n')) => PromptResult::Cancelled,
+            (PromptInput::Confirm, _) => PromptResult::Pending,
+            (_, KeyCode::Backspace) => {
+                self.text.pop();
+                PromptResult::Pending
+            }
+            (PromptInput::Text | PromptInput::OptionalText, KeyCode::Char(c)) => {
+                self.text.push(c);
+                PromptResult::Pending
+            }
+            (PromptInput::Number, KeyCode::Char(c @ '0'..='9')) => {
+                self.text.push(c);
+                PromptResult::Pending
+            }
+            (PromptInput::Number, KeyCode::Char('.')) if !self.text.contains('.') => {
+                self.text.push('.');
+                PromptResult::Pending
+            }
+            (PromptInput::Text, KeyCode::Enter) if !self.text.trim().is_empty() => {
+                PromptResult::Text(self.text.trim().to_string())
+            }
+            (PromptInput::OptionalText, KeyCode::Enter) => {
+                PromptResult::Text(self.text.trim().to_string())
+            }
+            (PromptInput::Number, KeyCode::Enter) => match self.text.parse::<f64>() {
+                Ok(number) if number > 0. => PromptResult::Number(number),
+                _ => PromptResult::Pending,
+            },
+            _ => PromptResult::Pending,
+        }
+    }
+}
+
+//------------------------------------------------------------------//
+//                           PromptWidget                           //
+//------------------------------------------------------------------//
+
+/// Draws a prompt as a popup over the app
+pub struct PromptWidget<'a, T> {
+    prompt: &'a Prompt<T>,
+}
+
+impl<'a, T> PromptWidget<'a, T> {
+    pub fn new(prompt: &'a Prompt<T>) -> Self {
+        Self { prompt }
+    }
+}
+
+impl<'a, T> Widget for PromptWidget<'a, T> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let input = match self.prompt.input {
+            PromptInput::Confirm => tr("prompt.yes_no"),
+            PromptInput::Text | PromptInput::OptionalText | PromptInput::Number => {
+                format!("> {}_", self.prompt.text)
+            }
+        };
+        let paragraph = Paragraph::new(format!("{}\n\n{}", self.prompt.message, input))
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .title(self.prompt.title.as_str())
+                    .borders(Borders::ALL),
+            );
+        PopupWidget::new(paragraph, 40, 20).render(area, buf);
+    }
+}
@@ -0,0 +1,52 @@
+use tui::{
+    style::{Color, Style},
+    widgets::{Block, Borders, List, ListItem, Widget},
+};
+
+use crate::{core::sampler::SamplerSlot, view::i18n::tr};
+
+/// A Widget for showing the sampler slots with their loaded samples and gain
+pub struct SamplerWidget<'a> {
+    slots: &'a [Option<SamplerSlot>],
+    selected_slot: usize,
+}
+
+impl<'a> SamplerWidget<'a> {
+    pub fn new(slots: &'a [Option<SamplerSlot>], selected_slot: usize) -> Self {
+        Self {
+            slots,
+            selected_slot,
+        }
+    }
+}
+
+impl<'a> Widget for SamplerWidget<'a> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let items: Vec<ListItem> = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                // || F<n> || sample name || gain
+                let text = match slot {
+                    Some(slot) => format!("F{} {} {:.0}%", i + 1, slot.name, slot.gain * 100.),
+                    None => format!("F{} -", i + 1),
+                };
+                let style = if i == self.selected_slot {
+                    Style::default().fg(Color::Black).bg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(text).style(style)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(tr("pane.sampler"))
+                    .borders(Borders::TOP),
+            )
+            .style(Style::default().fg(Color::White));
+        list.render(area, buf);
+    }
+}
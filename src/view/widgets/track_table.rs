@@ -1,62 +1,125 @@
 
-use std::sync::Arc;
+use std::{collections::HashSet, path::{Path, PathBuf}, sync::Arc};
 
 use indexmap::IndexSet;
 use tui::{layout::Constraint, style::{Color, Modifier, Style}, widgets::{Block, Borders, Cell, Row, Table, Widget}};
 
-use crate::view::model::track::Track;
+use crate::{core::columns::{Column, ColumnSetting}, view::{i18n::{self, tr}, model::{play_stats::{self, PlayStatsStore}, track::Track}}};
 
 //------------------------------------------------------------------//
 //                         TrackTableWidget                         //
 //------------------------------------------------------------------//
 
+/// returns the header of a column
+pub fn column_title(column: Column) -> String {
+    i18n::tr(&format!("column.{}", column.name()))
+}
+
 /// A Widget for visualizing a TrackList in table form
 pub struct TrackTableWidget<'a> {
     tracks: &'a TrackList,
     focused: bool,
+    /// play counts and last-played times
+    play_stats: Option<&'a PlayStatsStore>,
+    /// shown columns in their order
+    columns: &'a [ColumnSetting],
 }
 impl<'a> TrackTableWidget<'a> {
-    pub fn new(tracks: &'a TrackList, focused: bool) -> Self {
-        Self { tracks, focused }
+    pub fn new(tracks: &'a TrackList, focused: bool, columns: &'a [ColumnSetting]) -> Self {
+        Self { tracks, focused, play_stats: None, columns }
+    }
+
+    /// show how often and when the tracks were played
+    pub fn play_stats(mut self, play_stats: &'a PlayStatsStore) -> Self {
+        self.play_stats = Some(play_stats);
+        self
     }
 
     /// returns a TUI Row objed, with specific styling based on, whether the row is focused or an
     /// alternating row (every other row)
     fn get_row(&self, track:&Track, focused: bool)-> Row{
-        // || filename || analyzed_percentage
-        //
-        // if progress could be computed return it in formatted form, else return string "NaN"
-        let progress_string = track.progress().map_or(String::from("Nan"),|progress|{ format!("{}%", progress) });
         let meta = track.meta.read().unwrap();
-        let bpm = format!("{}",meta.bpm);
-        let title = meta.title.clone();
-        let artist = meta.artist.clone();
-        let style = if focused {Style::default().fg(Color::Black).bg(Color::DarkGray)}else {Style::default()};
-        Row::new(vec![Cell::from(track.file_name.to_string()), Cell::from(title), Cell::from(artist) , Cell::from(progress_string), Cell::from(bpm)]).style(style)
+        let stats = self.play_stats.and_then(|play_stats| play_stats.get(&track.file_path));
+        let cells: Vec<Cell> = self.columns.iter().map(|setting| match setting.column {
+            Column::FileName => track.file_name.to_string(),
+            Column::Title => meta.title.clone(),
+            Column::Artist => meta.artist.clone(),
+            Column::Album => meta.album.clone(),
+            // if progress could be computed return it in formatted form, else return string "NaN"
+            Column::Analysis => match &track.unsupported {
+                Some(_) => tr("track.unsupported"),
+                None if !track.is_available() => tr("track.unavailable"),
+                None => track.progress().map_or(String::from("Nan"),|progress|{ format!("{}%", progress) }),
+            },
+            Column::Bpm => format!("{}",meta.bpm),
+            Column::Key => meta.effective_key().to_string(),
+            // show the reason for unsupported files instead of their format
+            Column::Format => match (&track.unsupported, &track.format) {
+                (Some(reason), _) => reason.clone(),
+                (None, Some(format)) => format.to_string(),
+                (None, None) => String::new(),
+            },
+            Column::Plays => stats.map_or(0, |stats| stats.count).to_string(),
+            // days since the last play
+            Column::LastPlayed => match stats.map(|stats| play_stats::now().saturating_sub(stats.last_played) / (24 * 60 * 60)) {
+                None => tr("track.never"),
+                Some(0) => tr("track.today"),
+                Some(days) => i18n::tr_args("track.days_ago", &[&days]),
+            },
+        }).map(Cell::from).collect();
+        let style = if focused {Style::default().fg(Color::Black).bg(Color::DarkGray)} else if self.tracks.marked.contains(&track.file_path) {Style::default().fg(Color::Cyan)} else if track.unsupported.is_some() {Style::default().fg(Color::Red)} else if !track.is_available() {Style::default().fg(Color::DarkGray)} else {Style::default()};
+        Row::new(cells).style(style)
+    }
+
+    /// returns a TUI Row object for the header of a library group
+    fn get_group_row(&self, name: &str, collapsed: bool, num_tracks: usize, focused: bool) -> Row {
+        let marker = if collapsed { '▸' } else { '▾' };
+        let style = if focused {Style::default().fg(Color::Black).bg(Color::DarkGray)} else {Style::default().add_modifier(Modifier::BOLD)};
+        Row::new(vec![Cell::from(format!("{} {} ({})", marker, name, num_tracks))]).style(style)
     }
 
     fn get_header(&self) -> Row {
-        // || filename || analyzed_percentage
         let style = Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
-        Row::new(vec!["File Name","Title","Artist", "Analysis", "BPM"]).bottom_margin(0).style(style).bottom_margin(1)
+        Row::new(self.columns.iter().map(|setting| column_title(setting.column))).bottom_margin(0).style(style).bottom_margin(1)
+    }
+
+    /// returns the widths of the columns. Columns without a width share the rest of the space
+    fn get_widths(&self) -> Vec<Constraint> {
+        let num_auto = self.columns.iter().filter(|setting| setting.width.is_none()).count().max(1);
+        self.columns.iter().map(|setting| match setting.width {
+            Some(width) => Constraint::Length(width),
+            None => Constraint::Percentage(100 / num_auto as u16),
+        }).collect()
     }
 }
 impl<'a> Widget for TrackTableWidget<'a> {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
         let header = self.get_header();
-        let num_colums = 5 as usize;
-        let auto_widths = vec![Constraint::Percentage(100/num_colums as u16);num_colums];
-        let rows: Vec<Row> = self
-            .tracks
-            .values()
+        let widths = self.get_widths();
+        // only build the rows, that fit below the border and the header. The visible window
+        // pages along with the focus, so huge libraries render as fast as small ones
+        let height = (area.height as usize).saturating_sub(3).max(1);
+        let entries = self.tracks.entries();
+        let focused_entry = self.tracks.focused_entry(&entries);
+        let offset = focused_entry.map_or(0, |i| i / height * height);
+        let rows: Vec<Row> = entries
             .iter()
-            .map(|track| {
-                let focused = self.tracks.get_focused().map(|f| f == *track).unwrap_or(false);
-                self.get_row(&track, focused)
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .map(|(i, entry)| {
+                let focused = focused_entry == Some(i);
+                match *entry {
+                    Entry::Group(g, num_tracks) => {
+                        let group = &self.tracks.groups[g];
+                        self.get_group_row(&group.name, group.collapsed, num_tracks, focused)
+                    }
+                    Entry::Track(t) => self.get_row(&self.tracks.tracks[t], focused),
+                }
             })
             .collect();
         let table = Table::new(rows)
-            .block(Block::default().title("Files").borders(Borders::TOP)).header(header).style(Style::default().fg(Color::White)).widths(&auto_widths).column_spacing(1);
+            .block(Block::default().title(tr("pane.files")).borders(Borders::TOP)).header(header).style(Style::default().fg(Color::White)).widths(&widths).column_spacing(1);
         table.render(area, buf);
     }
 }
@@ -70,6 +133,29 @@ pub struct TrackList {
     tracks: IndexSet<Arc<Track>>,
     focused_track: Option<usize>,
     loaded_track: Option<usize>,
+    /// library roots, whose tracks are listed together. Without groups the list is flat
+    groups: Vec<TrackGroup>,
+    /// file paths of tracks, that are left out of the list
+    hidden: HashSet<String>,
+    /// file paths of tracks, that are marked for editing them together
+    marked: HashSet<String>,
+}
+
+/// The tracks below a library root
+struct TrackGroup {
+    name: String,
+    path: PathBuf,
+    /// whether the tracks of the group are hidden
+    collapsed: bool,
+}
+
+/// A row of the track list
+#[derive(Clone, Copy, PartialEq)]
+pub enum Entry {
+    /// header of a group with its index and number of tracks
+    Group(usize, usize),
+    /// a track with its index
+    Track(usize),
 }
 
 impl TrackList {
@@ -78,12 +164,48 @@ impl TrackList {
         &self.tracks
     }
 
-    // pub fn sort(&mut self) {
-    //     self.tracks.sort();
-    // }
+    /// sorts the tracks by a key. Focus and the loaded track stay on the same tracks
+    pub fn sort_by_key<K: Ord, F: FnMut(&Track) -> K>(&mut self, mut f: F) {
+        let focused = self.get_focused();
+        let loaded = self.get_loaded();
+        self.tracks.sort_by(|a, b| f(a).cmp(&f(b)));
+        self.focused_track = focused.and_then(|track| self.tracks.get_index_of(&track));
+        self.loaded_track = loaded.and_then(|track| self.tracks.get_index_of(&track));
+    }
+
+    /// leaves the tracks with the given file paths out of the list. Focus moves to the first
+    /// listed track, if the focused one gets hidden
+    pub fn set_hidden(&mut self, hidden: HashSet<String>) {
+        self.hidden = hidden;
+        if self.get_focused().map_or(false, |track| self.hidden.contains(&track.file_path)) {
+            self.focused_track = self.tracks.iter().position(|track| !self.hidden.contains(&track.file_path)).or(self.focused_track);
+        }
+    }
 
-    pub fn sort_by(&mut self){
-        todo!();
+    /// leaves one more track out of the list
+    pub fn hide(&mut self, file_path: &str) {
+        let mut hidden = std::mem::take(&mut self.hidden);
+        hidden.insert(String::from(file_path));
+        self.set_hidden(hidden);
+    }
+
+    /// marks the focused track, or unmarks it, if it is marked. Returns whether it is marked now
+    pub fn toggle_mark(&mut self) -> bool {
+        let file_path = match self.get_focused() { Some(track) => track.file_path.clone(), None => return false };
+        if !self.marked.remove(&file_path) {
+            self.marked.insert(file_path);
+            return true;
+        }
+        false
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// returns the marked tracks in the order of the list
+    pub fn marked(&self) -> Vec<Arc<Track>> {
+        self.tracks.iter().filter(|track| self.marked.contains(&track.file_path)).cloned().collect()
     }
 
     /// returns the currently focused track
@@ -100,37 +222,91 @@ impl TrackList {
             Arc::clone(track) })
     }
 
-    /// focus next track and return it
+    /// focus next visible track and return it
     pub fn focus_next(&mut self) -> Option<Arc<Track>> {
-        let new_index = self.focused_track.map(|i| {
-            // check bounds
-            if self.tracks.is_empty() {
-                i
-            } else {
-                if i < self.tracks.len() - 1 {
-                    i + 1
-                } else {
-                    // wrap list
-                    0
-                }
-            }
-        });
-        // check bounds
-        self.focused_track = new_index;
+        let entries = self.entries();
+        if let Some(pos) = self.focused_entry(&entries) {
+            let visible = |entry: &Entry| match entry { Entry::Track(t) => Some(*t), _ => None };
+            // wrap list
+            self.focused_track = entries[pos + 1..].iter().chain(entries[..=pos].iter()).find_map(visible).or(self.focused_track);
+        }
         self.get_focused()
     }
 
-    /// focus previous track and return it
+    /// focus previous visible track and return it
     pub fn focus_previous(&mut self) -> Option<Arc<Track>> {
-        let new_index = self.focused_track.map(|i|
-               // check bound 
-               if i > 0 { i - 1 } else { 
-                   // wrap list
-                   self.tracks.len() - 1
-               }
-           );
-        // check bounds
-        self.focused_track = new_index;
+        let entries = self.entries();
+        if let Some(pos) = self.focused_entry(&entries) {
+            let visible = |entry: &Entry| match entry { Entry::Track(t) => Some(*t), _ => None };
+            // wrap list
+            self.focused_track = entries[..pos].iter().rev().chain(entries[pos..].iter().rev()).find_map(visible).or(self.focused_track);
+        }
+        self.get_focused()
+    }
+
+    /// lists the tracks below the given library roots in groups
+    pub fn set_groups(&mut self, roots: Vec<(String, PathBuf)>) {
+        self.groups = roots.into_iter().map(|(name, path)| TrackGroup { name, path, collapsed: false }).collect();
+    }
+
+    /// collapses or expands the group of the focused track
+    pub fn toggle_group(&mut self) {
+        if let Some(g) = self.get_focused().and_then(|track| self.group_of(&track)) {
+            self.groups[g].collapsed = !self.groups[g].collapsed;
+        }
+    }
+
+    /// returns the tracks of the focused track's group, or all tracks, if the list is flat
+    pub fn focused_group(&self) -> Vec<Arc<Track>> {
+        let group = self.get_focused().and_then(|track| self.group_of(&track));
+        self.tracks.iter().filter(|track| self.groups.is_empty() || self.group_of(track) == group).cloned().collect()
+    }
+
+    /// returns the index of the group, that a track belongs to. Nested roots take precedence
+    fn group_of(&self, track: &Track) -> Option<usize> {
+        self.groups.iter().enumerate()
+            .filter(|(_, group)| Path::new(&track.file_path).starts_with(&group.path))
+            .max_by_key(|(_, group)| group.path.as_os_str().len())
+            .map(|(g, _)| g)
+    }
+
+    /// returns the rows of the list. Hidden tracks and tracks of collapsed groups are left out,
+    /// tracks outside of all groups come last
+    pub fn entries(&self) -> Vec<Entry> {
+        let listed = self.tracks.iter().enumerate().filter(|(_, track)| !self.hidden.contains(&track.file_path));
+        if self.groups.is_empty() {
+            return listed.map(|(t, _)| Entry::Track(t)).collect();
+        }
+        let mut members = vec![vec![]; self.groups.len() + 1];
+        for (t, track) in listed {
+            members[self.group_of(track).unwrap_or(self.groups.len())].push(t);
+        }
+        let mut entries = vec![];
+        for (g, tracks) in members.into_iter().enumerate() {
+            if let Some(group) = self.groups.get(g) {
+                entries.push(Entry::Group(g, tracks.len()));
+                if group.collapsed {
+                    continue;
+                }
+            }
+            entries.extend(tracks.into_iter().map(Entry::Track));
+        }
+        entries
+    }
+
+    /// returns the row of the focused track, or of its group header, if the group is collapsed
+    pub fn focused_entry(&self, entries: &[Entry]) -> Option<usize> {
+        let focused = self.focused_track?;
+        entries.iter().position(|entry| *entry == Entry::Track(focused)).or_else(|| {
+            let g = self.group_of(&self.tracks[focused])?;
+            entries.iter().position(|entry| matches!(entry, Entry::Group(group, _) if *group == g))
+        })
+    }
+
+    /// focus the track with the given file path and return it
+    pub fn focus(&mut self, file_path: &str) -> Option<Arc<Track>> {
+        let index = self.tracks.iter().position(|t| t.file_path == file_path)?;
+        self.focused_track = Some(index);
         self.get_focused()
     }
 
@@ -140,12 +316,30 @@ impl TrackList {
         self.get_focused()
     }
 
-    /// push a single track to the list
+    /// push a single track to the list. A track with the same file path gets replaced
     pub fn insert(&mut self, track: Arc<Track>) {
         if self.tracks.len() == 0 {
             self.focused_track = Some(0);
         }
-        self.tracks.insert(Arc::clone(&track));
+        self.tracks.replace(Arc::clone(&track));
+    }
+
+    /// remove all tracks, that are located at the given path or below it
+    pub fn remove(&mut self, path: &Path) {
+        while let Some(i) = self.tracks.iter().position(|t| Path::new(&t.file_path).starts_with(path)) {
+            self.tracks.shift_remove_index(i);
+            self.loaded_track = match self.loaded_track {
+                Some(l) if l == i => None,
+                Some(l) if l > i => Some(l - 1),
+                l => l,
+            };
+            self.focused_track = match self.focused_track {
+                _ if self.tracks.is_empty() => None,
+                // keep focus on the same track, or on the last one
+                Some(f) if f > i || f == self.tracks.len() => Some(f - 1),
+                f => f,
+            };
+        }
     }
 }
 
@@ -155,6 +349,9 @@ impl<'a> Default for TrackList {
             tracks: IndexSet::default(),
             focused_track: None,
             loaded_track: None,
+            groups: vec![],
+            hidden: HashSet::new(),
+            marked: HashSet::new(),
         }
     }
 }